@@ -0,0 +1,929 @@
+use crate::game::{Difficulty, GameMode, GameSettings, GeneratorKind};
+use crate::governor::GovernorSettings;
+use crate::listener::{Bind, ListenerSettings};
+use crate::net_client::{Limits, Timeouts};
+use crate::runtime::RuntimeSettings;
+use crate::send_queue::QueueSettings;
+use crate::shutdown::ShutdownSettings;
+use protocol::types::{Chat, ChatObj, Prim};
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use structopt::StructOpt;
+use tokio::sync::watch;
+use tracing::*;
+
+const DEFAULT_PATH: &str = "mcsrs.toml";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "mcsrs", about = "A minecraft server")]
+pub struct Args {
+    /// Path of the configuration file [default: mcsrs.toml]
+    #[structopt(short, long, parse(from_os_str))]
+    pub config: Option<PathBuf>,
+    /// Import settings from a vanilla server.properties file, they take precedence over the configuration file
+    #[structopt(long, parse(from_os_str))]
+    pub import_properties: Option<PathBuf>,
+    /// Listen on these addresses instead of the configured listeners, e.g. `[::]:25565` or `unix:/run/mcsrs.sock`
+    #[structopt(short, long)]
+    pub bind: Vec<Bind>,
+    #[structopt(long)]
+    pub motd: Option<String>,
+    #[structopt(long)]
+    pub max_players: Option<i32>,
+    /// Packets of at least this size get compressed, a negative value disables compression
+    #[structopt(long, allow_hyphen_values = true)]
+    pub compression_threshold: Option<i32>,
+    /// zlib compression level between 0 and 9
+    #[structopt(long)]
+    pub compression_level: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub compression: CompressionConfig,
+    pub listeners: Vec<ListenerSettings>,
+    pub limits: Limits,
+    pub send_queue: QueueSettings,
+    pub timeouts: Timeouts,
+    pub governor: GovernorSettings,
+    pub rcon: RconConfig,
+    pub runtime: RuntimeSettings,
+    pub shutdown: ShutdownSettings,
+    pub game: GameSettings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Either a plain string or a chat component
+    pub motd: Chat,
+    pub max_players: i32,
+    /// Path of a 64x64 PNG shown in the server list
+    pub favicon: Option<PathBuf>,
+    /// Contents of `favicon` as data url, filled in when the configuration is loaded
+    #[serde(skip)]
+    pub favicon_data: Option<String>,
+    /// Only allow players listed in `whitelisted` to join, checked when a player logs in and for
+    /// everyone online when the configuration is reloaded
+    pub whitelist: bool,
+    pub whitelisted: Vec<String>,
+    /// Game mode of joining players
+    pub gamemode: GameMode,
+    pub difficulty: Difficulty,
+    pub hardcore: bool,
+    /// Radius in chunks around a player which is sent to the client
+    pub view_distance: u8,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RconConfig {
+    pub enabled: bool,
+    pub bind: SocketAddr,
+    pub password: String,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionConfig {
+    /// Packets of at least this size get compressed, a negative value disables compression
+    pub threshold: i32,
+    pub level: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            compression: CompressionConfig::default(),
+            listeners: vec![ListenerSettings::default()],
+            limits: Limits::default(),
+            send_queue: QueueSettings::default(),
+            timeouts: Timeouts::default(),
+            governor: GovernorSettings::default(),
+            rcon: RconConfig::default(),
+            runtime: RuntimeSettings::default(),
+            shutdown: ShutdownSettings::default(),
+            game: GameSettings::default(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            motd: Chat::Obj(ChatObj {
+                text: Some("MCSRS!".to_string()),
+                bold: Some(true),
+                color: Some("gold".to_string()),
+                ..Default::default()
+            }),
+            max_players: 1337,
+            favicon: None,
+            favicon_data: None,
+            whitelist: false,
+            whitelisted: vec![],
+            gamemode: GameMode::Survival,
+            difficulty: Difficulty::Easy,
+            hardcore: false,
+            view_distance: 10,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn is_whitelisted(&self, name: &str) -> bool {
+        !self.whitelist || self.whitelisted.iter().any(|listed| listed == name)
+    }
+}
+
+impl Default for RconConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: SocketAddr::from((Ipv4Addr::LOCALHOST, 25575)),
+            password: String::new(),
+        }
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 256,
+            level: 4,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Toml(PathBuf, toml::de::Error),
+    Properties {
+        path: PathBuf,
+        line: usize,
+        msg: String,
+    },
+    Invalid {
+        field: &'static str,
+        msg: String,
+    },
+}
+
+impl Config {
+    /// Builds the configuration from the configuration file, an optional server.properties and the
+    /// command line, in that order
+    pub fn load(args: &Args) -> Result<Self, ConfigError> {
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_PATH).exists() => Self::from_file(Path::new(DEFAULT_PATH))?,
+            None => {
+                info!("no configuration file found, using defaults");
+                Self::default()
+            }
+        };
+
+        if let Some(path) = &args.import_properties {
+            config.import_properties(path)?;
+        }
+
+        if !args.bind.is_empty() {
+            config.listeners = args
+                .bind
+                .iter()
+                .map(|bind| ListenerSettings {
+                    bind: bind.clone(),
+                    ..Default::default()
+                })
+                .collect();
+        }
+        if let Some(motd) = &args.motd {
+            config.server.motd = Chat::Primitive(Prim::Str(motd.clone()));
+        }
+        if let Some(max_players) = args.max_players {
+            config.server.max_players = max_players;
+        }
+        if let Some(threshold) = args.compression_threshold {
+            config.compression.threshold = threshold;
+        }
+        if let Some(level) = args.compression_level {
+            config.compression.level = level;
+        }
+
+        config.validate()?;
+        if let Some(path) = &config.server.favicon {
+            config.server.favicon_data = Some(load_favicon(path)?);
+        }
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let content =
+            std::fs::read_to_string(path).map_err(|err| ConfigError::Io(path.into(), err))?;
+        toml::from_str(&content).map_err(|err| ConfigError::Toml(path.into(), err))
+    }
+
+    /// Applies all settings of a vanilla server.properties file which have an equivalent here
+    pub fn import_properties(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let content =
+            std::fs::read_to_string(path).map_err(|err| ConfigError::Io(path.into(), err))?;
+        self.apply_properties(path, &content)
+    }
+
+    /// Applies the content of a server.properties file, `path` is only used in errors
+    fn apply_properties(&mut self, path: &Path, content: &str) -> Result<(), ConfigError> {
+        let mut ip = None;
+        let mut port = None;
+        for (line, (key, value)) in parse_properties(content) {
+            let invalid = |msg: &str| ConfigError::Properties {
+                path: path.into(),
+                line,
+                msg: format!("{}: {}", key, msg),
+            };
+            match key.as_str() {
+                "server-ip" if value.is_empty() => (),
+                "server-ip" => {
+                    ip = Some(
+                        value
+                            .parse::<IpAddr>()
+                            .map_err(|_| invalid("expected an ip address"))?,
+                    )
+                }
+                "server-port" => {
+                    port = Some(
+                        value
+                            .parse::<u16>()
+                            .map_err(|_| invalid("expected a port"))?,
+                    )
+                }
+                "motd" => self.server.motd = Chat::Primitive(Prim::Str(value)),
+                "max-players" => {
+                    self.server.max_players =
+                        value.parse().map_err(|_| invalid("expected a number"))?
+                }
+                "white-list" => {
+                    self.server.whitelist = value
+                        .parse()
+                        .map_err(|_| invalid("expected true or false"))?
+                }
+                "gamemode" => {
+                    self.server.gamemode =
+                        value.parse().map_err(|_| invalid("expected a game mode"))?
+                }
+                "difficulty" => {
+                    self.server.difficulty = value
+                        .parse()
+                        .map_err(|_| invalid("expected a difficulty"))?
+                }
+                "hardcore" => {
+                    self.server.hardcore = value
+                        .parse()
+                        .map_err(|_| invalid("expected true or false"))?
+                }
+                "view-distance" => {
+                    self.server.view_distance =
+                        value.parse().map_err(|_| invalid("expected a number"))?
+                }
+                "level-seed" => self.game.seed = value,
+                "level-name" => self.game.level = PathBuf::from(value),
+                "level-type" => {
+                    self.game.generator = match value.as_str() {
+                        "default" | "normal" | "minecraft:normal" => GeneratorKind::Noise,
+                        "flat" | "minecraft:flat" => GeneratorKind::Flat,
+                        _ => return Err(invalid("expected normal or flat")),
+                    }
+                }
+                "network-compression-threshold" => {
+                    self.compression.threshold =
+                        value.parse().map_err(|_| invalid("expected a number"))?
+                }
+                _ => debug!("ignoring server.properties entry; key={}", key),
+            }
+        }
+
+        if ip.is_some() || port.is_some() {
+            let addr = SocketAddr::new(
+                ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                port.unwrap_or(25565),
+            );
+            self.listeners = vec![ListenerSettings {
+                bind: Bind::Tcp(addr),
+                ..Default::default()
+            }];
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        fn check(ok: bool, field: &'static str, msg: &str) -> Result<(), ConfigError> {
+            match ok {
+                true => Ok(()),
+                false => Err(ConfigError::Invalid {
+                    field,
+                    msg: msg.to_string(),
+                }),
+            }
+        }
+
+        check(
+            self.server.max_players >= 0,
+            "server.max_players",
+            "must not be negative",
+        )?;
+        check(
+            (2..=32).contains(&self.server.view_distance),
+            "server.view_distance",
+            "must be between 2 and 32",
+        )?;
+        check(
+            self.compression.level <= 9,
+            "compression.level",
+            "must be between 0 and 9",
+        )?;
+        check(
+            !self.listeners.is_empty(),
+            "listeners",
+            "at least one listener is required",
+        )?;
+        check(
+            (1..=protocol::packets::MAX_FRAME_SIZE).contains(&self.limits.frame),
+            "limits.frame",
+            &format!(
+                "must be between 1 and {}",
+                protocol::packets::MAX_FRAME_SIZE
+            ),
+        )?;
+        for (field, limit) in [
+            ("limits.handshake", self.limits.handshake),
+            ("limits.status", self.limits.status),
+            ("limits.login", self.limits.login),
+            ("limits.decompressed", self.limits.decompressed),
+        ] {
+            check(limit > 0, field, "must be greater than 0")?;
+        }
+        for (field, timeout) in [
+            ("timeouts.handshake", self.timeouts.handshake),
+            ("timeouts.status", self.timeouts.status),
+            ("timeouts.login", self.timeouts.login),
+            ("timeouts.play_read", self.timeouts.play_read),
+            (
+                "timeouts.keep_alive_interval",
+                self.timeouts.keep_alive_interval,
+            ),
+            ("timeouts.keep_alive", self.timeouts.keep_alive),
+        ] {
+            check(!timeout.is_zero(), field, "must be greater than 0")?;
+        }
+        check(
+            self.listeners.iter().all(|listener| {
+                !listener.proxy_protocol.enabled || !listener.proxy_protocol.trusted.is_empty()
+            }),
+            "listeners.proxy_protocol.trusted",
+            "must not be empty when the PROXY protocol is enabled",
+        )?;
+        check(
+            self.governor.max_connections > 0,
+            "governor.max_connections",
+            "must be greater than 0",
+        )?;
+        check(
+            self.governor.ipv4_prefix <= 32,
+            "governor.ipv4_prefix",
+            "must be between 0 and 32",
+        )?;
+        check(
+            self.governor.ipv6_prefix <= 128,
+            "governor.ipv6_prefix",
+            "must be between 0 and 128",
+        )?;
+        check(
+            (1..=1000).contains(&self.runtime.tick_rate),
+            "runtime.tick_rate",
+            "must be between 1 and 1000",
+        )?;
+        check(
+            self.runtime.max_blocking_threads > 0,
+            "runtime.max_blocking_threads",
+            "must be greater than 0",
+        )?;
+        check(
+            self.game.max_chunks > 0,
+            "game.max_chunks",
+            "must be greater than 0",
+        )?;
+        check(
+            !self.game.save_interval.is_zero(),
+            "game.save_interval",
+            "must be greater than 0",
+        )?;
+        check(
+            !self.rcon.enabled || !self.rcon.password.is_empty(),
+            "rcon.password",
+            "must be set when rcon is enabled",
+        )?;
+
+        Ok(())
+    }
+
+    /// Resets settings which can only be applied by restarting the server to the values in
+    /// `current`, and returns the names of those which differed
+    fn keep_restart_only(&mut self, current: &Config) -> Vec<&'static str> {
+        let mut changed = vec![];
+        if self.listeners != current.listeners {
+            self.listeners = current.listeners.clone();
+            changed.push("listeners");
+        }
+        if self.rcon.enabled != current.rcon.enabled || self.rcon.bind != current.rcon.bind {
+            self.rcon.enabled = current.rcon.enabled;
+            self.rcon.bind = current.rcon.bind;
+            changed.push("rcon");
+        }
+        if self.runtime != current.runtime {
+            self.runtime = current.runtime;
+            changed.push("runtime");
+        }
+        if self.game != current.game {
+            self.game = current.game.clone();
+            changed.push("game");
+        }
+        changed
+    }
+}
+
+fn load_favicon(path: &Path) -> Result<String, ConfigError> {
+    let png = std::fs::read(path).map_err(|err| ConfigError::Io(path.into(), err))?;
+    if !png.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Err(ConfigError::Invalid {
+            field: "server.favicon",
+            msg: format!("{} is not a PNG image", path.display()),
+        });
+    }
+    Ok(format!("data:image/png;base64,{}", base64::encode(png)))
+}
+
+/// Shared view of the configuration, which is replaced whenever the configuration gets reloaded
+#[derive(Clone)]
+pub struct ConfigHandle(watch::Receiver<Arc<Config>>);
+
+impl ConfigHandle {
+    pub fn get(&self) -> Arc<Config> {
+        self.0.borrow().clone()
+    }
+
+    /// Resolves once the configuration was reloaded, or never if it can't change anymore
+    pub async fn changed(&mut self) {
+        if self.0.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Owns the configuration and re-reads it from the same sources it was first loaded from
+pub struct Reloader {
+    args: Args,
+    sender: watch::Sender<Arc<Config>>,
+    handle: ConfigHandle,
+}
+
+impl Reloader {
+    pub fn new(args: Args, config: Config) -> Self {
+        let (sender, receiver) = watch::channel(Arc::new(config));
+        Self {
+            args,
+            sender,
+            handle: ConfigHandle(receiver),
+        }
+    }
+
+    pub fn handle(&self) -> ConfigHandle {
+        self.handle.clone()
+    }
+
+    /// Loads and applies the configuration, returns the settings which changed but need a restart
+    pub fn reload(&self) -> Result<Vec<&'static str>, ConfigError> {
+        let mut config = Config::load(&self.args)?;
+        let restart = config.keep_restart_only(&self.handle.get());
+        // we hold a receiver ourselves, so sending can't fail
+        let _ = self.sender.send(Arc::new(config));
+        info!("reloaded configuration; restart_required={:?}", &restart);
+        Ok(restart)
+    }
+}
+
+/// Parses the subset of the java properties format which is used by server.properties
+fn parse_properties(content: &str) -> impl Iterator<Item = (usize, (String, String))> + '_ {
+    content
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim_start()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+        .map(|(n, line)| {
+            let split = line.find(&['=', ':'][..]).unwrap_or(line.len());
+            let key = unescape(line[..split].trim_end());
+            let value = unescape(line.get(split + 1..).unwrap_or("").trim_start());
+            (n, (key, value))
+        })
+}
+
+fn unescape(str: &str) -> String {
+    let mut out = String::with_capacity(str.len());
+    let mut chars = str.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('f') => out.push('\x0C'),
+            Some('u') => {
+                let hex = chars.by_ref().take(4).collect::<String>();
+                let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                match c {
+                    Some(c) if hex.len() == 4 => out.push(c),
+                    _ => out.push_str(&hex),
+                }
+            }
+            Some(c) => out.push(c),
+            None => (),
+        }
+    }
+    out
+}
+
+/// (De)serializes durations from strings like `500ms`, `5s` or `2m`
+pub mod duration {
+    use serde::{de::Error, Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Duration, D::Error> {
+        let str = String::deserialize(de)?;
+        parse(&str).ok_or_else(|| {
+            D::Error::custom(format!(
+                "invalid duration `{}`, expected something like `500ms`, `5s` or `2m`",
+                str
+            ))
+        })
+    }
+
+    pub fn parse(str: &str) -> Option<Duration> {
+        let split = str.find(|c: char| !c.is_ascii_digit())?;
+        let num = str[..split].parse::<u64>().ok()?;
+        Some(match str[split..].trim() {
+            "ms" => Duration::from_millis(num),
+            "s" => Duration::from_secs(num),
+            "m" => Duration::from_secs(num.checked_mul(60)?),
+            _ => return None,
+        })
+    }
+}
+
+/// Deserializes any type which implements `FromStr` from a string
+pub mod from_str {
+    use serde::{de::Error, Deserialize, Deserializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn deserialize<'de, D, T>(de: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: Display,
+    {
+        String::deserialize(de)?
+            .parse()
+            .map_err(|err| D::Error::custom(format!("{}", err)))
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "unable to read {}: {}", path.display(), err),
+            ConfigError::Toml(path, err) => write!(f, "invalid config {}: {}", path.display(), err),
+            ConfigError::Properties { path, line, msg } => {
+                write!(f, "invalid entry in {}:{}: {}", path.display(), line, msg)
+            }
+            ConfigError::Invalid { field, msg } => {
+                write!(f, "invalid value for {}: {}", field, msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// As written by a vanilla 1.17.1 server, with a few values changed
+    const VANILLA: &str = r#"#Minecraft server properties
+#Tue Oct 19 12:00:00 CEST 2021
+enable-jmx-monitoring=false
+rcon.port=25575
+level-seed=-4172144997902289642
+gamemode=creative
+enable-command-block=false
+enable-query=false
+generator-settings=
+level-name=my\ world
+motd=A Minecraft Server §cwith colors\: and \\ escapes
+query.port=25565
+pvp=true
+generate-structures=true
+difficulty=hard
+network-compression-threshold=512
+require-resource-pack=false
+max-tick-time=60000
+use-native-transport=true
+max-players=42
+online-mode=false
+enable-status=true
+allow-flight=false
+broadcast-rcon-to-ops=true
+view-distance=12
+server-ip=
+resource-pack-prompt=
+allow-nether=true
+server-port=25566
+enable-rcon=false
+sync-chunk-writes=true
+op-permission-level=4
+prevent-proxy-connections=false
+hide-online-players=false
+resource-pack=
+entity-broadcast-range-percentage=100
+simulation-distance=10
+rcon.password=
+player-idle-timeout=0
+force-gamemode=false
+rate-limit=0
+hardcore=false
+white-list=true
+broadcast-console-to-ops=true
+spawn-npcs=true
+spawn-animals=true
+snooper-enabled=true
+function-permission-level=2
+level-type=flat
+text-filtering-config=
+spawn-monsters=true
+enforce-whitelist=false
+resource-pack-sha1=
+spawn-protection=16
+max-world-size=29999984
+"#;
+
+    fn import(content: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        config.apply_properties(Path::new("server.properties"), content)?;
+        Ok(config)
+    }
+
+    #[test]
+    fn vanilla_properties() {
+        let config = import(VANILLA).unwrap();
+        let server = &config.server;
+        assert!(matches!(
+            &server.motd,
+            Chat::Primitive(Prim::Str(motd)) if motd == "A Minecraft Server §cwith colors: and \\ escapes"
+        ));
+        assert_eq!(server.max_players, 42);
+        assert!(server.whitelist);
+        assert_eq!(server.gamemode, GameMode::Creative);
+        assert_eq!(server.difficulty, Difficulty::Hard);
+        assert!(!server.hardcore);
+        assert_eq!(server.view_distance, 12);
+        assert_eq!(config.compression.threshold, 512);
+        assert_eq!(config.game.seed, "-4172144997902289642");
+        assert_eq!(config.game.level, PathBuf::from("my world"));
+        assert_eq!(config.game.generator, GeneratorKind::Flat);
+        // an empty server-ip binds to every address
+        assert_eq!(
+            config.listeners,
+            vec![ListenerSettings {
+                bind: Bind::Tcp(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 25566))),
+                ..Default::default()
+            }]
+        );
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn properties() {
+        for (content, check) in [
+            (
+                "",
+                (|config| config.listeners == Config::default().listeners) as fn(&Config) -> bool,
+            ),
+            ("gamemode=2", |config| {
+                config.server.gamemode == GameMode::Adventure
+            }),
+            ("difficulty=0", |config| {
+                config.server.difficulty == Difficulty::Peaceful
+            }),
+            ("level-type=default", |config| {
+                config.game.generator == GeneratorKind::Noise
+            }),
+            ("level-type=minecraft\\:flat", |config| {
+                config.game.generator == GeneratorKind::Flat
+            }),
+            ("network-compression-threshold=-1", |config| {
+                config.compression.threshold == -1
+            }),
+            ("  max-players = 7", |config| config.server.max_players == 7),
+            ("max-players:8", |config| config.server.max_players == 8),
+            ("# max-players=9\n! max-players=9", |config| {
+                config.server.max_players == 1337
+            }),
+            ("server-ip=::1", |config| {
+                config.listeners[0].bind == Bind::Tcp("[::1]:25565".parse().unwrap())
+            }),
+            ("server-ip=10.0.0.1\nserver-port=1", |config| {
+                config.listeners[0].bind == Bind::Tcp("10.0.0.1:1".parse().unwrap())
+            }),
+            ("unknown-key=whatever", |config| {
+                config.server.view_distance == 10
+            }),
+        ] {
+            let config = import(content).unwrap_or_else(|err| panic!("{:?}: {}", content, err));
+            assert!(check(&config), "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn invalid_properties() {
+        for (content, line) in [
+            ("server-ip=localhost", 1),
+            ("server-port=65536", 1),
+            ("server-port=", 1),
+            ("motd=hi\nmax-players=lots", 2),
+            ("white-list=yes", 1),
+            ("gamemode=4", 1),
+            ("difficulty=impossible", 1),
+            ("hardcore=1", 1),
+            ("view-distance=-", 1),
+            ("#\n\nlevel-type=amplified", 3),
+            ("network-compression-threshold=256.0", 1),
+        ] {
+            match import(content) {
+                Err(ConfigError::Properties { line: actual, .. }) => {
+                    assert_eq!(actual, line, "{:?}", content)
+                }
+                other => panic!("{:?}: {:?}", content, other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn unescape() {
+        for (escaped, unescaped) in [
+            ("plain", "plain"),
+            (r"a\tb\nc\rd\fe", "a\tb\nc\rd\x0Ce"),
+            (r"\:\=\ \#\!\\", ":= #!\\"),
+            (r"é☃", "é☃"),
+            (r"\u00", "00"),
+            (r"\uzzzz", "zzzz"),
+            (r"\q", "q"),
+            ("trailing\\", "trailing"),
+        ] {
+            assert_eq!(super::unescape(escaped), unescaped, "{:?}", escaped);
+        }
+    }
+
+    #[test]
+    fn durations() {
+        for (str, duration) in [
+            ("0ms", Some(Duration::ZERO)),
+            ("500ms", Some(Duration::from_millis(500))),
+            ("5s", Some(Duration::from_secs(5))),
+            ("2m", Some(Duration::from_secs(120))),
+            ("10 s", Some(Duration::from_secs(10))),
+            ("", None),
+            ("5", None),
+            ("s", None),
+            ("1h", None),
+            ("-1s", None),
+            ("1.5s", None),
+            ("99999999999999999999ms", None),
+            // u64::MAX / 60 and one more
+            (
+                "307445734561825860m",
+                Some(Duration::from_secs(u64::MAX / 60 * 60)),
+            ),
+            ("307445734561825861m", None),
+        ] {
+            assert_eq!(duration::parse(str), duration, "{:?}", str);
+        }
+
+        let timeouts = toml::from_str::<Timeouts>("handshake = \"3s\"").unwrap();
+        assert_eq!(timeouts.handshake, Duration::from_secs(3));
+        let err = toml::from_str::<Timeouts>("handshake = \"3 seconds\"").unwrap_err();
+        assert!(err.to_string().contains("invalid duration `3 seconds`"));
+    }
+
+    #[test]
+    fn validate() {
+        Config::default().validate().unwrap();
+
+        for (field, change) in [
+            (
+                "server.max_players",
+                (|config| config.server.max_players = -1) as fn(&mut Config),
+            ),
+            ("server.view_distance", |config| {
+                config.server.view_distance = 1
+            }),
+            ("server.view_distance", |config| {
+                config.server.view_distance = 33
+            }),
+            ("compression.level", |config| config.compression.level = 10),
+            ("listeners", |config| config.listeners.clear()),
+            ("limits.frame", |config| config.limits.frame = 0),
+            ("limits.frame", |config| {
+                config.limits.frame = protocol::packets::MAX_FRAME_SIZE + 1
+            }),
+            ("limits.login", |config| config.limits.login = 0),
+            ("timeouts.keep_alive", |config| {
+                config.timeouts.keep_alive = Duration::ZERO
+            }),
+            ("listeners.proxy_protocol.trusted", |config| {
+                config.listeners[0].proxy_protocol.enabled = true
+            }),
+            ("governor.max_connections", |config| {
+                config.governor.max_connections = 0
+            }),
+            ("governor.ipv4_prefix", |config| {
+                config.governor.ipv4_prefix = 33
+            }),
+            ("governor.ipv6_prefix", |config| {
+                config.governor.ipv6_prefix = 129
+            }),
+            ("runtime.tick_rate", |config| config.runtime.tick_rate = 0),
+            ("runtime.tick_rate", |config| {
+                config.runtime.tick_rate = 1001
+            }),
+            ("game.max_chunks", |config| config.game.max_chunks = 0),
+            ("game.save_interval", |config| {
+                config.game.save_interval = Duration::ZERO
+            }),
+            ("runtime.max_blocking_threads", |config| {
+                config.runtime.max_blocking_threads = 0
+            }),
+            ("rcon.password", |config| config.rcon.enabled = true),
+        ] {
+            let mut config = Config::default();
+            change(&mut config);
+            match config.validate() {
+                Err(ConfigError::Invalid { field: actual, .. }) => assert_eq!(actual, field),
+                other => panic!("{}: {:?}", field, other),
+            }
+        }
+    }
+
+    #[test]
+    fn keep_restart_only() {
+        let current = Config::default();
+        let mut unchanged = Config::default();
+        assert!(unchanged.keep_restart_only(&current).is_empty());
+
+        let mut config = Config::default();
+        config.listeners[0].bind = Bind::Tcp("127.0.0.1:25570".parse().unwrap());
+        config.rcon.enabled = true;
+        config.rcon.bind = "127.0.0.1:25580".parse().unwrap();
+        config.rcon.password = "secret".to_string();
+        config.runtime.tick_rate = 40;
+        config.game.seed = "1".to_string();
+        config.server.max_players = 5;
+        config.compression.threshold = -1;
+        config.timeouts.handshake = Duration::from_secs(1);
+        config.governor.max_per_subnet = 1;
+        config.send_queue.max_bytes = 1 << 10;
+
+        let restart = config.keep_restart_only(&current);
+        assert_eq!(restart, ["listeners", "rcon", "runtime", "game"]);
+        assert_eq!(config.listeners, current.listeners);
+        assert_eq!(config.rcon.enabled, current.rcon.enabled);
+        assert_eq!(config.rcon.bind, current.rcon.bind);
+        assert_eq!(config.runtime, current.runtime);
+        assert_eq!(config.game, current.game);
+        // everything else applies right away
+        assert_eq!(config.rcon.password, "secret");
+        assert_eq!(config.server.max_players, 5);
+        assert_eq!(config.compression.threshold, -1);
+        assert_eq!(config.timeouts.handshake, Duration::from_secs(1));
+        assert_eq!(config.governor.max_per_subnet, 1);
+        assert_eq!(config.send_queue.max_bytes, 1 << 10);
+    }
+}
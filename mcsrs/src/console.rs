@@ -0,0 +1,121 @@
+use crate::config::Reloader;
+use crate::metrics;
+use crate::registry::Registry;
+use crate::shutdown::Shutdown;
+use protocol::packets::play::clientbound::ChatMessage;
+use protocol::packets::PreparedPacket;
+use protocol::types::{Chat, ChatObj};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::*;
+use uuid::Uuid;
+
+/// Everything console commands operate on, shared by stdin and RCON
+pub struct Console {
+    pub reloader: Arc<Reloader>,
+    pub registry: Arc<Registry>,
+    pub shutdown: Arc<Shutdown>,
+}
+
+/// Runs a single console command and returns its output
+pub async fn execute(console: &Console, line: &str) -> String {
+    let mut args = line.split_whitespace();
+    match args.next() {
+        Some("reload") => match reload(console.reloader.clone()).await {
+            Ok(restart) if restart.is_empty() => "Reloaded the configuration".to_string(),
+            Ok(restart) => format!(
+                "Reloaded the configuration, changes to {} only take effect after a restart",
+                restart.join(", ")
+            ),
+            Err(err) => format!("Unable to reload the configuration: {}", err),
+        },
+        Some("stop") if console.shutdown.trigger() => "Stopping the server".to_string(),
+        Some("stop") => "The server is already stopping".to_string(),
+        Some("list") => {
+            let players = console.registry.players();
+            format!(
+                "There are {} of a max of {} players online: {}",
+                players.len(),
+                console.reloader.handle().get().server.max_players,
+                players
+                    .iter()
+                    .map(|player| format!(
+                        "{} ({}ms, {} bytes queued)",
+                        player.name, player.latency, player.queued
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+        Some("say") => {
+            let message = args.collect::<Vec<_>>().join(" ");
+            if message.is_empty() {
+                return "Usage: say <message>".to_string();
+            }
+            let packet = PreparedPacket::new(ChatMessage {
+                message: Chat::Obj(ChatObj {
+                    text: Some(format!("[Server] {}", message)),
+                    ..Default::default()
+                }),
+                position: 1,
+                sender: Uuid::nil(),
+            });
+            match packet {
+                Ok(packet) => {
+                    console.registry.broadcast(packet);
+                    format!("[Server] {}", message)
+                }
+                Err(err) => format!("Unable to encode message: {}", err),
+            }
+        }
+        Some("metrics") => metrics::report(),
+        Some("help") => "Available commands: help, list, metrics, reload, say, stop".to_string(),
+        Some(command) => format!("Unknown command `{}`, try `help`", command),
+        None => String::new(),
+    }
+}
+
+/// Reloads the configuration on the blocking pool, as it reads files
+async fn reload(reloader: Arc<Reloader>) -> Result<Vec<&'static str>, String> {
+    match tokio::task::spawn_blocking(move || reloader.reload()).await {
+        Ok(result) => result.map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Executes commands entered on stdin until it is closed
+pub async fn read_stdin(console: Arc<Console>) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                let output = execute(&console, &line).await;
+                if !output.is_empty() {
+                    info!("{}", output);
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                error!("unable to read from stdin; err={}", err);
+                break;
+            }
+        }
+    }
+}
+
+/// Reloads the configuration whenever the process receives SIGHUP
+#[cfg(unix)]
+pub async fn reload_on_sighup(console: Arc<Console>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(err) => {
+            error!("unable to listen for SIGHUP; err={}", err);
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        info!("received SIGHUP; {}", execute(&console, "reload").await);
+    }
+}
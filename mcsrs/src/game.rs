@@ -0,0 +1,258 @@
+use crate::save::Storage;
+use protocol::codec::EncodeError;
+use protocol::packets::play::dim::DimensionCodec;
+use protocol::packets::PreparedPacket;
+use protocol::registry::{Registries, RegistryError, Tags};
+use protocol::types::Position;
+use protocol::world::anvil::Anvil;
+use protocol::world::gen::{
+    self, FeatureBlocks, Flat, Generator, GeneratorError, Noise, NoiseBlocks, Void,
+};
+use protocol::world::{ChunkEncoder, Palettes, World};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The only world players can join for now
+pub const WORLD: &str = "minecraft:overworld";
+
+#[derive(Debug, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameSettings {
+    /// Output directory of the vanilla data generator's `--reports`, read once at startup
+    pub reports: PathBuf,
+    /// Directories containing a `data` directory, tags of later packs add to or replace those of
+    /// earlier ones
+    pub data_packs: Vec<PathBuf>,
+    pub generator: GeneratorKind,
+    /// Seed of the noise generator, a number or any text like `level-seed` of server.properties.
+    /// A random seed is picked at every start when empty.
+    pub seed: String,
+    /// Layers of the flat generator, in the format of the superflat customization screen
+    pub flat_preset: String,
+    /// World directory of a vanilla 1.17 server the anvil generator serves the chunks of, like
+    /// `level-name` of server.properties
+    pub level: PathBuf,
+    /// Chunks kept in memory, the least recently sent ones are unloaded and generated or loaded
+    /// again when they are needed
+    pub max_chunks: usize,
+    /// Directory the time of the world and the location of every player are saved in
+    pub save: PathBuf,
+    /// Time between two saves, players are also saved when they leave and everything is saved
+    /// when the server stops
+    #[serde(deserialize_with = "crate::config::duration::deserialize")]
+    pub save_interval: Duration,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            reports: PathBuf::from("generated/reports"),
+            // where the data generator's `--server` puts the vanilla tags
+            data_packs: vec![PathBuf::from("generated")],
+            generator: GeneratorKind::Noise,
+            seed: String::new(),
+            flat_preset: gen::CLASSIC_FLAT.to_string(),
+            level: PathBuf::from("world"),
+            max_chunks: 16384,
+            save: PathBuf::from("save"),
+            // vanilla saves every 6000 ticks
+            save_interval: Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratorKind {
+    Noise,
+    Flat,
+    Void,
+    /// Chunks saved in [`GameSettings::level`], chunks which were never saved stay empty
+    Anvil,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Flags of the PlayerAbilities packet
+    pub fn abilities(self) -> i8 {
+        match self {
+            GameMode::Survival | GameMode::Adventure => 0,
+            // invulnerable, may fly and breaks blocks instantly
+            GameMode::Creative => 0x0D,
+            // invulnerable, flying and may fly
+            GameMode::Spectator => 0x07,
+        }
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = ();
+
+    /// Accepts the names and ids server.properties allows
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "survival" | "0" => Ok(GameMode::Survival),
+            "creative" | "1" => Ok(GameMode::Creative),
+            "adventure" | "2" => Ok(GameMode::Adventure),
+            "spectator" | "3" => Ok(GameMode::Spectator),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ();
+
+    /// Accepts the names and ids server.properties allows
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "peaceful" | "0" => Ok(Difficulty::Peaceful),
+            "easy" | "1" => Ok(Difficulty::Easy),
+            "normal" | "2" => Ok(Difficulty::Normal),
+            "hard" | "3" => Ok(Difficulty::Hard),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Game data shared by every connection, built once at startup
+pub struct Game {
+    pub registries: Registries,
+    pub tags: Tags,
+    /// The Tags packet, sent to every joining player
+    pub tags_packet: PreparedPacket,
+    pub dimension_codec: nbt::Blob,
+    /// Dimension type of [`WORLD`] as sent in JoinGame
+    pub dimension: nbt::Blob,
+    /// Where players appear when they join
+    pub spawn: Position,
+    pub seed: i64,
+    /// Sent to clients instead of the seed
+    pub hashed_seed: i64,
+    pub chunk_encoder: ChunkEncoder,
+    /// Whether the world is a superflat one, which lowers the horizon of the client
+    pub is_flat: bool,
+    /// Only locked on the blocking pool, generating a chunk can take a while
+    pub world: Mutex<World>,
+    /// Player data is read from here when they join, the tick thread writes it
+    pub storage: Storage,
+}
+
+#[derive(Debug)]
+pub enum GameError {
+    Registry(RegistryError),
+    UnknownDimensionType(String),
+    Nbt(nbt::Error),
+    Encode(EncodeError),
+    Generator(GeneratorError),
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::Registry(err) => write!(f, "unable to load registries: {}", err),
+            GameError::UnknownDimensionType(name) => write!(f, "unknown dimension type {}", name),
+            GameError::Nbt(err) => write!(f, "unable to encode registry: {}", err),
+            GameError::Encode(err) => write!(f, "unable to encode packet: {}", err),
+            GameError::Generator(err) => write!(f, "invalid world generator: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl Game {
+    pub fn load(settings: &GameSettings) -> Result<Arc<Self>, GameError> {
+        let registries = Registries::load(&settings.reports).map_err(GameError::Registry)?;
+        let tags = Tags::load(&settings.data_packs, &registries).map_err(GameError::Registry)?;
+        let tags_packet = PreparedPacket::new(tags.packet()).map_err(GameError::Encode)?;
+        let codec = DimensionCodec::default();
+        let dimension_type = codec
+            .dimension_types
+            .get(WORLD)
+            .ok_or_else(|| GameError::UnknownDimensionType(WORLD.to_string()))?
+            .element
+            .clone();
+        let biomes = codec.biomes.value.iter().map(|biome| biome.id + 1).max();
+        let palettes = Palettes::new(
+            registries.blocks.states() as usize,
+            biomes.unwrap_or(1) as usize,
+        );
+        let chunk_encoder = ChunkEncoder::new(&registries.blocks, &dimension_type);
+        let seed = gen::parse_seed(&settings.seed).unwrap_or_else(rand::random);
+        let void = codec
+            .biomes
+            .get("minecraft:the_void")
+            .map_or(0, |biome| biome.id);
+        let generator: Box<dyn Generator> = match settings.generator {
+            GeneratorKind::Noise => {
+                let blocks =
+                    NoiseBlocks::from_registry(&registries.blocks).map_err(GameError::Generator)?;
+                let features = FeatureBlocks::from_registry(&registries.blocks)
+                    .map_err(GameError::Generator)?;
+                Box::new(
+                    Noise::new(seed, blocks, Some(features), &codec)
+                        .map_err(GameError::Generator)?,
+                )
+            }
+            GeneratorKind::Flat => Box::new(
+                Flat::from_preset(&settings.flat_preset, &registries.blocks, &codec)
+                    .map_err(GameError::Generator)?,
+            ),
+            GeneratorKind::Void => Box::new(Void::new(void)),
+            GeneratorKind::Anvil => Box::new(Anvil::new(
+                &settings.level,
+                registries.blocks.clone(),
+                Box::new(Void::new(void)),
+            )),
+        };
+        let dimension = dimension_type.to_blob().map_err(GameError::Nbt)?;
+        let mut world = World::new(dimension_type, palettes, generator);
+        world.set_max_chunks(settings.max_chunks);
+        // on top of the blocks at the center of the world, or at sea level if there are none
+        let spawn = Position::new(0, world.top(0, 0).map_or(64, |y| y + 1), 0);
+        Ok(Arc::new(Self {
+            registries,
+            tags,
+            tags_packet,
+            dimension_codec: codec.to_blob().map_err(GameError::Nbt)?,
+            dimension,
+            spawn,
+            seed,
+            hashed_seed: gen::hashed_seed(seed),
+            chunk_encoder,
+            is_flat: world.is_flat(),
+            world: Mutex::new(world),
+            storage: Storage::new(&settings.save),
+        }))
+    }
+}
@@ -0,0 +1,597 @@
+use crate::config::ConfigHandle;
+use crate::metrics;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::*;
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GovernorSettings {
+    /// Maximum amount of concurrent connections over all addresses
+    pub max_connections: usize,
+    /// Maximum amount of concurrent connections from a single subnet
+    pub max_per_subnet: usize,
+    /// Maximum amount of connections over a limit which are kept open to tell them why they are
+    /// rejected, any further ones are closed right away
+    pub max_rejected: usize,
+    /// Prefix length used to group IPv4 addresses into subnets
+    pub ipv4_prefix: u8,
+    /// Prefix length used to group IPv6 addresses into subnets
+    pub ipv6_prefix: u8,
+    /// Minimum time between two login attempts from the same address, like vanilla's `connection-throttle`
+    #[serde(deserialize_with = "crate::config::duration::deserialize")]
+    pub login_throttle: Duration,
+    /// Addresses which are exempt from the per subnet limit and the login throttle, e.g. proxies
+    pub allow: Vec<Cidr>,
+}
+
+impl Default for GovernorSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 2048,
+            max_per_subnet: 16,
+            max_rejected: 64,
+            ipv4_prefix: 32,
+            ipv6_prefix: 64,
+            login_throttle: Duration::from_millis(4000),
+            allow: vec![],
+        }
+    }
+}
+
+impl GovernorSettings {
+    fn subnet(&self, ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(ip) => IpAddr::V4(mask_v4(ip, self.ipv4_prefix)),
+            IpAddr::V6(ip) => IpAddr::V6(mask_v6(ip, self.ipv6_prefix)),
+        }
+    }
+
+    fn is_allowed(&self, ip: IpAddr) -> bool {
+        self.allow.iter().any(|cidr| cidr.contains(ip))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Cidr {
+    pub addr: IpAddr,
+    pub prefix: u8,
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    /// Parses `10.0.0.0/8` style networks, a plain address is treated as a single host
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid address `{}`", addr))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("invalid prefix length `{}`", prefix))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Cidr {
+    fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        crate::config::from_str::deserialize(de)
+    }
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                mask_v4(net, self.prefix) == mask_v4(ip, self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                mask_v6(net, self.prefix) == mask_v6(ip, self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn mask_v4(ip: Ipv4Addr, prefix: u8) -> Ipv4Addr {
+    let mask = u32::MAX
+        .checked_shl(32 - prefix.min(32) as u32)
+        .unwrap_or(0);
+    Ipv4Addr::from(u32::from(ip) & mask)
+}
+
+fn mask_v6(ip: Ipv6Addr, prefix: u8) -> Ipv6Addr {
+    let mask = u128::MAX
+        .checked_shl(128 - prefix.min(128) as u32)
+        .unwrap_or(0);
+    Ipv6Addr::from(u128::from(ip) & mask)
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Rejection {
+    ServerFull,
+    SubnetLimit,
+    Throttled,
+}
+
+impl Rejection {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Rejection::ServerFull => "The server is not accepting any more connections",
+            Rejection::SubnetLimit => "Too many connections from your network",
+            Rejection::Throttled => "Connection throttled! Please wait before reconnecting.",
+        }
+    }
+}
+
+/// Keeps track of open connections and login attempts, the limits always come from the current configuration
+pub struct Governor {
+    config: ConfigHandle,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    total: usize,
+    rejected: usize,
+    subnets: HashMap<IpAddr, usize>,
+    logins: HashMap<IpAddr, Instant>,
+}
+
+impl Governor {
+    pub fn new(config: ConfigHandle) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            inner: Mutex::new(Inner::default()),
+        })
+    }
+
+    /// Registers a new connection.
+    ///
+    /// Connections over a limit are not counted but still handed a permit, so they can be told why
+    /// they are rejected once they reach the login stage. Only `max_rejected` of them are kept
+    /// open at a time, see [`Permit::close`].
+    pub fn admit(self: &Arc<Self>, ip: IpAddr) -> Permit {
+        let mut permit = self.reserve(ip);
+        permit.readdress(ip);
+        permit
+    }
+
+    /// Registers a connection from a unix socket. Without a source address it only counts towards
+    /// `max_connections` and skips the login throttle, unless [`Permit::readdress`] is called.
+    pub fn admit_local(self: &Arc<Self>) -> Permit {
+        let mut permit = self.reserve(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        permit.local = true;
+        permit
+    }
+
+    /// Registers a connection whose source address is not known yet, e.g. because it still has to
+    /// send its PROXY header. It only counts towards `max_connections` until [`Permit::readdress`]
+    /// is called with the source address.
+    pub fn reserve(self: &Arc<Self>, peer: IpAddr) -> Permit {
+        let config = self.config.get();
+        let ip = peer.to_canonical();
+
+        let mut inner = self.inner.lock().unwrap();
+        let mut rejection = None;
+        let mut notify = false;
+        if inner.total >= config.governor.max_connections {
+            rejection = Some(Rejection::ServerFull);
+            notify = inner.reject(config.governor.max_rejected);
+        } else {
+            inner.total += 1;
+        }
+        drop(inner);
+
+        if let Some(rejection) = rejection {
+            rejected(ip, rejection, notify);
+        }
+
+        Permit {
+            governor: self.clone(),
+            ip,
+            subnet: None,
+            rejection,
+            notify,
+            local: false,
+        }
+    }
+
+    fn login_attempt(&self, ip: IpAddr, now: Instant) -> Result<(), Rejection> {
+        let config = self.config.get();
+        if config.governor.is_allowed(ip) {
+            return Ok(());
+        }
+
+        let throttle = config.governor.login_throttle;
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(last) = inner.logins.insert(ip, now) {
+            if now.duration_since(last) < throttle {
+                warn!(
+                    "throttling login; ip={}; total={}",
+                    ip,
+                    metrics::THROTTLED_LOGINS.inc()
+                );
+                return Err(Rejection::Throttled);
+            }
+        }
+        // forget about addresses which can no longer be throttled, so the map can't grow forever
+        if inner.logins.len() > 1024 {
+            inner
+                .logins
+                .retain(|_, last| now.duration_since(*last) < throttle);
+        }
+        Ok(())
+    }
+
+    fn release(&self, subnet: Option<IpAddr>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.total -= 1;
+        if let Some(subnet) = subnet {
+            inner.leave(subnet);
+        }
+    }
+}
+
+impl Inner {
+    /// Counts a rejected connection if there is room to keep it open until it is told why
+    fn reject(&mut self, max: usize) -> bool {
+        if self.rejected < max {
+            self.rejected += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn leave(&mut self, subnet: IpAddr) {
+        if let Some(count) = self.subnets.get_mut(&subnet) {
+            *count -= 1;
+            if *count == 0 {
+                self.subnets.remove(&subnet);
+            }
+        }
+    }
+}
+
+fn rejected(ip: IpAddr, rejection: Rejection, notify: bool) {
+    warn!(
+        "rejecting connection; ip={}; reason={:?}; notify={}; total={}",
+        ip,
+        rejection,
+        notify,
+        metrics::REJECTED_CONNECTIONS.inc()
+    );
+}
+
+/// A connection slot handed out by the [`Governor`], the slot is freed once this is dropped
+pub struct Permit {
+    governor: Arc<Governor>,
+    ip: IpAddr,
+    subnet: Option<IpAddr>,
+    rejection: Option<Rejection>,
+    /// Whether this rejected connection is counted towards `max_rejected`
+    notify: bool,
+    /// Unix socket connections have no address the throttle could apply to
+    local: bool,
+}
+
+impl Permit {
+    /// Moves this connection to the address it really comes from and applies the per subnet limit
+    pub fn readdress(&mut self, ip: IpAddr) {
+        self.ip = ip.to_canonical();
+        self.local = false;
+        if self.rejection.is_some() {
+            return;
+        }
+
+        let config = self.governor.config.get();
+        let settings = &config.governor;
+        let subnet = settings.subnet(self.ip);
+        let allowed = settings.is_allowed(self.ip);
+
+        let mut inner = self.governor.inner.lock().unwrap();
+        if let Some(previous) = self.subnet.take() {
+            inner.leave(previous);
+        }
+        if !allowed && inner.subnets.get(&subnet).copied().unwrap_or(0) >= settings.max_per_subnet {
+            inner.total -= 1;
+            self.rejection = Some(Rejection::SubnetLimit);
+            self.notify = inner.reject(settings.max_rejected);
+        } else {
+            *inner.subnets.entry(subnet).or_insert(0) += 1;
+            self.subnet = Some(subnet);
+        }
+        drop(inner);
+
+        if let Some(rejection) = self.rejection {
+            rejected(self.ip, rejection, self.notify);
+        }
+    }
+
+    /// Checks whether this connection may proceed into the login stage
+    pub fn login(&self) -> Result<(), Rejection> {
+        match self.rejection {
+            Some(rejection) => Err(rejection),
+            None if self.local => Ok(()),
+            None => self.governor.login_attempt(self.ip, Instant::now()),
+        }
+    }
+
+    /// Whether this connection has to be closed right away, as too many rejected connections are
+    /// already waiting to be told why
+    pub fn close(&self) -> bool {
+        self.rejection.is_some() && !self.notify
+    }
+
+    /// Reason why this connection was not counted towards the limits, if it was rejected
+    pub fn rejection(&self) -> Option<Rejection> {
+        self.rejection
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if self.rejection.is_none() {
+            self.governor.release(self.subnet);
+        } else if self.notify {
+            self.governor.inner.lock().unwrap().rejected -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Args, Config, Reloader};
+    use structopt::StructOpt;
+
+    fn governor(settings: GovernorSettings) -> Arc<Governor> {
+        let config = Config {
+            governor: settings,
+            ..Config::default()
+        };
+        let reloader = Reloader::new(Args::from_iter(&["mcsrs"]), config);
+        Governor::new(reloader.handle())
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn ip4(s: &str) -> Ipv4Addr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn cidr() {
+        let cidr = "10.1.0.0/16".parse::<Cidr>().unwrap();
+        assert_eq!(cidr.addr, ip("10.1.0.0"));
+        assert_eq!(cidr.prefix, 16);
+        assert!(cidr.contains(ip("10.1.255.3")));
+        assert!(cidr.contains(ip("::ffff:10.1.2.3")));
+        assert!(!cidr.contains(ip("10.2.0.0")));
+        assert!(!cidr.contains(ip("::1")));
+
+        let host = "192.168.0.7".parse::<Cidr>().unwrap();
+        assert_eq!(host.prefix, 32);
+        assert!(host.contains(ip("192.168.0.7")));
+        assert!(!host.contains(ip("192.168.0.8")));
+
+        let v6 = "2001:db8::/32".parse::<Cidr>().unwrap();
+        assert!(v6.contains(ip("2001:db8:ffff::1")));
+        assert!(!v6.contains(ip("2001:db9::1")));
+        assert_eq!("::1".parse::<Cidr>().unwrap().prefix, 128);
+        assert!("0.0.0.0/0".parse::<Cidr>().unwrap().contains(ip("1.2.3.4")));
+
+        for invalid in [
+            "",
+            "10.0.0.0/",
+            "10.0.0.0/33",
+            "::/129",
+            "10.0.0.0/-1",
+            "10.0.0/8",
+            "localhost",
+        ] {
+            assert!(invalid.parse::<Cidr>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn subnets() {
+        let settings = GovernorSettings {
+            ipv4_prefix: 24,
+            ipv6_prefix: 64,
+            ..GovernorSettings::default()
+        };
+        assert_eq!(settings.subnet(ip("192.168.5.77")), ip("192.168.5.0"));
+        assert_eq!(settings.subnet(ip("192.168.5.0")), ip("192.168.5.0"));
+        assert_eq!(
+            settings.subnet(ip("2001:db8:1:2:3:4:5:6")),
+            ip("2001:db8:1:2::")
+        );
+        assert_eq!(mask_v4(ip4("255.255.255.255"), 0), ip4("0.0.0.0"));
+        assert_eq!(mask_v4(ip4("1.2.3.4"), 32), ip4("1.2.3.4"));
+        assert_eq!(mask_v4(ip4("1.2.3.255"), 25), ip4("1.2.3.128"));
+        assert_eq!(mask_v6(Ipv6Addr::from(u128::MAX), 0), Ipv6Addr::from(0));
+        assert_eq!(
+            mask_v6(Ipv6Addr::from(u128::MAX), 128),
+            Ipv6Addr::from(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn subnet_limit() {
+        let governor = governor(GovernorSettings {
+            max_per_subnet: 2,
+            ipv4_prefix: 24,
+            allow: vec!["10.0.0.0/8".parse().unwrap()],
+            ..GovernorSettings::default()
+        });
+        let a = governor.admit(ip("192.168.5.1"));
+        let b = governor.admit(ip("::ffff:192.168.5.2"));
+        assert!(a.rejection().is_none() && b.rejection().is_none());
+        assert!(matches!(
+            governor.admit(ip("192.168.5.3")).rejection(),
+            Some(Rejection::SubnetLimit)
+        ));
+        // other subnets and allowed addresses are not affected
+        assert!(governor.admit(ip("192.168.6.1")).rejection().is_none());
+        let allowed = (0..4)
+            .map(|i| governor.admit(IpAddr::V4(Ipv4Addr::new(10, 0, 0, i))))
+            .collect::<Vec<_>>();
+        assert!(allowed.iter().all(|permit| permit.rejection().is_none()));
+        assert_eq!(governor.inner.lock().unwrap().total, 6);
+    }
+
+    #[test]
+    fn server_full() {
+        let governor = governor(GovernorSettings {
+            max_connections: 2,
+            ..GovernorSettings::default()
+        });
+        let _a = governor.admit(ip("1.1.1.1"));
+        let pending = governor.reserve(ip("2.2.2.2"));
+        let full = governor.admit(ip("3.3.3.3"));
+        assert!(matches!(full.rejection(), Some(Rejection::ServerFull)));
+        assert!(matches!(full.login(), Err(Rejection::ServerFull)));
+        drop(pending);
+        assert!(governor.admit(ip("3.3.3.3")).rejection().is_none());
+    }
+
+    #[test]
+    fn release() {
+        let governor = governor(GovernorSettings {
+            max_per_subnet: 1,
+            ..GovernorSettings::default()
+        });
+        let permit = governor.admit(ip("1.2.3.4"));
+        let rejected = governor.admit(ip("1.2.3.4"));
+        assert!(rejected.rejection().is_some());
+        // rejected permits were never counted
+        drop(rejected);
+        assert_eq!(governor.inner.lock().unwrap().total, 1);
+        drop(permit);
+        let inner = governor.inner.lock().unwrap();
+        assert_eq!(inner.total, 0);
+        assert!(inner.subnets.is_empty());
+    }
+
+    #[test]
+    fn readdress() {
+        let governor = governor(GovernorSettings {
+            max_per_subnet: 1,
+            ..GovernorSettings::default()
+        });
+        let proxy = ip("127.0.0.1");
+        let mut a = governor.reserve(proxy);
+        let mut b = governor.reserve(proxy);
+        assert_eq!(governor.inner.lock().unwrap().total, 2);
+        assert!(governor.inner.lock().unwrap().subnets.is_empty());
+
+        a.readdress(ip("1.2.3.4"));
+        b.readdress(ip("1.2.3.4"));
+        assert!(a.rejection().is_none());
+        assert!(matches!(b.rejection(), Some(Rejection::SubnetLimit)));
+        assert_eq!(governor.inner.lock().unwrap().total, 1);
+        drop((a, b));
+        assert_eq!(governor.inner.lock().unwrap().total, 0);
+    }
+
+    #[test]
+    fn local() {
+        let governor = governor(GovernorSettings {
+            max_connections: 3,
+            max_per_subnet: 1,
+            ..GovernorSettings::default()
+        });
+        let a = governor.admit_local();
+        let b = governor.admit_local();
+        assert!(a.rejection().is_none() && b.rejection().is_none());
+        assert!(a.login().is_ok() && b.login().is_ok() && a.login().is_ok());
+        assert!(governor.inner.lock().unwrap().subnets.is_empty());
+
+        // a PROXY header puts the connection under the usual limits
+        let mut c = governor.admit_local();
+        c.readdress(ip("127.0.0.1"));
+        assert!(c.login().is_ok());
+        assert!(matches!(c.login(), Err(Rejection::Throttled)));
+        assert!(matches!(
+            governor.admit_local().rejection(),
+            Some(Rejection::ServerFull)
+        ));
+    }
+
+    #[test]
+    fn max_rejected() {
+        let governor = governor(GovernorSettings {
+            max_connections: 2,
+            max_per_subnet: 1,
+            max_rejected: 2,
+            ..GovernorSettings::default()
+        });
+        let _a = governor.admit(ip("1.1.1.1"));
+        let b = governor.admit(ip("1.1.1.1"));
+        let _x = governor.admit(ip("4.4.4.4"));
+        let c = governor.admit(ip("2.2.2.2"));
+        assert!(matches!(b.rejection(), Some(Rejection::SubnetLimit)));
+        assert!(matches!(c.rejection(), Some(Rejection::ServerFull)));
+        assert!(!b.close() && !c.close());
+        let d = governor.admit(ip("3.3.3.3"));
+        assert!(d.close());
+        assert_eq!(governor.inner.lock().unwrap().rejected, 2);
+
+        drop((b, d));
+        assert_eq!(governor.inner.lock().unwrap().rejected, 1);
+        assert!(!governor.admit(ip("3.3.3.3")).close());
+        drop(c);
+        assert_eq!(governor.inner.lock().unwrap().rejected, 0);
+    }
+
+    #[test]
+    fn login_throttle() {
+        let throttle = Duration::from_millis(200);
+        let governor = governor(GovernorSettings {
+            login_throttle: throttle,
+            allow: vec!["10.0.0.1".parse().unwrap()],
+            ..GovernorSettings::default()
+        });
+        let (a, b, allowed) = (ip("1.2.3.4"), ip("1.2.3.5"), ip("10.0.0.1"));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert!(governor.login_attempt(a, at(0)).is_ok());
+        assert!(governor.login_attempt(b, at(0)).is_ok());
+        assert!(matches!(
+            governor.login_attempt(a, at(199)),
+            Err(Rejection::Throttled)
+        ));
+        for ms in 0..3 {
+            assert!(governor.login_attempt(allowed, at(ms)).is_ok());
+        }
+
+        // the failed attempt restarted the window
+        assert!(matches!(
+            governor.login_attempt(a, at(300)),
+            Err(Rejection::Throttled)
+        ));
+        assert!(governor.login_attempt(a, at(500)).is_ok());
+        assert!(governor.login_attempt(b, at(500)).is_ok());
+
+        // permits use the current time
+        let permit = governor.admit(a);
+        assert!(matches!(permit.login(), Err(Rejection::Throttled)));
+    }
+}
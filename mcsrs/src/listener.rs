@@ -0,0 +1,175 @@
+use crate::config::ConfigHandle;
+use crate::game::Game;
+use crate::governor::Governor;
+use crate::net_client;
+use crate::proxy_protocol::ProxyProtocolSettings;
+use crate::registry::Registry;
+use crate::shutdown::Shutdown;
+use socket2::{Domain, Socket, Type};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::*;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Bind {
+    Tcp(SocketAddr),
+    /// Meant for proxies running on the same machine. Connections have no source address, so they
+    /// are exempt from the per subnet limit and the login throttle unless a PROXY header provides
+    /// one, enable `proxy_protocol` if the proxy forwards players from the internet.
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+impl FromStr for Bind {
+    type Err = std::net::AddrParseError;
+
+    /// Parses either a socket address like `[::]:25565` or a unix socket path like `unix:/run/mcsrs.sock`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[cfg(unix)]
+        if let Some(path) = s.strip_prefix("unix:") {
+            return Ok(Bind::Unix(path.into()));
+        }
+        s.parse().map(Bind::Tcp)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ListenerSettings {
+    #[serde(deserialize_with = "crate::config::from_str::deserialize")]
+    pub bind: Bind,
+    /// When bound to an IPv6 address, refuse IPv4 connections instead of accepting them as mapped addresses
+    pub ipv6_only: bool,
+    pub backlog: u32,
+    pub proxy_protocol: ProxyProtocolSettings,
+}
+
+impl Default for ListenerSettings {
+    fn default() -> Self {
+        Self {
+            bind: Bind::Tcp(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 25565))),
+            ipv6_only: false,
+            backlog: 1024,
+            proxy_protocol: ProxyProtocolSettings::default(),
+        }
+    }
+}
+
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl Listener {
+    pub fn bind(settings: &ListenerSettings) -> io::Result<Self> {
+        match &settings.bind {
+            Bind::Tcp(addr) => {
+                let socket = Socket::new(Domain::for_address(*addr), Type::STREAM, None)?;
+                if addr.is_ipv6() {
+                    socket.set_only_v6(settings.ipv6_only)?;
+                }
+                socket.set_reuse_address(true)?;
+                socket.set_nonblocking(true)?;
+                socket.bind(&(*addr).into())?;
+                socket.listen(settings.backlog as i32)?;
+                Ok(Listener::Tcp(TcpListener::from_std(socket.into())?))
+            }
+            #[cfg(unix)]
+            Bind::Unix(path) => {
+                // a socket file left behind by a previous run would make the bind fail
+                if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+                Ok(Listener::Unix(tokio::net::UnixListener::bind(path)?))
+            }
+        }
+    }
+
+    pub fn local_addr(&self) -> String {
+        match self {
+            Listener::Tcp(listener) => listener
+                .local_addr()
+                .map_or_else(|err| err.to_string(), |addr| addr.to_string()),
+            #[cfg(unix)]
+            Listener::Unix(listener) => listener
+                .local_addr()
+                .ok()
+                .and_then(|addr| addr.as_pathname().map(|path| path.display().to_string()))
+                .unwrap_or_else(|| "<unnamed>".to_string()),
+        }
+    }
+}
+
+/// Accepts connections until the server shuts down, failed accepts are logged and retried
+pub async fn serve(
+    listener: Listener,
+    settings: ListenerSettings,
+    governor: Arc<Governor>,
+    config: ConfigHandle,
+    registry: Arc<Registry>,
+    game: Arc<Game>,
+    shutdown: Arc<Shutdown>,
+) {
+    info!("listening for connections; addr={}", listener.local_addr());
+    let proxy = Arc::new(settings.proxy_protocol);
+    let mut signal = shutdown.signal();
+
+    loop {
+        let accept = async {
+            match &listener {
+                Listener::Tcp(listener) => listener.accept().await.map(|(stream, peer)| {
+                    tokio::spawn(net_client::accept(
+                        stream,
+                        Some(peer),
+                        governor.clone(),
+                        proxy.clone(),
+                        config.clone(),
+                        registry.clone(),
+                        game.clone(),
+                        shutdown.signal(),
+                    ));
+                }),
+                // unix sockets have no peer address
+                #[cfg(unix)]
+                Listener::Unix(listener) => listener.accept().await.map(|(stream, _)| {
+                    tokio::spawn(net_client::accept(
+                        stream,
+                        None,
+                        governor.clone(),
+                        proxy.clone(),
+                        config.clone(),
+                        registry.clone(),
+                        game.clone(),
+                        shutdown.signal(),
+                    ));
+                }),
+            }
+        };
+        let accepted = tokio::select! {
+            accepted = accept => accepted,
+            _ = signal.recv() => break,
+        };
+
+        if let Err(err) = accepted {
+            match err.kind() {
+                // the connection died before we got to it, nothing wrong with the listener
+                io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset => {
+                    debug!("connection aborted before accept; err={}", err)
+                }
+                // most likely out of file descriptors (EMFILE/ENFILE) or memory, give the
+                // server some time to close connections before trying again
+                _ => {
+                    error!("unable to accept connection; err={}", err);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }
+
+    info!("stopped listening; addr={}", listener.local_addr());
+}
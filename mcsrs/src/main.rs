@@ -1,23 +1,151 @@
 #![deny(unsafe_code)]
 
+mod config;
+mod console;
+mod game;
+mod governor;
+mod listener;
+mod metrics;
 mod net_client;
+mod proxy_protocol;
+mod rcon;
+mod registry;
+mod runtime;
+mod save;
+mod send_queue;
+mod shutdown;
 mod sm;
+mod tick;
 
-use tokio::net::TcpListener;
+use std::sync::Arc;
+use structopt::StructOpt;
 use tracing::*;
-use tracing_futures::Instrument;
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() {
+fn main() {
     tracing_subscriber::fmt::init();
 
-    let listener = TcpListener::bind("0.0.0.0:25565").await.unwrap();
-    loop {
-        let client = listener.accept().await.unwrap();
-        tokio::spawn(
-            net_client::handle_client(client.0)
-                .instrument(debug_span!("client", addr = client.1.to_string().as_str())),
-        );
+    let args = config::Args::from_args();
+    let reloader = match config::Config::load(&args) {
+        Ok(config) => Arc::new(config::Reloader::new(args, config)),
+        Err(err) => {
+            error!("unable to load configuration: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let settings = reloader.handle().get().runtime;
+    let runtime = match runtime::build(&settings) {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            error!("unable to start runtime; err={}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let game = match game::Game::load(&reloader.handle().get().game) {
+        Ok(game) => game,
+        Err(err) => {
+            error!("unable to load game data; err={}", err);
+            std::process::exit(1);
+        }
+    };
+    info!(
+        "loaded registries; blocks={}; states={}; items={}; tags={}",
+        game.registries.blocks.len(),
+        game.registries.blocks.states(),
+        game.registries.items.len(),
+        game.tags.len()
+    );
+    info!("loaded world; seed={}", game.seed);
+    // a world which can't be read would be overwritten by the next save
+    let world = match game.storage.load_world() {
+        Ok(world) => world.unwrap_or_default(),
+        Err(err) => {
+            error!("unable to load saved world; err={}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // the tick thread only talks to network tasks through channels
+    let (events, tick_events) = std::sync::mpsc::channel();
+    let registry = registry::Registry::new(events);
+    let shutdown = shutdown::Shutdown::new();
+    let tick = match tick::spawn(
+        settings.tick_rate,
+        world,
+        game.storage.clone(),
+        reloader.handle().get().game.save_interval,
+        registry.clone(),
+        tick_events,
+        shutdown.clone(),
+    ) {
+        Ok(tick) => tick,
+        Err(err) => {
+            error!("unable to start tick thread; err={}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let code = runtime.block_on(run(reloader, registry, game, shutdown));
+    if tick.join().is_err() {
+        error!("tick thread panicked");
+    }
+
+    info!("server stopped; code={}", code);
+    std::process::exit(code);
+}
+
+/// Serves connections until the server shuts down, returns the exit code
+async fn run(
+    reloader: Arc<config::Reloader>,
+    registry: Arc<registry::Registry>,
+    game: Arc<game::Game>,
+    shutdown: Arc<shutdown::Shutdown>,
+) -> i32 {
+    let config = reloader.handle();
+    let governor = governor::Governor::new(config.clone());
+
+    let listeners = config.get().listeners.clone();
+    for settings in listeners {
+        let listener = match listener::Listener::bind(&settings) {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!(
+                    "unable to bind listener; bind={:?}; err={}",
+                    &settings.bind, err
+                );
+                std::process::exit(1);
+            }
+        };
+        tokio::spawn(listener::serve(
+            listener,
+            settings,
+            governor.clone(),
+            config.clone(),
+            registry.clone(),
+            game.clone(),
+            shutdown.clone(),
+        ));
+    }
+
+    let console = Arc::new(console::Console {
+        reloader,
+        registry,
+        shutdown: shutdown.clone(),
+    });
+    tokio::spawn(console::read_stdin(console.clone()));
+    #[cfg(unix)]
+    tokio::spawn(console::reload_on_sighup(console.clone()));
+    tokio::spawn(rcon::serve(console));
+    tokio::spawn(shutdown::on_signal(shutdown.clone()));
+
+    shutdown.triggered().await;
+    info!("stopping server");
+    let timeout = config.get().shutdown.timeout;
+    if shutdown.wait(timeout).await {
+        0
+    } else {
+        warn!("connections did not close in time; timeout={:?}", timeout);
+        1
     }
 }
 
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    /// Increments the counter and returns the new total
+    pub fn inc(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A value which can go up and down
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub const fn new() -> Self {
+        Self(AtomicI64::new(0))
+    }
+
+    pub fn add(&self, n: i64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn sub(&self, n: i64) {
+        self.0.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Frames rejected because their announced length exceeded the limit of the current state
+pub static OVERSIZE_FRAMES: Counter = Counter::new();
+/// Packets rejected because their decompressed length exceeded the limit
+pub static OVERSIZE_PACKETS: Counter = Counter::new();
+/// Frames rejected because the length prefix was not a valid VarInt
+pub static INVALID_FRAMES: Counter = Counter::new();
+/// Connections closed because a packet could not be decoded
+pub static INVALID_PACKETS: Counter = Counter::new();
+/// Connections closed because a packet for the client could not be encoded
+pub static ENCODE_ERRORS: Counter = Counter::new();
+/// Connections closed because reading from the socket failed
+pub static READ_ERRORS: Counter = Counter::new();
+/// Connections dropped because they did not finish a stage in time
+pub static TIMEOUTS: Counter = Counter::new();
+/// Connections over the global or per subnet connection limit
+pub static REJECTED_CONNECTIONS: Counter = Counter::new();
+/// Login attempts rejected by the login throttle
+pub static THROTTLED_LOGINS: Counter = Counter::new();
+/// Connections closed because their PROXY header could not be parsed
+pub static INVALID_PROXY_HEADERS: Counter = Counter::new();
+/// Bytes waiting in the send queues of all connections
+pub static QUEUED_BYTES: Gauge = Gauge::new();
+/// Non-essential packets dropped because the client was falling behind
+pub static DROPPED_PACKETS: Counter = Counter::new();
+/// Clients kicked because their send queue overflowed
+pub static SLOW_CLIENTS: Counter = Counter::new();
+
+/// Current value of every metric as `name=value` pairs
+pub fn report() -> String {
+    let counters = [
+        ("oversize_frames", &OVERSIZE_FRAMES),
+        ("oversize_packets", &OVERSIZE_PACKETS),
+        ("invalid_frames", &INVALID_FRAMES),
+        ("invalid_packets", &INVALID_PACKETS),
+        ("encode_errors", &ENCODE_ERRORS),
+        ("read_errors", &READ_ERRORS),
+        ("timeouts", &TIMEOUTS),
+        ("rejected_connections", &REJECTED_CONNECTIONS),
+        ("throttled_logins", &THROTTLED_LOGINS),
+        ("invalid_proxy_headers", &INVALID_PROXY_HEADERS),
+        ("dropped_packets", &DROPPED_PACKETS),
+        ("slow_clients", &SLOW_CLIENTS),
+    ];
+    let mut report = format!("queued_bytes={}", QUEUED_BYTES.get());
+    for (name, counter) in counters.iter() {
+        report.push_str(&format!("; {}={}", name, counter.get()));
+    }
+    report
+}
@@ -1,68 +1,265 @@
-use crate::sm::StateMachine;
+use crate::config::{self, ConfigHandle};
+use crate::game::Game;
+use crate::governor::{Governor, Permit};
+use crate::metrics;
+use crate::proxy_protocol::{self, ProxyProtocolSettings};
+use crate::registry::Registry;
+use crate::send_queue;
+use crate::shutdown::Signal;
+use crate::sm::{Stage, StateMachine};
 use bytes::{Buf, Bytes, BytesMut};
 use protocol::codec::{DecodeError, Transcodeable};
 use protocol::types::VarInt;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use std::io::{self, IoSlice};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{Instant, MissedTickBehavior};
 use tracing::*;
 use tracing_futures::Instrument;
 
-pub async fn handle_client(stream: TcpStream) {
-    debug!("handeling new client");
+/// Upper bounds for the length of a single frame, excluding its length prefix
+#[derive(Debug, Copy, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Hard limit for every state, frames can never be larger than this
+    pub frame: usize,
+    pub handshake: usize,
+    pub status: usize,
+    pub login: usize,
+    /// Maximum size of a packet after it has been decompressed
+    pub decompressed: usize,
+}
 
-    let (mut read, mut write) = stream.into_split();
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            frame: protocol::packets::MAX_FRAME_SIZE,
+            // the handshake carries a 255 char address, which may take up to 4 bytes per char
+            handshake: 1 << 11,
+            status: 1 << 8,
+            login: 1 << 11,
+            decompressed: protocol::packets::MAX_DECOMPRESSED_SIZE,
+        }
+    }
+}
 
-    let (stx, mut srx) = tokio::sync::mpsc::unbounded_channel::<Bytes>();
+/// How long a connection may stay in each stage before it gets dropped.
+///
+/// The handshake, status and login timeouts cover the whole stage, so trickling in bytes does not
+/// extend them. In play the timeout restarts with every read instead.
+#[derive(Debug, Copy, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub handshake: Duration,
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub status: Duration,
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub login: Duration,
+    /// Clients answer keep-alives every 15 seconds, so a healthy connection never idles this long
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub play_read: Duration,
+    /// How often clients in play are sent a keep-alive
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub keep_alive_interval: Duration,
+    /// Clients which do not answer a keep-alive within this time get kicked
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub keep_alive: Duration,
+    /// How long a closing connection gets to write out what is still queued
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub flush: Duration,
+}
 
-    let mut write_handle = tokio::spawn(
-        async move {
-            debug!("spawned new write task");
-            while let Some(mut packet) = srx.recv().await {
-                debug!("sending packet; pkt={:?}", &packet);
-                loop {
-                    match write.write_buf(&mut packet).await {
-                        Ok(0) => return Ok(()),
-                        Ok(_) if packet.has_remaining() => continue,
-                        Ok(_) => break,
-                        Err(err) => return Err(err),
-                    }
-                }
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            handshake: Duration::from_secs(5),
+            status: Duration::from_secs(10),
+            login: Duration::from_secs(30),
+            play_read: Duration::from_secs(30),
+            keep_alive_interval: Duration::from_secs(15),
+            keep_alive: Duration::from_secs(30),
+            flush: Duration::from_secs(5),
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn for_stage(&self, stage: Stage) -> Duration {
+        match stage {
+            Stage::Handshake => self.handshake,
+            Stage::Status => self.status,
+            Stage::Login => self.login,
+            Stage::Play => self.play_read,
+        }
+    }
+}
+
+/// Reads the PROXY header if required and hands the connection over to [`handle_client`].
+///
+/// Unix socket connections have no `peer`, they are treated as local connections unless a PROXY
+/// header says otherwise.
+#[allow(clippy::too_many_arguments)]
+pub async fn accept<S>(
+    mut stream: S,
+    peer: Option<SocketAddr>,
+    governor: Arc<Governor>,
+    proxy: Arc<ProxyProtocolSettings>,
+    config: ConfigHandle,
+    registry: Arc<Registry>,
+    game: Arc<Game>,
+    shutdown: Signal,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let mut buf = BytesMut::with_capacity(1024);
+
+    let local = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+    let mut source = peer;
+    let permit = if proxy.enabled {
+        let peer = peer.unwrap_or(local);
+        if !proxy.is_trusted(peer.ip()) {
+            warn!("closing connection from untrusted proxy; peer={}", peer);
+            return;
+        }
+        // count the connection while it waits for its header, its subnet is only known afterwards
+        let mut permit = match source {
+            Some(peer) => governor.reserve(peer.ip()),
+            None => governor.admit_local(),
+        };
+        if permit.rejection().is_some() {
+            // there is no way to tell the client why yet
+            return;
+        }
+        let header = proxy_protocol::read_header(&mut stream, &mut buf);
+        match tokio::time::timeout(config.get().timeouts.handshake, header).await {
+            Ok(Ok(Some(addr))) => source = Some(addr),
+            Ok(Ok(None)) => (),
+            Ok(Err(err)) => {
+                warn!(
+                    "unable to read proxy header; peer={}; err={}; total={}",
+                    peer,
+                    err,
+                    metrics::INVALID_PROXY_HEADERS.inc()
+                );
+                return;
+            }
+            Err(_) => {
+                warn!(
+                    "proxy header timed out; peer={}; total={}",
+                    peer,
+                    metrics::TIMEOUTS.inc()
+                );
+                return;
             }
-            Ok(())
         }
-        .instrument(debug_span!("write")),
+        if let Some(addr) = source {
+            permit.readdress(addr.ip());
+        }
+        permit
+    } else {
+        match peer {
+            Some(peer) => governor.admit(peer.ip()),
+            None => governor.admit_local(),
+        }
+    };
+    if permit.close() {
+        return;
+    }
+
+    let addr = source.unwrap_or(local);
+    let peer = peer.map_or_else(|| "unix".to_string(), |peer| peer.to_string());
+    handle_client(stream, addr, buf, permit, config, registry, game, shutdown)
+        .instrument(debug_span!(
+            "client",
+            addr = addr.to_string().as_str(),
+            peer = peer.as_str()
+        ))
+        .await
+}
+
+/// Drives a single connection, `buf` holds data which has already been read from the stream
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_client<S>(
+    stream: S,
+    addr: SocketAddr,
+    mut buf: BytesMut,
+    permit: Permit,
+    config: ConfigHandle,
+    registry: Arc<Registry>,
+    game: Arc<Game>,
+    mut shutdown: Signal,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    debug!("handeling new client");
+    // timeouts are fixed for the lifetime of a connection, a reload only affects new connections
+    let timeouts = config.get().timeouts;
+
+    let (mut read, write) = tokio::io::split(stream);
+
+    let (stx, srx) = send_queue::channel(config.get().send_queue);
+
+    let mut write_handle = tokio::spawn(
+        write_loop(write, srx, config.get().send_queue.flush_interval)
+            .instrument(debug_span!("write")),
     );
+    let mut write_done = false;
 
     debug!("creating state machine");
-    let mut stmch = StateMachine::new(stx);
+    let (btx, mut brx) = tokio::sync::mpsc::unbounded_channel();
+    // one chunk at a time, the light and data packets of a chunk are sent together
+    let (ctx, mut crx) = tokio::sync::mpsc::channel(2);
+    let mut stmch = StateMachine::new(stx, addr, config.clone(), registry, game, btx, ctx);
+
+    let mut stage = stmch.stage();
+    let mut admitted = false;
+    let timeout = tokio::time::sleep(timeouts.for_stage(stage));
+    tokio::pin!(timeout);
+
+    let mut reloads = config.clone();
+
+    let mut keep_alive = tokio::time::interval_at(
+        Instant::now() + timeouts.keep_alive_interval,
+        timeouts.keep_alive_interval,
+    );
+    keep_alive.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-    let mut buf = BytesMut::with_capacity(1024);
     let mut clen = None;
     'read_loop: loop {
-        debug!("waiting for read or join");
-        let read = tokio::select! {
-            read = read.read_buf(&mut buf) => match read {
-                // Client closed connection
-                Ok(0) => break,
-                Ok(read) => read,
-                Err(_err) => {
-                    todo!("Handle error")
-                }
-            },
-            _join_res = &mut write_handle => todo!(),
-        };
-        debug!("read data; len={}", read);
-
         loop {
             let len = match clen {
                 Some(len) => len,
                 None => {
                     let len_bytes = &(buf.chunk())[..5.min(buf.len())];
                     let len = match VarInt::decode(len_bytes) {
-                        Ok(len) => *len as usize + len.size_hint().unwrap(),
+                        Ok(len) if *len >= 0 => len,
                         Err(DecodeError::ToLittleData) => break,
-                        Err(_) => unreachable!(),
+                        _ => {
+                            warn!(
+                                "invalid frame length; total={}",
+                                metrics::INVALID_FRAMES.inc()
+                            );
+                            stmch.disconnect("Invalid packet length");
+                            break 'read_loop;
+                        }
                     };
+
+                    let max = stmch.max_frame_size();
+                    if *len as usize > max {
+                        warn!(
+                            "frame exceeds size limit; len={}; max={}; total={}",
+                            *len,
+                            max,
+                            metrics::OVERSIZE_FRAMES.inc()
+                        );
+                        stmch.disconnect("Packet too large");
+                        break 'read_loop;
+                    }
+
+                    let len = *len as usize + len.size_hint().unwrap();
                     clen = Some(len);
                     len
                 }
@@ -75,12 +272,302 @@ pub async fn handle_client(stream: TcpStream) {
                 let packet = buf.copy_to_bytes(len);
                 match stmch.submit(packet).await {
                     Ok(_) => (),
-                    Err(_err) => todo!("handle error"),
+                    Err(DecodeError::OversizePacket { max, recv }) => {
+                        warn!(
+                            "decompressed packet exceeds size limit; len={}; max={}; total={}",
+                            recv,
+                            max,
+                            metrics::OVERSIZE_PACKETS.inc()
+                        );
+                        stmch.disconnect("Packet too large");
+                        break 'read_loop;
+                    }
+                    Err(err) => {
+                        warn!(
+                            "unable to decode packet; err={}; total={}",
+                            err,
+                            metrics::INVALID_PACKETS.inc()
+                        );
+                        stmch.disconnect("Invalid packet");
+                        break 'read_loop;
+                    }
                 }
                 clen = None;
+                if stmch.is_closed() {
+                    break 'read_loop;
+                }
+
+                // rejected connections are only told about it once they leave the handshake
+                if !admitted && stmch.stage() != Stage::Handshake {
+                    admitted = true;
+                    let admission = match (stmch.stage(), permit.rejection()) {
+                        (Stage::Login, _) => permit.login(),
+                        (_, Some(rejection)) => Err(rejection),
+                        (_, None) => Ok(()),
+                    };
+                    if let Err(rejection) = admission {
+                        stmch.disconnect(rejection.reason());
+                        break 'read_loop;
+                    }
+                }
             } else {
-                continue 'read_loop; // we break out of the processing loop as we have no complete packets left
+                break; // we break out of the processing loop as we have no complete packets left
             }
         }
+
+        let next = stmch.stage();
+        if next != stage {
+            debug!("resetting timeout; stage={:?}", next);
+            stage = next;
+            timeout
+                .as_mut()
+                .reset(Instant::now() + timeouts.for_stage(stage));
+        }
+
+        debug!("waiting for read or join");
+        let read = tokio::select! {
+            read = read.read_buf(&mut buf) => match read {
+                // Client closed connection
+                Ok(0) => break,
+                Ok(read) => {
+                    if stage == Stage::Play {
+                        timeout
+                            .as_mut()
+                            .reset(Instant::now() + timeouts.play_read);
+                    }
+                    read
+                }
+                Err(err) => {
+                    debug!(
+                        "unable to read from client; err={}; total={}",
+                        err,
+                        metrics::READ_ERRORS.inc()
+                    );
+                    break;
+                }
+            },
+            _join_res = &mut write_handle => {
+                write_done = true;
+                break;
+            },
+            _ = &mut timeout => {
+                warn!(
+                    "client timed out; stage={:?}; total={}",
+                    stage,
+                    metrics::TIMEOUTS.inc()
+                );
+                stmch.disconnect("Timed out");
+                break;
+            },
+            _ = keep_alive.tick(), if stage == Stage::Play => {
+                stmch.keep_alive(timeouts.keep_alive);
+                if stmch.is_closed() {
+                    break;
+                }
+                continue;
+            },
+            Some(packet) = brx.recv() => {
+                stmch.send_prepared(&packet);
+                if stmch.is_closed() {
+                    break;
+                }
+                continue;
+            },
+            Some(packet) = crx.recv() => {
+                stmch.send_prepared(&packet);
+                if stmch.is_closed() {
+                    break;
+                }
+                continue;
+            },
+            _ = reloads.changed() => {
+                stmch.reloaded();
+                if stmch.is_closed() {
+                    break;
+                }
+                continue;
+            },
+            _ = shutdown.recv() => {
+                stmch.disconnect(&config.get().shutdown.kick_message);
+                break;
+            },
+        };
+        debug!("read data; len={}", read);
+    }
+
+    debug!("closing connection; peak_queue={}", stmch.queue_peak());
+    // dropping the state machine closes the send queue, so the write task finishes after flushing
+    drop(stmch);
+    if !write_done
+        && tokio::time::timeout(timeouts.flush, &mut write_handle)
+            .await
+            .is_err()
+    {
+        debug!("unable to flush send queue in time");
+        write_handle.abort();
+    }
+}
+
+/// Writes queued frames in batches until the queue is closed
+async fn write_loop<W: AsyncWrite + Unpin>(
+    mut write: W,
+    mut queue: send_queue::Receiver,
+    flush_interval: Duration,
+) -> io::Result<()> {
+    debug!("spawned new write task");
+    let mut ticks = match flush_interval {
+        Duration::ZERO => None,
+        interval => {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            Some(ticks)
+        }
+    };
+    let mut batch = Vec::new();
+    let mut coalesced = BytesMut::new();
+
+    loop {
+        if let Some(ticks) = &mut ticks {
+            ticks.tick().await;
+        }
+        if !queue.recv_batch(&mut batch).await {
+            return Ok(());
+        }
+        trace!(
+            "writing batch; packets={}; len={}",
+            batch.len(),
+            batch.iter().map(Bytes::len).sum::<usize>()
+        );
+
+        if batch.len() == 1 {
+            write.write_all(&batch[0]).await?;
+        } else if write.is_write_vectored() {
+            write_all_vectored(&mut write, &batch).await?;
+        } else {
+            // one copy is still a lot cheaper than one syscall per packet
+            coalesced.clear();
+            batch
+                .iter()
+                .for_each(|frame| coalesced.extend_from_slice(frame));
+            write.write_all(&coalesced).await?;
+        }
+        batch.clear();
+    }
+}
+
+async fn write_all_vectored<W: AsyncWrite + Unpin>(
+    write: &mut W,
+    batch: &[Bytes],
+) -> io::Result<()> {
+    let mut slices = batch
+        .iter()
+        .map(|frame| IoSlice::new(frame))
+        .collect::<Vec<_>>();
+    let mut slices = &mut slices[..];
+    while !slices.is_empty() {
+        match write.write_vectored(slices).await? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => IoSlice::advance_slices(&mut slices, n),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Args, Config, Reloader};
+    use crate::game::{GameSettings, GeneratorKind};
+    use crate::shutdown::Shutdown;
+    use crate::sm::NOT_WHITELISTED;
+    use protocol::codec::Packet;
+    use protocol::packets::login::serverbound::LoginStart;
+    use protocol::packets::play::clientbound::Disconnect;
+    use protocol::packets::{decode, encode, read_header, Handshake};
+    use protocol::types::{Chat, StringN};
+    use std::borrow::Cow;
+    use std::path::{Path, PathBuf};
+    use structopt::StructOpt;
+
+    fn write_config(path: &Path, whitelisted: &[&str]) {
+        let config = format!(
+            "[server]\nwhitelist = true\nwhitelisted = {:?}\n[compression]\nthreshold = -1\n",
+            whitelisted
+        );
+        std::fs::write(path, config).unwrap();
+    }
+
+    #[tokio::test]
+    async fn kicked_when_no_longer_whitelisted() {
+        let path =
+            std::env::temp_dir().join(format!("mcsrs-whitelist-{}.toml", std::process::id()));
+        write_config(&path, &["alice"]);
+        let args = || Args::from_iter(&["mcsrs", "-c", path.to_str().unwrap()]);
+        let reloader = Reloader::new(args(), Config::load(&args()).unwrap());
+        let game = Game::load(&GameSettings {
+            reports: PathBuf::from(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../protocol/tests/data/reports"
+            )),
+            data_packs: vec![],
+            generator: GeneratorKind::Void,
+            ..GameSettings::default()
+        })
+        .unwrap();
+        let (events, _events) = std::sync::mpsc::channel();
+        let shutdown = Shutdown::new();
+        let addr = SocketAddr::from(([127, 0, 0, 1], 4000));
+
+        let (mut client, server) = tokio::io::duplex(1 << 20);
+        let permit = Governor::new(reloader.handle()).admit(addr.ip());
+        let connection = tokio::spawn(handle_client(
+            server,
+            addr,
+            BytesMut::new(),
+            permit,
+            reloader.handle(),
+            Registry::new(events),
+            game,
+            shutdown.signal(),
+        ));
+
+        let handshake = Handshake {
+            version: VarInt(756),
+            address: StringN(Cow::Borrowed("localhost")),
+            port: 25565,
+            next: VarInt(2),
+        };
+        client.write_all(&encode(handshake).unwrap()).await.unwrap();
+        let login = LoginStart(StringN(Cow::Borrowed("alice")));
+        client.write_all(&encode(login).unwrap()).await.unwrap();
+        // alice is in play once the join sequence arrives
+        let mut received = BytesMut::new();
+        while received.len() < 4096 {
+            client.read_buf(&mut received).await.unwrap();
+        }
+
+        write_config(&path, &["bob"]);
+        reloader.reload().unwrap();
+        let closed = async { while client.read_buf(&mut received).await.unwrap() > 0 {} };
+        tokio::time::timeout(Duration::from_secs(5), closed)
+            .await
+            .expect("connection was not closed");
+        connection.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut frames = received.freeze();
+        let mut last = None;
+        while frames.has_remaining() {
+            let (len, id) = read_header(&mut frames.clone()).unwrap();
+            let len = len + VarInt(len as i32).size_hint().unwrap();
+            last = Some((id, frames.split_to(len)));
+        }
+        let (id, frame) = last.unwrap();
+        assert_eq!(id, Disconnect::id());
+        let Disconnect(reason) = decode::<Disconnect, _>(frame).unwrap();
+        assert!(matches!(
+            reason,
+            Chat::Obj(obj) if obj.text.as_deref() == Some(NOT_WHITELISTED)
+        ));
     }
 }
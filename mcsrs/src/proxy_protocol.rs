@@ -0,0 +1,294 @@
+//! https://www.haproxy.org/download/2.4/doc/proxy-protocol.txt
+
+use crate::governor::Cidr;
+use bytes::{Buf, BytesMut};
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LEN: usize = 107;
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LEN: usize = 16;
+/// Addresses and TLVs which are larger than this are refused instead of being buffered
+const V2_MAX_LEN: usize = 1 << 10;
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxyProtocolSettings {
+    /// When enabled, every connection from a trusted source has to start with a PROXY header
+    pub enabled: bool,
+    /// Sources which are allowed to send a PROXY header, connections from others are closed.
+    /// An empty list trusts nobody. Unix sockets connect from `127.0.0.1`.
+    pub trusted: Vec<Cidr>,
+}
+
+impl ProxyProtocolSettings {
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted.iter().any(|cidr| cidr.contains(ip))
+    }
+}
+
+#[derive(Debug)]
+pub enum ProxyError {
+    Io(std::io::Error),
+    InvalidHeader,
+}
+
+/// Reads a v1 or v2 PROXY header from the start of the stream.
+///
+/// Returns the source address carried by the header, or `None` if the proxy did not forward one
+/// (health checks use `LOCAL`/`UNKNOWN`). Everything read past the header is left in `buf`.
+pub async fn read_header<R: AsyncRead + Unpin>(
+    read: &mut R,
+    buf: &mut BytesMut,
+) -> Result<Option<SocketAddr>, ProxyError> {
+    loop {
+        if let Some((addr, len)) = parse(buf.chunk())? {
+            buf.advance(len);
+            return Ok(addr);
+        }
+        match read.read_buf(buf).await {
+            Ok(0) => return Err(ProxyError::Io(std::io::ErrorKind::UnexpectedEof.into())),
+            Ok(_) => (),
+            Err(err) => return Err(ProxyError::Io(err)),
+        }
+    }
+}
+
+type Parsed = Option<(Option<SocketAddr>, usize)>;
+
+fn parse(buf: &[u8]) -> Result<Parsed, ProxyError> {
+    if starts_with(buf, V2_SIGNATURE) {
+        parse_v2(buf)
+    } else if starts_with(buf, V1_PREFIX) {
+        parse_v1(buf)
+    } else {
+        Err(ProxyError::InvalidHeader)
+    }
+}
+
+/// Like `<[u8]>::starts_with`, but also matches if `buf` is still shorter than `prefix`
+fn starts_with(buf: &[u8], prefix: &[u8]) -> bool {
+    let len = buf.len().min(prefix.len());
+    buf[..len] == prefix[..len]
+}
+
+fn parse_v1(buf: &[u8]) -> Result<Parsed, ProxyError> {
+    let end = match buf.windows(2).position(|w| w == b"\r\n") {
+        Some(end) => end,
+        None if buf.len() < V1_MAX_LEN => return Ok(None),
+        None => return Err(ProxyError::InvalidHeader),
+    };
+    let line = std::str::from_utf8(&buf[..end]).map_err(|_| ProxyError::InvalidHeader)?;
+    let parts = line.split(' ').skip(1).collect::<Vec<_>>();
+
+    let addr = match parts[..] {
+        [family @ ("TCP4" | "TCP6"), src, dst, src_port, dst_port] => {
+            let src = src
+                .parse::<IpAddr>()
+                .map_err(|_| ProxyError::InvalidHeader)?;
+            let dst = dst
+                .parse::<IpAddr>()
+                .map_err(|_| ProxyError::InvalidHeader)?;
+            let src_port = src_port.parse().map_err(|_| ProxyError::InvalidHeader)?;
+            dst_port
+                .parse::<u16>()
+                .map_err(|_| ProxyError::InvalidHeader)?;
+            if src.is_ipv4() != (family == "TCP4") || dst.is_ipv4() != src.is_ipv4() {
+                return Err(ProxyError::InvalidHeader);
+            }
+            Some(SocketAddr::new(src, src_port))
+        }
+        // whatever follows is ignored
+        ["UNKNOWN", ..] => None,
+        _ => return Err(ProxyError::InvalidHeader),
+    };
+
+    Ok(Some((addr, end + 2)))
+}
+
+fn parse_v2(buf: &[u8]) -> Result<Parsed, ProxyError> {
+    if buf.len() < V2_HEADER_LEN {
+        return Ok(None);
+    }
+    let ver_cmd = buf[12];
+    let family = buf[13];
+    let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    if ver_cmd >> 4 != 2 || len > V2_MAX_LEN {
+        return Err(ProxyError::InvalidHeader);
+    }
+    if buf.len() < V2_HEADER_LEN + len {
+        return Ok(None);
+    }
+    let body = &buf[V2_HEADER_LEN..V2_HEADER_LEN + len];
+
+    let addr = match (ver_cmd & 0x0F, family >> 4) {
+        // LOCAL, the connection was opened by the proxy itself
+        (0x0, _) => None,
+        (0x1, 0x0) => None,
+        (0x1, 0x1) if body.len() >= 12 => {
+            let ip = Ipv4Addr::new(body[0], body[1], body[2], body[3]);
+            let port = u16::from_be_bytes([body[8], body[9]]);
+            Some(SocketAddr::new(IpAddr::V4(ip), port))
+        }
+        (0x1, 0x2) if body.len() >= 36 => {
+            let mut ip = [0u8; 16];
+            ip.copy_from_slice(&body[..16]);
+            let port = u16::from_be_bytes([body[32], body[33]]);
+            Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(ip)), port))
+        }
+        _ => return Err(ProxyError::InvalidHeader),
+    };
+
+    Ok(Some((addr, V2_HEADER_LEN + len)))
+}
+
+impl Display for ProxyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for ProxyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProxyError::Io(err) => Some(err),
+            ProxyError::InvalidHeader => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2(command: u8, family: u8, body: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(0x20 | command);
+        header.push(family);
+        header.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        header.extend_from_slice(body);
+        header
+    }
+
+    fn addr(s: &str) -> Option<SocketAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn trusted() {
+        let settings = ProxyProtocolSettings {
+            enabled: true,
+            trusted: vec![],
+        };
+        assert!(!settings.is_trusted(Ipv4Addr::LOCALHOST.into()));
+
+        let settings = ProxyProtocolSettings {
+            enabled: true,
+            trusted: vec!["10.0.0.0/8".parse().unwrap(), "::1".parse().unwrap()],
+        };
+        assert!(settings.is_trusted("10.1.2.3".parse().unwrap()));
+        assert!(settings.is_trusted(Ipv6Addr::LOCALHOST.into()));
+        assert!(!settings.is_trusted("11.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn v1() {
+        let header = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n\x10\x00";
+        assert_eq!(
+            parse(header).unwrap(),
+            Some((addr("192.0.2.1:56324"), header.len() - 2))
+        );
+        let header = b"PROXY TCP6 2001:db8::1 2001:db8::2 4000 25565\r\n";
+        assert_eq!(
+            parse(header).unwrap(),
+            Some((addr("[2001:db8::1]:4000"), header.len()))
+        );
+        let header = b"PROXY UNKNOWN ffff:f::1 ffff:f::2 1 2\r\n";
+        assert_eq!(parse(header).unwrap(), Some((None, header.len())));
+
+        // truncated headers wait for more data, unless they are already too long
+        assert_eq!(parse(b"PRO").unwrap(), None);
+        assert_eq!(parse(b"PROXY TCP4 192.0.2.1 198.51").unwrap(), None);
+        let long = [b"PROXY TCP4 ".as_ref(), &[b'1'; V1_MAX_LEN]].concat();
+        assert!(matches!(parse(&long), Err(ProxyError::InvalidHeader)));
+
+        for header in [
+            b"PROXY TCP4 192.0.2.1 198.51.100.1 56324\r\n".as_ref(),
+            b"PROXY TCP4 192.0.2.x 198.51.100.1 1 2\r\n",
+            b"PROXY TCP4 192.0.2.1 198.51.100.1 65536 2\r\n",
+            b"PROXY UDP4 192.0.2.1 198.51.100.1 1 2\r\n",
+            b"PROXY TCP6 192.0.2.1 198.51.100.1 1 2\r\n",
+            b"PROXY TCP4 192.0.2.1 198.51.100.1 1 2 3\r\n",
+            b"\x10\x00\xf4\x05",
+        ] {
+            assert!(matches!(parse(header), Err(ProxyError::InvalidHeader)));
+        }
+    }
+
+    #[test]
+    fn v2_addresses() {
+        let ipv4 = [192, 0, 2, 1, 198, 51, 100, 1, 0xDC, 0x04, 0x63, 0xDD];
+        let header = v2(0x1, 0x11, &ipv4);
+        assert_eq!(
+            parse(&header).unwrap(),
+            Some((addr("192.0.2.1:56324"), header.len()))
+        );
+
+        let mut ipv6 = "2001:db8::1".parse::<Ipv6Addr>().unwrap().octets().to_vec();
+        ipv6.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        ipv6.extend_from_slice(&[0x0F, 0xA0, 0x63, 0xDD]);
+        // TLVs after the addresses are skipped
+        ipv6.extend_from_slice(&[0x04, 0x00, 0x01, 0x00]);
+        let mut header = v2(0x1, 0x21, &ipv6);
+        header.extend_from_slice(b"\x10\x00");
+        assert_eq!(
+            parse(&header).unwrap(),
+            Some((addr("[2001:db8::1]:4000"), header.len() - 2))
+        );
+    }
+
+    #[test]
+    fn v2_without_address() {
+        // LOCAL ignores whatever addresses there are
+        let header = v2(0x0, 0x11, &[0; 12]);
+        assert_eq!(parse(&header).unwrap(), Some((None, header.len())));
+        let header = v2(0x0, 0x00, &[]);
+        assert_eq!(parse(&header).unwrap(), Some((None, header.len())));
+        // PROXY with the UNSPEC family
+        let header = v2(0x1, 0x00, &[]);
+        assert_eq!(parse(&header).unwrap(), Some((None, header.len())));
+    }
+
+    #[test]
+    fn invalid_v2() {
+        let header = v2(0x1, 0x11, &[0; 12]);
+        for len in [0, 5, 12, V2_HEADER_LEN, header.len() - 1] {
+            assert_eq!(parse(&header[..len]).unwrap(), None);
+        }
+
+        let mut signature = header.clone();
+        signature[11] = b'X';
+        assert!(matches!(parse(&signature), Err(ProxyError::InvalidHeader)));
+        let mut version = header.clone();
+        version[12] = 0x11;
+        assert!(matches!(parse(&version), Err(ProxyError::InvalidHeader)));
+
+        for header in [
+            // unknown command and family
+            v2(0x2, 0x11, &[0; 12]),
+            v2(0x1, 0x31, &[0; 216]),
+            // too short for the addresses of the family
+            v2(0x1, 0x11, &[0; 11]),
+            v2(0x1, 0x21, &[0; 35]),
+        ] {
+            assert!(matches!(parse(&header), Err(ProxyError::InvalidHeader)));
+        }
+
+        // refused before the body arrives
+        let mut oversized = header[..V2_HEADER_LEN].to_vec();
+        oversized[14..16].copy_from_slice(&(V2_MAX_LEN as u16 + 1).to_be_bytes());
+        assert!(matches!(parse(&oversized), Err(ProxyError::InvalidHeader)));
+    }
+}
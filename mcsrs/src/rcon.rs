@@ -0,0 +1,122 @@
+//! https://wiki.vg/RCON
+
+use crate::console::{self, Console};
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::*;
+use tracing_futures::Instrument;
+
+const TYPE_RESPONSE: i32 = 0;
+const TYPE_COMMAND: i32 = 2;
+const TYPE_LOGIN: i32 = 3;
+
+/// Largest packet a client may send, the vanilla server uses the same limit
+const MAX_PACKET_SIZE: usize = 1460;
+/// Responses are split into packets carrying at most this many bytes
+const MAX_RESPONSE_SIZE: usize = 4096;
+
+/// Binds the configured RCON address and accepts connections, does nothing if RCON is disabled
+pub async fn serve(console: Arc<Console>) {
+    let config = console.reloader.handle().get();
+    if !config.rcon.enabled {
+        return;
+    }
+
+    let listener = match TcpListener::bind(config.rcon.bind).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(
+                "unable to bind rcon; addr={}; err={}",
+                config.rcon.bind, err
+            );
+            return;
+        }
+    };
+    info!("listening for rcon connections; addr={}", config.rcon.bind);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tokio::spawn(
+                    handle(stream, console.clone())
+                        .instrument(debug_span!("rcon", addr = peer.to_string().as_str())),
+                );
+            }
+            Err(err) => {
+                error!("unable to accept rcon connection; err={}", err);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+async fn handle(mut stream: TcpStream, console: Arc<Console>) {
+    match run(&mut stream, &console).await {
+        Ok(()) => debug!("rcon connection closed"),
+        Err(err) => debug!("rcon connection failed; err={}", err),
+    }
+}
+
+async fn run(stream: &mut TcpStream, console: &Console) -> io::Result<()> {
+    let mut authenticated = false;
+    loop {
+        let len = match stream.read_i32_le().await {
+            Ok(len) => len as usize,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        // id, type and the two terminating zero bytes
+        if !(10..=MAX_PACKET_SIZE).contains(&len) {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        let mut packet = vec![0; len];
+        stream.read_exact(&mut packet).await?;
+        let mut packet = &packet[..];
+        let id = packet.get_i32_le();
+        let kind = packet.get_i32_le();
+        let body = String::from_utf8_lossy(&packet[..packet.len() - 2]).into_owned();
+
+        match kind {
+            TYPE_LOGIN => {
+                let password = &console.reloader.handle().get().rcon.password;
+                authenticated = !password.is_empty() && body == *password;
+                if !authenticated {
+                    warn!("rcon login failed");
+                }
+                let id = if authenticated { id } else { -1 };
+                write(stream, id, TYPE_COMMAND, "").await?;
+            }
+            TYPE_COMMAND if authenticated => {
+                info!("rcon command; cmd={}", &body);
+                let output = console::execute(console, &body).await;
+                let mut output = output.as_str();
+                loop {
+                    let mut split = output.len().min(MAX_RESPONSE_SIZE);
+                    while !output.is_char_boundary(split) {
+                        split -= 1;
+                    }
+                    write(stream, id, TYPE_RESPONSE, &output[..split]).await?;
+                    output = &output[split..];
+                    if output.is_empty() {
+                        break;
+                    }
+                }
+            }
+            _ => write(stream, id, TYPE_RESPONSE, "").await?,
+        }
+    }
+}
+
+async fn write(stream: &mut TcpStream, id: i32, kind: i32, body: &str) -> io::Result<()> {
+    let mut buf = BytesMut::with_capacity(14 + body.len());
+    buf.put_i32_le(10 + body.len() as i32);
+    buf.put_i32_le(id);
+    buf.put_i32_le(kind);
+    buf.put_slice(body.as_bytes());
+    buf.put_u16(0);
+    stream.write_all(&buf).await
+}
@@ -0,0 +1,251 @@
+use crate::game::GameMode;
+use crate::save::Location;
+use crate::send_queue::QueueDepth;
+use crate::tick;
+use protocol::packets::play::clientbound::{NewPlayer, PlayerInfo, PlayerInfoAction};
+use protocol::packets::PreparedPacket;
+use protocol::types::{Array, StringN, VarInt};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use tokio::sync::mpsc;
+use tracing::*;
+use uuid::Uuid;
+
+pub type Broadcasts = mpsc::UnboundedSender<Arc<PreparedPacket>>;
+
+/// Every player currently in play, used to reach all of them at once
+pub struct Registry {
+    /// Entity id of the next player, ids are reused once it wraps around
+    next_id: AtomicI32,
+    players: Mutex<HashMap<i32, Player>>,
+    /// Tells the tick thread about players joining and leaving
+    events: std_mpsc::Sender<tick::Event>,
+}
+
+struct Player {
+    name: String,
+    uuid: Uuid,
+    gamemode: GameMode,
+    broadcasts: Broadcasts,
+    /// Smoothed round trip time in milliseconds, kept up to date by the connection
+    latency: Arc<AtomicU32>,
+    queue: QueueDepth,
+    /// Kept up to date by the connection, so the tick thread can save it
+    location: Arc<Mutex<Location>>,
+}
+
+/// Snapshot of a registered player
+pub struct Entry {
+    pub name: String,
+    pub uuid: Uuid,
+    pub latency: u32,
+    /// Bytes waiting in the send queue of the connection
+    pub queued: usize,
+    pub location: Location,
+}
+
+/// Keeps a player registered until it is dropped
+pub struct Registration {
+    registry: Arc<Registry>,
+    id: i32,
+}
+
+impl Registry {
+    pub fn new(events: std_mpsc::Sender<tick::Event>) -> Arc<Self> {
+        Arc::new(Self {
+            next_id: AtomicI32::new(0),
+            players: Mutex::new(HashMap::new()),
+            events,
+        })
+    }
+
+    /// Packets passed to [`Registry::broadcast`] are delivered through `broadcasts`, the
+    /// connection applies its own compression and encryption before queueing them.
+    ///
+    /// Everyone already registered is told to add the player to their tab list, before any
+    /// latency update can mention it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn register(
+        self: &Arc<Self>,
+        name: String,
+        uuid: Uuid,
+        gamemode: GameMode,
+        broadcasts: Broadcasts,
+        latency: Arc<AtomicU32>,
+        queue: QueueDepth,
+        location: Arc<Mutex<Location>>,
+    ) -> Registration {
+        let _ = self.events.send(tick::Event::Joined(broadcasts.clone()));
+        let mut players = self.players.lock().unwrap();
+        // the counter wraps on overflow, ids of players who are still online are skipped
+        let id = loop {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            if !players.contains_key(&id) {
+                break id;
+            }
+        };
+        let player = Player {
+            name,
+            uuid,
+            gamemode,
+            broadcasts,
+            latency,
+            queue,
+            location,
+        };
+        let added = PreparedPacket::new(PlayerInfo(PlayerInfoAction::AddPlayer(vec![
+            player.tab_entry()
+        ])));
+        match added {
+            Ok(added) => {
+                let added = Arc::new(added);
+                for other in players.values() {
+                    let _ = other.broadcasts.send(added.clone());
+                }
+            }
+            Err(err) => error!("unable to encode player info; err={}", err),
+        }
+        players.insert(id, player);
+        Registration {
+            registry: self.clone(),
+            id,
+        }
+    }
+
+    /// Sends a packet to every registered player, it is only encoded and compressed once
+    pub fn broadcast(&self, packet: PreparedPacket) {
+        let packet = Arc::new(packet);
+        for player in self.players.lock().unwrap().values() {
+            // the connection is already closing if this fails
+            let _ = player.broadcasts.send(packet.clone());
+        }
+    }
+
+    /// Adds every registered player to the tab list, sent to players when they join
+    pub fn tab_list(&self) -> PlayerInfo {
+        let mut players = self
+            .players
+            .lock()
+            .unwrap()
+            .values()
+            .map(Player::tab_entry)
+            .collect::<Vec<_>>();
+        players.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+        PlayerInfo(PlayerInfoAction::AddPlayer(players))
+    }
+
+    pub fn online(&self) -> usize {
+        self.players.lock().unwrap().len()
+    }
+
+    /// Every registered player, sorted by name
+    pub fn players(&self) -> Vec<Entry> {
+        let mut players = self
+            .players
+            .lock()
+            .unwrap()
+            .values()
+            .map(|player| Entry {
+                name: player.name.clone(),
+                uuid: player.uuid,
+                latency: player.latency.load(Ordering::Relaxed),
+                queued: player.queue.queued(),
+                location: *player.location.lock().unwrap(),
+            })
+            .collect::<Vec<_>>();
+        players.sort_by(|a, b| a.name.cmp(&b.name));
+        players
+    }
+}
+
+impl Player {
+    fn tab_entry(&self) -> (Uuid, NewPlayer) {
+        let entry = NewPlayer {
+            name: StringN(Cow::Owned(self.name.clone())),
+            properties: Array::new(vec![]),
+            gamemode: VarInt(self.gamemode.id() as i32),
+            ping: VarInt(self.latency.load(Ordering::Relaxed) as i32),
+            display_name: None,
+        };
+        (self.uuid, entry)
+    }
+}
+
+impl Registration {
+    /// Entity id of the player
+    pub fn eid(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        // sent while locked, so a player is either in a snapshot of the registry or its event is
+        // already queued when the tick thread saves everyone
+        let mut players = self.registry.players.lock().unwrap();
+        if let Some(player) = players.remove(&self.id) {
+            let location = *player.location.lock().unwrap();
+            let _ = self
+                .registry
+                .events
+                .send(tick::Event::Left(player.uuid, location));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::send_queue;
+
+    fn register(registry: &Arc<Registry>, name: &str) -> Registration {
+        let (broadcasts, _) = mpsc::unbounded_channel();
+        let (queue, _) = send_queue::channel(Default::default());
+        registry.register(
+            name.to_string(),
+            Uuid::from_u128(name.len() as u128),
+            GameMode::Survival,
+            broadcasts,
+            Arc::new(AtomicU32::new(0)),
+            queue.depth(),
+            Arc::default(),
+        )
+    }
+
+    #[test]
+    fn events() {
+        let (tx, rx) = std_mpsc::channel();
+        let registry = Registry::new(tx);
+        let alice = register(&registry, "alice");
+        let bob = register(&registry, "bob");
+        assert_eq!(registry.online(), 2);
+        drop(alice);
+        assert_eq!(registry.online(), 1);
+        assert_eq!(registry.players()[0].name, "bob");
+
+        let events = rx.try_iter().collect::<Vec<_>>();
+        assert!(matches!(
+            events[..],
+            [tick::Event::Joined(_), tick::Event::Joined(_), tick::Event::Left(uuid, _)]
+                if uuid == Uuid::from_u128(5)
+        ));
+        drop(bob);
+        assert!(matches!(rx.try_recv(), Ok(tick::Event::Left(..))));
+    }
+
+    #[test]
+    fn eids_wrap() {
+        let (tx, _rx) = std_mpsc::channel();
+        let registry = Registry::new(tx);
+        let first = register(&registry, "first");
+        assert_eq!(first.eid(), 0);
+        registry.next_id.store(i32::MAX, Ordering::Relaxed);
+        assert_eq!(register(&registry, "max").eid(), i32::MAX);
+        registry.next_id.store(-1, Ordering::Relaxed);
+        let _last = register(&registry, "last");
+        // 0 is still taken
+        assert_eq!(register(&registry, "next").eid(), 1);
+    }
+}
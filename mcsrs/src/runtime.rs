@@ -0,0 +1,36 @@
+use std::io;
+use tokio::runtime::{Builder, Runtime};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeSettings {
+    /// Threads running network tasks, 0 uses one per CPU core
+    pub worker_threads: usize,
+    /// Upper limit for threads generating, loading and compressing chunks
+    pub max_blocking_threads: usize,
+    /// Game ticks per second, the tick loop runs on its own thread
+    pub tick_rate: u32,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            worker_threads: 0,
+            max_blocking_threads: 64,
+            tick_rate: 20,
+        }
+    }
+}
+
+/// Builds the multi-threaded runtime all network tasks run on
+pub fn build(settings: &RuntimeSettings) -> io::Result<Runtime> {
+    let mut builder = Builder::new_multi_thread();
+    builder
+        .enable_all()
+        .thread_name("mcsrs-worker")
+        .max_blocking_threads(settings.max_blocking_threads);
+    if settings.worker_threads > 0 {
+        builder.worker_threads(settings.worker_threads);
+    }
+    builder.build()
+}
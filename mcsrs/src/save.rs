@@ -0,0 +1,143 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// State of the world kept across restarts, owned by the tick thread
+#[derive(Debug, Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldData {
+    pub age: i64,
+    pub time_of_day: i64,
+}
+
+/// Where a player is, kept across restarts so it rejoins where it left
+#[derive(Debug, Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Location {
+    pub x: f64,
+    /// Position of the feet
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(PathBuf, io::Error),
+    Read(PathBuf, toml::de::Error),
+    Write(PathBuf, toml::ser::Error),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(path, err) => write!(f, "unable to access {}: {}", path.display(), err),
+            SaveError::Read(path, err) => write!(f, "invalid data in {}: {}", path.display(), err),
+            SaveError::Write(path, err) => {
+                write!(f, "unable to encode {}: {}", path.display(), err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// Reads and writes the files in the save directory, `world.toml` and a `players/<uuid>.toml` for
+/// every player who ever joined
+#[derive(Debug, Clone)]
+pub struct Storage {
+    directory: PathBuf,
+}
+
+impl Storage {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    /// `None` if the world was never saved
+    pub fn load_world(&self) -> Result<Option<WorldData>, SaveError> {
+        read(&self.directory.join("world.toml"))
+    }
+
+    pub fn save_world(&self, world: &WorldData) -> Result<(), SaveError> {
+        write(&self.directory.join("world.toml"), world)
+    }
+
+    /// `None` if the player never joined before
+    pub fn load_player(&self, uuid: Uuid) -> Result<Option<Location>, SaveError> {
+        read(&self.player(uuid))
+    }
+
+    pub fn save_player(&self, uuid: Uuid, location: &Location) -> Result<(), SaveError> {
+        write(&self.player(uuid), location)
+    }
+
+    fn player(&self, uuid: Uuid) -> PathBuf {
+        self.directory
+            .join("players")
+            .join(format!("{}.toml", uuid.to_hyphenated()))
+    }
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, SaveError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(SaveError::Io(path.into(), err)),
+    };
+    toml::from_str(&content)
+        .map(Some)
+        .map_err(|err| SaveError::Read(path.into(), err))
+}
+
+/// Writes to a temporary file first, so a crash while saving never leaves a half written file
+fn write<T: Serialize>(path: &Path, value: &T) -> Result<(), SaveError> {
+    let content = toml::to_string(value).map_err(|err| SaveError::Write(path.into(), err))?;
+    let temporary = path.with_extension("toml.tmp");
+    let io = |err| SaveError::Io(path.into(), err);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io)?;
+    }
+    std::fs::write(&temporary, content).map_err(io)?;
+    std::fs::rename(&temporary, path).map_err(io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let directory = std::env::temp_dir().join(format!("mcsrs-save-{}", std::process::id()));
+        let storage = Storage::new(&directory);
+        let uuid = Uuid::from_u128(7);
+        assert_eq!(storage.load_world().unwrap(), None);
+        assert_eq!(storage.load_player(uuid).unwrap(), None);
+
+        let world = WorldData {
+            age: 1 << 40,
+            time_of_day: 18000,
+        };
+        let location = Location {
+            x: -0.5,
+            y: 70.0,
+            z: 1e7,
+            yaw: 90.0,
+            pitch: -12.5,
+        };
+        storage.save_world(&world).unwrap();
+        storage.save_player(uuid, &location).unwrap();
+        assert_eq!(storage.load_world().unwrap(), Some(world));
+        assert_eq!(storage.load_player(uuid).unwrap(), Some(location));
+        assert_eq!(storage.load_player(Uuid::from_u128(8)).unwrap(), None);
+
+        std::fs::write(directory.join("world.toml"), "age = \"old\"").unwrap();
+        assert!(matches!(storage.load_world(), Err(SaveError::Read(..))));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use crate::metrics;
+use bytes::Bytes;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+use tokio::sync::{mpsc, Notify};
+
+/// Limits for the outgoing queue of a single connection, measured in bytes as they go over the wire
+#[derive(Debug, Copy, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueSettings {
+    /// Once this many bytes are waiting, non-essential packets like particles and sounds are dropped
+    pub drop_threshold: usize,
+    /// Clients which let more than this many bytes pile up get kicked
+    pub max_bytes: usize,
+    /// Write at most once per interval, collecting everything queued in between into one batch.
+    /// Zero writes as soon as something is queued.
+    #[serde(deserialize_with = "crate::config::duration::deserialize")]
+    pub flush_interval: Duration,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self {
+            drop_threshold: 1 << 19,
+            max_bytes: 1 << 22,
+            flush_interval: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Priority {
+    Essential,
+    /// Purely cosmetic, the client does not notice if it never arrives
+    Droppable,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Sent {
+    Queued,
+    Dropped,
+}
+
+#[derive(Debug)]
+pub enum SendError {
+    /// The client is not keeping up, `queued` bytes are still waiting to be written
+    Full { queued: usize },
+    /// The write task is gone
+    Closed,
+}
+
+struct Depth {
+    queued: AtomicUsize,
+    peak: AtomicUsize,
+    /// Notified whenever the write task took frames out of the queue
+    drained: Notify,
+}
+
+/// Watches how many bytes are waiting in the queue of a connection
+#[derive(Clone)]
+pub struct QueueDepth(Arc<Depth>);
+
+pub struct Sender {
+    tx: mpsc::UnboundedSender<Bytes>,
+    depth: Arc<Depth>,
+    settings: QueueSettings,
+}
+
+pub struct Receiver {
+    rx: mpsc::UnboundedReceiver<Bytes>,
+    depth: Arc<Depth>,
+}
+
+pub fn channel(settings: QueueSettings) -> (Sender, Receiver) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let depth = Arc::new(Depth {
+        queued: AtomicUsize::new(0),
+        peak: AtomicUsize::new(0),
+        drained: Notify::new(),
+    });
+    (
+        Sender {
+            tx,
+            depth: depth.clone(),
+            settings,
+        },
+        Receiver { rx, depth },
+    )
+}
+
+impl Sender {
+    pub fn send(&self, frame: Bytes, priority: Priority) -> Result<Sent, SendError> {
+        let len = frame.len();
+        let queued = self.depth.queued.load(Ordering::Relaxed);
+        if priority == Priority::Droppable && queued + len > self.settings.drop_threshold {
+            metrics::DROPPED_PACKETS.inc();
+            return Ok(Sent::Dropped);
+        }
+        // a single packet always fits into an empty queue, otherwise the client is not keeping up
+        if queued != 0 && queued + len > self.settings.max_bytes {
+            return Err(SendError::Full { queued });
+        }
+
+        // counted before sending, so the receiver never subtracts bytes which were not added yet
+        let queued = self.depth.queued.fetch_add(len, Ordering::Relaxed) + len;
+        metrics::QUEUED_BYTES.add(len as i64);
+        if self.tx.send(frame).is_err() {
+            self.depth.queued.fetch_sub(len, Ordering::Relaxed);
+            metrics::QUEUED_BYTES.sub(len as i64);
+            return Err(SendError::Closed);
+        }
+        self.depth.peak.fetch_max(queued, Ordering::Relaxed);
+        Ok(Sent::Queued)
+    }
+
+    /// Largest depth the queue ever reached
+    pub fn peak(&self) -> usize {
+        self.depth.peak.load(Ordering::Relaxed)
+    }
+
+    pub fn depth(&self) -> QueueDepth {
+        QueueDepth(self.depth.clone())
+    }
+}
+
+impl QueueDepth {
+    /// Bytes waiting to be written
+    pub fn queued(&self) -> usize {
+        self.0.queued.load(Ordering::Relaxed)
+    }
+
+    /// Waits until fewer than `limit` bytes are waiting. Only a single task may wait at a time.
+    pub async fn below(&self, limit: usize) {
+        while self.queued() >= limit {
+            self.0.drained.notified().await;
+        }
+    }
+}
+
+impl Receiver {
+    /// Waits for at least one frame and moves everything queued so far into `batch`.
+    /// Returns `false` once the queue is closed and empty.
+    pub async fn recv_batch(&mut self, batch: &mut Vec<Bytes>) -> bool {
+        match self.rx.recv().await {
+            Some(frame) => self.take(frame, batch),
+            None => return false,
+        }
+        std::future::poll_fn(|cx| {
+            while let Poll::Ready(Some(frame)) = self.rx.poll_recv(cx) {
+                self.take(frame, batch);
+            }
+            Poll::Ready(())
+        })
+        .await;
+        // stores a permit if nobody is waiting yet, so a waiter never misses the wakeup
+        self.depth.drained.notify_one();
+        true
+    }
+
+    fn take(&self, frame: Bytes, batch: &mut Vec<Bytes>) {
+        self.depth.queued.fetch_sub(frame.len(), Ordering::Relaxed);
+        metrics::QUEUED_BYTES.sub(frame.len() as i64);
+        batch.push(frame);
+    }
+}
+
+impl Drop for Depth {
+    fn drop(&mut self) {
+        // whatever never got written no longer counts towards the total
+        metrics::QUEUED_BYTES.sub(*self.queued.get_mut() as i64);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::config;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tracing::*;
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownSettings {
+    /// Shown to every player still connected when the server stops
+    pub kick_message: String,
+    /// How long connections get to flush their queues, the server exits with 1 if they take longer
+    #[serde(deserialize_with = "config::duration::deserialize")]
+    pub timeout: Duration,
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        Self {
+            kick_message: "Server closed".to_string(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Tells tasks when the server is stopping and keeps track of the ones which are still running
+pub struct Shutdown {
+    trigger: watch::Sender<bool>,
+    triggered: watch::Receiver<bool>,
+    /// Every [`Signal`] holds a clone, the channel closes once all of them are gone
+    alive: Mutex<Option<mpsc::Sender<()>>>,
+    done: tokio::sync::Mutex<mpsc::Receiver<()>>,
+}
+
+/// Held by a task for as long as it runs, the server waits for all of them before exiting
+pub struct Signal {
+    triggered: watch::Receiver<bool>,
+    _alive: Option<mpsc::Sender<()>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Arc<Self> {
+        let (trigger, triggered) = watch::channel(false);
+        let (alive, done) = mpsc::channel(1);
+        Arc::new(Self {
+            trigger,
+            triggered,
+            alive: Mutex::new(Some(alive)),
+            done: tokio::sync::Mutex::new(done),
+        })
+    }
+
+    pub fn signal(&self) -> Signal {
+        Signal {
+            triggered: self.triggered.clone(),
+            _alive: self.alive.lock().unwrap().clone(),
+        }
+    }
+
+    /// Starts shutting down, returns `false` if the server is already stopping
+    pub fn trigger(&self) -> bool {
+        if self.is_triggered() {
+            return false;
+        }
+        let _ = self.trigger.send(true);
+        // tasks started from now on don't get waited for
+        self.alive.lock().unwrap().take();
+        true
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Resolves once [`Shutdown::trigger`] has been called
+    pub async fn triggered(&self) {
+        let mut triggered = self.triggered.clone();
+        while !*triggered.borrow() {
+            if triggered.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Waits until every [`Signal`] has been dropped, returns `false` if that took longer than `timeout`
+    pub async fn wait(&self, timeout: Duration) -> bool {
+        let mut done = self.done.lock().await;
+        tokio::time::timeout(timeout, done.recv()).await.is_ok()
+    }
+}
+
+impl Signal {
+    /// Resolves once the server starts shutting down
+    pub async fn recv(&mut self) {
+        while !*self.triggered.borrow() {
+            if self.triggered.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Starts shutting down on Ctrl-C or SIGTERM
+pub async fn on_signal(shutdown: Arc<Shutdown>) {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                error!("unable to listen for SIGTERM; err={}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        res = tokio::signal::ctrl_c() => {
+            if let Err(err) = res {
+                error!("unable to listen for Ctrl-C; err={}", err);
+                return;
+            }
+            info!("received Ctrl-C");
+        }
+        _ = terminate => info!("received SIGTERM"),
+    }
+    shutdown.trigger();
+}
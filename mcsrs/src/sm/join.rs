@@ -0,0 +1,244 @@
+use super::StateMachine;
+use crate::game::{self, Game};
+use crate::metrics;
+use crate::save::Location;
+use crate::send_queue::QueueDepth;
+use protocol::codec::Transcodeable;
+use protocol::packets::compression::Compression;
+use protocol::packets::play::clientbound::*;
+use protocol::packets::PreparedPacket;
+use protocol::types::{Array, Identifier, MaxString, Remaining, VarInt};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::*;
+
+/// Sent as the server brand, shown in the debug screen of the client
+const BRAND: &str = "mcsrs";
+
+/// Entity status which sets the permission level of the player to 0
+const OP_LEVEL_0: i8 = 24;
+
+impl StateMachine {
+    /// Sends everything a client needs before it leaves the "Loading terrain" screen, in the order
+    /// vanilla sends it. The player appears at its current location.
+    pub(super) fn join(&mut self, eid: i32) {
+        let config = self.config.get();
+        let game = self.game.clone();
+        let gamemode = config.server.gamemode;
+        let view_distance = config.server.view_distance as i32;
+        let world = Identifier::from_static(game::WORLD);
+
+        let mut brand = Vec::new();
+        MaxString::from_static(BRAND)
+            .encode(&mut brand)
+            .expect("the brand is a valid string");
+
+        respond!(
+            self <- JoinGame {
+                eid,
+                hardcore: config.server.hardcore,
+                gamemode: gamemode.id(),
+                prev_gamemode: -1,
+                worlds: Array::new(vec![world.clone()]),
+                dim_codec: game.dimension_codec.clone(),
+                dim: game.dimension.clone(),
+                world_name: world,
+                hashed_seed: game.hashed_seed,
+                max_players: VarInt(config.server.max_players),
+                view_distance: VarInt(view_distance),
+                reduce_debug: false,
+                respawn_screen: true,
+                is_debug: false,
+                is_flat: game.is_flat,
+            };
+            <- PluginMessage {
+                channel: Identifier::from_static("minecraft:brand"),
+                data: Remaining(brand.into()),
+            };
+            <- ServerDifficulty {
+                difficulty: config.server.difficulty.id(),
+                locked: false,
+            };
+            <- PlayerAbilities {
+                flags: gamemode.abilities(),
+                flying_speed: 0.05,
+                fov_modifier: 0.1,
+            };
+            <- HeldItemChange(0);
+            <- DeclareRecipes(Array::new(vec![]))
+        );
+        self.send_prepared(&game.tags_packet);
+        respond!(
+            self <- EntityStatus {
+                eid,
+                status: OP_LEVEL_0,
+            };
+            <- DeclareCommands {
+                nodes: Array::new(vec![CommandNode {
+                    flags: NODE_ROOT,
+                    children: Array::new(vec![]),
+                    redirect: None,
+                    name: None,
+                    parser: None,
+                    suggestions: None,
+                }]),
+                root: VarInt(0),
+            }
+        );
+
+        let spawn = game.spawn;
+        let location = *self.location.lock().unwrap();
+        self.teleport(location);
+        // everyone online, the player included, others were told about it when it registered
+        respond!(self <- self.registry.tab_list());
+        respond!(
+            self <- SpawnPosition {
+                location: spawn,
+                angle: 0.0,
+            }
+        );
+
+        let chunk_x = (location.x.floor() as i32) >> 4;
+        let chunk_z = (location.z.floor() as i32) >> 4;
+        respond!(
+            self <- UpdateViewPosition {
+                x: VarInt(chunk_x),
+                z: VarInt(chunk_z),
+            }
+        );
+        // nearest chunks first, so the client can render the spawn as early as possible
+        let mut chunks = Vec::new();
+        for x in -view_distance..=view_distance {
+            for z in -view_distance..=view_distance {
+                chunks.push((chunk_x + x, chunk_z + z));
+            }
+        }
+        chunks.sort_by_key(|(x, z)| (x - chunk_x).pow(2) + (z - chunk_z).pow(2));
+        // sounds and particles still get through while chunks are streamed
+        let limit = config.send_queue.drop_threshold;
+        let compression = self
+            .compression
+            .as_ref()
+            .map(|(threshold, _)| (*threshold, Compression::new(config.compression.level)));
+        self.streaming = Some(tokio::spawn(
+            stream_chunks(
+                game,
+                chunks,
+                compression,
+                self.send_queue.depth(),
+                limit,
+                self.chunks.clone(),
+            )
+            .in_current_span(),
+        ));
+    }
+
+    /// Moves the player, movement packets are ignored until the client confirmed the teleport
+    pub(super) fn teleport(&mut self, location: Location) {
+        self.teleports = self.teleports.wrapping_add(1);
+        self.teleport = Some(self.teleports);
+        respond!(
+            self <- PlayerPositionAndLook {
+                x: location.x,
+                y: location.y,
+                z: location.z,
+                yaw: location.yaw,
+                pitch: location.pitch,
+                flags: 0,
+                teleport_id: VarInt(self.teleports),
+                dismount: false,
+            }
+        );
+        *self.location.lock().unwrap() = location;
+    }
+
+    /// Where new players appear, in the middle of the spawn block
+    pub(super) fn spawn(&self) -> Location {
+        let spawn = self.game.spawn;
+        Location {
+            x: spawn.x as f64 + 0.5,
+            y: spawn.y as f64,
+            z: spawn.z as f64 + 0.5,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+}
+
+/// Generates, encodes and compresses chunks on the blocking pool and hands them to the connection
+/// in order. Waits for the send queue to drop below `limit` bytes before each chunk, and stops once
+/// the connection is gone.
+async fn stream_chunks(
+    game: Arc<Game>,
+    chunks: Vec<(i32, i32)>,
+    mut compression: Option<(usize, Compression)>,
+    depth: QueueDepth,
+    limit: usize,
+    tx: mpsc::Sender<PreparedPacket>,
+) {
+    for (x, z) in chunks {
+        depth.below(limit).await;
+        let game = game.clone();
+        let generated = tokio::task::spawn_blocking(move || {
+            // the lock is only held to look up and decorate chunks, connections generate terrain
+            // and encode chunks in parallel
+            let (missing, generator) = {
+                let world = game.world.lock().unwrap();
+                (world.missing(x, z), world.terrain_generator())
+            };
+            let generated = missing
+                .into_iter()
+                .map(|(x, z)| (x, z, generator.generate(x, z)))
+                .collect::<Vec<_>>();
+            let chunk = {
+                let mut world = game.world.lock().unwrap();
+                for (x, z, terrain) in generated {
+                    world.insert(x, z, terrain);
+                }
+                world.chunk(x, z).clone()
+            };
+            let light = PreparedPacket::new(game.chunk_encoder.update_light(&chunk, x, z));
+            let data = game
+                .chunk_encoder
+                .chunk_data(&chunk, x, z)
+                .and_then(PreparedPacket::new);
+            let packets = light.and_then(|light| Ok((light, data?)));
+            // the connection finds the compressed frames in the cache of the packets
+            if let (Ok((light, data)), Some((threshold, compression))) =
+                (&packets, &mut compression)
+            {
+                light.compressed(*threshold, compression);
+                data.compressed(*threshold, compression);
+            }
+            (packets, compression)
+        })
+        .await;
+        let packets = match generated {
+            Ok((packets, returned)) => {
+                compression = returned;
+                packets
+            }
+            Err(err) => {
+                error!("unable to generate chunk; x={}; z={}; err={}", x, z, err);
+                return;
+            }
+        };
+        let (light, data) = match packets {
+            Ok(packets) => packets,
+            Err(err) => {
+                error!(
+                    "unable to encode chunk; x={}; z={}; err={}; total={}",
+                    x,
+                    z,
+                    err,
+                    metrics::ENCODE_ERRORS.inc()
+                );
+                continue;
+            }
+        };
+        if tx.send(light).await.is_err() || tx.send(data).await.is_err() {
+            debug!("connection closed while streaming chunks");
+            return;
+        }
+    }
+}
@@ -1,8 +1,21 @@
+use crate::config::ConfigHandle;
+use crate::game::Game;
+use crate::metrics;
+use crate::registry::{Broadcasts, Registration, Registry};
+use crate::save::Location;
+use crate::send_queue::{self, Priority, SendError};
 use bytes::Bytes;
-use protocol::codec::DecodeError;
-use protocol::codec::Transcodeable;
-use protocol::packets::{self, encode};
-use protocol::types::{Chat, ChatObj};
+use protocol::codec::{DecodeError, EncodeError};
+use protocol::codec::{Packet, Transcodeable};
+use protocol::packets::compression::Compression;
+use protocol::packets::{self, PreparedPacket};
+use protocol::types::{Chat, ChatObj, VarInt};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tracing::*;
 use uuid::Uuid;
 
@@ -23,18 +36,59 @@ macro_rules! receive {
 
 macro_rules! respond {
     ($self:ident $(<- $val:expr $(;)?)*) => {
-        $($self
-            .send_queue
-            .send(protocol::packets::encode($val).expect("TODO: ADD GENERAL ERROR HANDLING!"))
-            .expect("TODO: add this to error handling");)*
+        $(match protocol::packets::encode($val) {
+            Ok(frame) => $self.send(frame),
+            Err(err) => $self.encode_failed(err),
+        })*
     };
 }
 
+mod join;
+
+/// Vanilla's reason for kicking players who are not on the whitelist
+pub const NOT_WHITELISTED: &str = "You are not white-listed on this server!";
+
 pub struct StateMachine {
     encryption: Box<dyn encryption::Encryption + Send + Sync>,
-    compressed: bool,
-    send_queue: tokio::sync::mpsc::UnboundedSender<Bytes>,
+    /// Threshold announced to the client and the compression state, `None` while compression is
+    /// disabled
+    compression: Option<(usize, Compression)>,
+    send_queue: send_queue::Sender,
     state: State,
+    /// Address of the client, as reported by the PROXY header if there is one
+    addr: SocketAddr,
+    config: ConfigHandle,
+    registry: Arc<Registry>,
+    game: Arc<Game>,
+    /// Handed to the registry once the player enters play
+    broadcasts: Broadcasts,
+    /// Chunks streamed in the background, sent in order of the channel
+    chunks: mpsc::Sender<PreparedPacket>,
+    /// The task streaming chunks, stopped when the connection closes
+    streaming: Option<JoinHandle<()>>,
+    registration: Option<Registration>,
+    /// Name of the player, once it logged in
+    name: Option<String>,
+    /// Id and send time of the keep-alive the client still has to answer
+    keep_alive: Option<(i64, Instant)>,
+    /// Smoothed round trip time in milliseconds, shared with the registry
+    latency: Arc<AtomicU32>,
+    /// Where the player is, as last confirmed by the client, shared with the registry
+    location: Arc<Mutex<Location>>,
+    /// Id of the teleport the client still has to confirm
+    teleport: Option<i32>,
+    /// Number of teleports so far, used as the id of the next one
+    teleports: i32,
+    closed: bool,
+}
+
+/// Coarse connection stage, as seen from outside of the state machine
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Stage {
+    Handshake,
+    Status,
+    Login,
+    Play,
 }
 
 #[derive(Debug)]
@@ -46,19 +100,217 @@ enum State {
 }
 
 impl StateMachine {
-    pub fn new(send_queue: tokio::sync::mpsc::UnboundedSender<Bytes>) -> Self {
+    pub fn new(
+        send_queue: send_queue::Sender,
+        addr: SocketAddr,
+        config: ConfigHandle,
+        registry: Arc<Registry>,
+        game: Arc<Game>,
+        broadcasts: Broadcasts,
+        chunks: mpsc::Sender<PreparedPacket>,
+    ) -> Self {
         Self {
             encryption: Box::new(encryption::PassTrough),
-            compressed: false,
+            compression: None,
             send_queue,
             state: State::Init,
+            addr,
+            config,
+            registry,
+            game,
+            broadcasts,
+            chunks,
+            streaming: None,
+            registration: None,
+            name: None,
+            keep_alive: None,
+            latency: Arc::new(AtomicU32::new(0)),
+            location: Arc::default(),
+            teleport: None,
+            teleports: 0,
+            closed: false,
+        }
+    }
+
+    pub fn stage(&self) -> Stage {
+        match &self.state {
+            State::Init => Stage::Handshake,
+            State::Status(_) => Stage::Status,
+            State::Login(_) => Stage::Login,
+            State::Play => Stage::Play,
+        }
+    }
+
+    /// Largest frame length accepted in the current state
+    pub fn max_frame_size(&self) -> usize {
+        let limits = self.config.get().limits;
+        let limit = match &self.state {
+            State::Init => limits.handshake,
+            State::Status(_) => limits.status,
+            State::Login(_) => limits.login,
+            State::Play => limits.frame,
+        };
+        limit.min(limits.frame)
+    }
+
+    /// Whether the connection should be closed, e.g. because the client got kicked
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Notifies the client about why it is about to be disconnected, if the current state allows it
+    pub fn disconnect(&mut self, reason: &str) {
+        let reason = Chat::Obj(ChatObj {
+            text: Some(reason.to_string()),
+            ..Default::default()
+        });
+        debug!(
+            "disconnecting client; state={:?}; reason={:?}",
+            &self.state, &reason
+        );
+        match &self.state {
+            State::Login(_) => {
+                respond!(self <- packets::login::clientbound::Disconnect(reason));
+            }
+            State::Play => {
+                respond!(self <- packets::play::clientbound::Disconnect(reason));
+            }
+            State::Init | State::Status(_) => (),
+        }
+        self.closed = true;
+    }
+
+    /// Applies a reloaded configuration, players who are no longer whitelisted are kicked
+    pub fn reloaded(&mut self) {
+        let config = self.config.get();
+        if let Some(name) = &self.name {
+            if !config.server.is_whitelisted(name) {
+                info!("{} is no longer whitelisted; addr={}", name, self.addr);
+                self.disconnect(NOT_WHITELISTED);
+            }
+        }
+    }
+
+    /// Sends a new keep-alive, or kicks the client if it did not answer the last one within
+    /// `timeout`
+    pub fn keep_alive(&mut self, timeout: Duration) {
+        use packets::play::clientbound::KeepAlive;
+
+        if let Some((_, sent)) = self.keep_alive {
+            if sent.elapsed() >= timeout {
+                warn!(
+                    "client did not answer keep-alive; total={}",
+                    metrics::TIMEOUTS.inc()
+                );
+                self.disconnect("Timed out");
+            }
+            return;
+        }
+        let id = rand::random();
+        respond!(self <- KeepAlive(id));
+        self.keep_alive = Some((id, Instant::now()));
+    }
+
+    /// Largest amount of bytes which were waiting to be written at once
+    pub fn queue_peak(&self) -> usize {
+        self.send_queue.peak()
+    }
+
+    /// Queues a packet which was prepared for several connections
+    pub fn send_prepared(&mut self, packet: &PreparedPacket) {
+        let priority = self.priority(&packet.frame());
+        let frame = match self.compression() {
+            Some((threshold, compression)) => packet.compressed(threshold, compression),
+            None => packet.frame(),
+        };
+        self.enqueue(frame, priority);
+    }
+
+    /// Applies compression to an encoded packet and queues it
+    fn send(&mut self, mut frame: Bytes) {
+        let priority = self.priority(&frame);
+        if let Some((threshold, compression)) = self.compression() {
+            frame = compression.compress(frame, threshold);
+        }
+        self.enqueue(frame, priority);
+    }
+
+    /// Compression state using the configured level, `None` while compression is disabled
+    fn compression(&mut self) -> Option<(usize, &mut Compression)> {
+        let level = self.config.get().compression.level;
+        let (threshold, compression) = self.compression.as_mut()?;
+        if compression.level() != level {
+            *compression = Compression::new(level);
+        }
+        Some((*threshold, compression))
+    }
+
+    fn enqueue(&mut self, mut frame: Bytes, priority: Priority) {
+        if self.closed {
+            return;
+        }
+        frame = self.encryption.encrypt(frame);
+        match self.send_queue.send(frame, priority) {
+            Ok(_) => (),
+            Err(SendError::Full { queued }) => {
+                warn!(
+                    "client is not keeping up, kicking; queued={}; total={}",
+                    queued,
+                    metrics::SLOW_CLIENTS.inc()
+                );
+                self.closed = true;
+            }
+            Err(SendError::Closed) => self.closed = true,
+        }
+    }
+
+    /// Closes the connection, the client can not continue without the packet it was meant to get
+    fn encode_failed(&mut self, err: EncodeError) {
+        error!(
+            "unable to encode packet; err={}; total={}",
+            err,
+            metrics::ENCODE_ERRORS.inc()
+        );
+        self.closed = true;
+    }
+
+    /// Sounds and particles can be left out when the client falls behind, everything else has to
+    /// arrive
+    fn priority(&self, frame: &Bytes) -> Priority {
+        use packets::play::clientbound::{
+            BlockBreakAnimation, Effect, EntityAnimation, EntitySoundEffect, NamedSoundEffect,
+            Particle, SoundEffect,
+        };
+
+        if !matches!(self.state, State::Play) {
+            return Priority::Essential;
+        }
+        let mut frame = frame.clone();
+        let id = match VarInt::decode(&mut frame).and_then(|_| VarInt::decode(&mut frame)) {
+            Ok(id) => *id,
+            Err(_) => return Priority::Essential,
+        };
+        let droppable = [
+            EntityAnimation::id(),
+            BlockBreakAnimation::id(),
+            NamedSoundEffect::id(),
+            Effect::id(),
+            Particle::id(),
+            EntitySoundEffect::id(),
+            SoundEffect::id(),
+        ];
+        if droppable.contains(&id) {
+            Priority::Droppable
+        } else {
+            Priority::Essential
         }
     }
 
     pub async fn submit(&mut self, mut packet: Bytes) -> Result<(), DecodeError> {
         packet = self.encryption.decrypt(packet);
-        if self.compressed {
-            packet = protocol::packets::decompess(packet)?
+        let max_len = self.config.get().limits.decompressed;
+        if let Some((threshold, compression)) = &mut self.compression {
+            packet = compression.decompress(packet, *threshold, max_len)?;
         }
 
         debug!(
@@ -71,13 +323,84 @@ impl StateMachine {
             State::Status(1) => self.status1(packet)?,
             State::Status(_) => unreachable!(),
             State::Login(0) => self.login0(packet)?,
-            State::Login(_) => todo!(),
-            State::Play => {}
+            // login has a single step, the state machine never enters any other
+            State::Login(_) => return Err(DecodeError::InvalidData),
+            State::Play => self.play(packet)?,
         }
 
         Ok(())
     }
 
+    fn play(&mut self, packet: Bytes) -> Result<(), DecodeError> {
+        use packets::play::serverbound::{
+            KeepAlive, PlayerPosition, PlayerPositionAndRotation, PlayerRotation, TeleportConfirm,
+        };
+
+        let (_, id) = packets::read_header(packet.clone())?;
+        match id {
+            id if id == KeepAlive::id() => {
+                let KeepAlive(id) = receive!(packet => KeepAlive);
+                match self.keep_alive.take() {
+                    Some((expected, sent)) if expected == id => {
+                        let rtt = sent.elapsed().as_millis().min(u32::MAX as u128) as u32;
+                        // smoothed the same way as vanilla, so the tab list shows the same values
+                        let latency = match self.latency.load(Ordering::Relaxed) {
+                            0 => rtt,
+                            latency => (latency * 3 + rtt) / 4,
+                        };
+                        self.latency.store(latency, Ordering::Relaxed);
+                        debug!("received keep-alive; rtt={}ms; latency={}ms", rtt, latency);
+                    }
+                    _ => {
+                        warn!("unexpected keep-alive; id={}", id);
+                        self.disconnect("Timed out");
+                    }
+                }
+            }
+            id if id == TeleportConfirm::id() => {
+                let TeleportConfirm(id) = receive!(packet => TeleportConfirm);
+                if self.teleport == Some(*id) {
+                    self.teleport = None;
+                } else {
+                    debug!("ignoring teleport confirmation; id={}", *id);
+                }
+            }
+            // the client keeps sending its old position until it confirmed a teleport
+            id if self.teleport.is_some()
+                && [
+                    PlayerPosition::id(),
+                    PlayerPositionAndRotation::id(),
+                    PlayerRotation::id(),
+                ]
+                .contains(&id) => {}
+            id if id == PlayerPosition::id() => {
+                let pkt = receive!(packet => PlayerPosition);
+                let mut location = self.location.lock().unwrap();
+                location.x = pkt.x;
+                location.y = pkt.y;
+                location.z = pkt.z;
+            }
+            id if id == PlayerPositionAndRotation::id() => {
+                let pkt = receive!(packet => PlayerPositionAndRotation);
+                *self.location.lock().unwrap() = Location {
+                    x: pkt.x,
+                    y: pkt.y,
+                    z: pkt.z,
+                    yaw: pkt.yaw,
+                    pitch: pkt.pitch,
+                };
+            }
+            id if id == PlayerRotation::id() => {
+                let pkt = receive!(packet => PlayerRotation);
+                let mut location = self.location.lock().unwrap();
+                location.yaw = pkt.yaw;
+                location.pitch = pkt.pitch;
+            }
+            _ => debug!("ignoring packet; id={:#04x}", id),
+        }
+        Ok(())
+    }
+
     fn handshake(&mut self, packet: Bytes) -> Result<(), DecodeError> {
         let packets::Handshake {
             version,
@@ -107,6 +430,7 @@ impl StateMachine {
 
         // StatusRequest is Zero Sized, this will only act as gate
         receive!(packet => StatusRequest);
+        let config = self.config.get();
         respond!(
             self <- StatusResponse(Response {
                 version: Version {
@@ -114,17 +438,12 @@ impl StateMachine {
                     protocol: protocol::VERSION,
                 },
                 players: Players {
-                    max: 1337,
-                    online: 0,
+                    max: config.server.max_players,
+                    online: self.registry.online() as i32,
                     sample: None,
                 },
-                description: Chat::Obj(ChatObj {
-                    text: Some("MCSRS!".to_string()),
-                    bold: Some(true),
-                    color: Some("gold".to_string()),
-                    ..Default::default()
-                }),
-                favicon: None,
+                description: config.server.motd.clone(),
+                favicon: config.server.favicon_data.clone(),
             })
         );
 
@@ -145,22 +464,95 @@ impl StateMachine {
     }
 
     fn login0(&mut self, packet: Bytes) -> Result<(), DecodeError> {
-        use packets::login::{clientbound::LoginSuccess, serverbound::LoginStart};
-        use packets::play::JoinGame;
+        use packets::login::{
+            clientbound::{LoginSuccess, SetCompression},
+            serverbound::LoginStart,
+        };
 
         let LoginStart(player) = receive!(packet => LoginStart);
-        info!("{} joined the game", &player.0);
+        let config = self.config.get();
+        if !config.server.is_whitelisted(&player) {
+            info!("{} is not whitelisted; addr={}", &player.0, self.addr);
+            self.disconnect(NOT_WHITELISTED);
+            return Ok(());
+        }
+        info!("{} joined the game; addr={}", &player.0, self.addr);
+        let name = player.0.to_string();
+        let uuid = offline_uuid(&name);
+        self.name = Some(name.clone());
+
+        let threshold = config.compression.threshold;
+        if threshold >= 0 {
+            respond!(self <- SetCompression(VarInt(threshold)));
+            self.compression = Some((
+                threshold as usize,
+                Compression::new(config.compression.level),
+            ));
+        }
+        // where the player left, before it is registered so it is never saved anywhere else
+        let saved = match self.game.storage.load_player(uuid) {
+            Ok(saved) => saved,
+            Err(err) => {
+                error!("unable to load player data; err={}", err);
+                None
+            }
+        };
+        *self.location.lock().unwrap() = saved.unwrap_or_else(|| self.spawn());
+        let registration = self.registry.register(
+            name,
+            uuid,
+            config.server.gamemode,
+            self.broadcasts.clone(),
+            self.latency.clone(),
+            self.send_queue.depth(),
+            self.location.clone(),
+        );
         respond!(
             self <- LoginSuccess {
                 username: player,
-                uuid: Uuid::nil(),
-            };
-            <- JoinGame {
+                uuid,
             }
         );
 
         self.state = State::Play;
+        self.join(registration.eid());
+        self.registration = Some(registration);
 
         Ok(())
     }
 }
+
+/// The UUID vanilla gives players in offline mode, a name based UUID of `OfflinePlayer:<name>`
+fn offline_uuid(name: &str) -> Uuid {
+    let hash = md5::compute(format!("OfflinePlayer:{}", name));
+    uuid::Builder::from_bytes(hash.0)
+        .set_version(uuid::Version::Md5)
+        .set_variant(uuid::Variant::RFC4122)
+        .build()
+}
+
+impl Drop for StateMachine {
+    fn drop(&mut self) {
+        if let Some(streaming) = self.streaming.take() {
+            streaming.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuids() {
+        assert_eq!(
+            offline_uuid("Notch").to_string(),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+        assert_eq!(
+            offline_uuid("alice").to_string(),
+            "40f5db53-a47a-33ee-b1f6-db0e20deded4"
+        );
+        assert_eq!(offline_uuid("alice").get_version_num(), 3);
+    }
+}
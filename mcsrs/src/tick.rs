@@ -0,0 +1,395 @@
+use crate::registry::{Broadcasts, Registry};
+use crate::save::{Location, Storage, WorldData};
+use crate::shutdown::Shutdown;
+use protocol::packets::play::clientbound::{PlayerInfo, PlayerInfoAction, TimeUpdate};
+use protocol::packets::PreparedPacket;
+use protocol::types::VarInt;
+use std::io;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::*;
+use uuid::Uuid;
+
+/// The tick loop skips ahead instead of trying to catch up once it is this far behind
+const MAX_LAG: Duration = Duration::from_secs(2);
+/// Vanilla updates the latency shown in the tab list every 30 seconds
+const LATENCY_INTERVAL: i64 = 600;
+
+/// Messages from network tasks to the tick thread
+pub enum Event {
+    /// A player entered play, carries the channel used to reach its connection
+    Joined(Broadcasts),
+    /// A player left, removed from the tab list of everyone else and saved where it left
+    Left(Uuid, Location),
+}
+
+/// Game state, only ever touched by the tick thread
+struct World {
+    age: i64,
+    time_of_day: i64,
+}
+
+impl World {
+    fn data(&self) -> WorldData {
+        WorldData {
+            age: self.age,
+            time_of_day: self.time_of_day,
+        }
+    }
+
+    fn time_update(&self) -> Option<PreparedPacket> {
+        let packet = PreparedPacket::new(TimeUpdate {
+            world_age: self.age,
+            time_of_day: self.time_of_day,
+        });
+        match packet {
+            Ok(packet) => Some(packet),
+            Err(err) => {
+                error!("unable to encode time update; err={}", err);
+                None
+            }
+        }
+    }
+}
+
+/// Runs the tick loop on its own thread until the server shuts down, `world` is where the last
+/// run stopped. Everything is saved every `save_interval` and once more after the last tick.
+pub fn spawn(
+    tick_rate: u32,
+    world: WorldData,
+    storage: Storage,
+    save_interval: Duration,
+    registry: Arc<Registry>,
+    events: Receiver<Event>,
+    shutdown: Arc<Shutdown>,
+) -> io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("mcsrs-tick".to_string())
+        .spawn(move || {
+            run(
+                tick_rate,
+                world,
+                storage,
+                save_interval,
+                registry,
+                events,
+                shutdown,
+            )
+        })
+}
+
+fn run(
+    tick_rate: u32,
+    world: WorldData,
+    storage: Storage,
+    save_interval: Duration,
+    registry: Arc<Registry>,
+    events: Receiver<Event>,
+    shutdown: Arc<Shutdown>,
+) {
+    let period = Duration::from_secs(1) / tick_rate;
+    let save_every =
+        (save_interval.as_nanos() / period.as_nanos()).clamp(1, i64::MAX as u128) as i64;
+    let mut world = World {
+        age: world.age,
+        time_of_day: world.time_of_day,
+    };
+    let mut next = Instant::now();
+
+    while !shutdown.is_triggered() {
+        while let Ok(event) = events.try_recv() {
+            handle(&world, &registry, &storage, event);
+        }
+
+        world.age += 1;
+        world.time_of_day = (world.time_of_day + 1) % 24000;
+        // the client keeps its clock running by itself, vanilla only syncs it every 20 ticks
+        if world.age % 20 == 0 {
+            if let Some(packet) = world.time_update() {
+                registry.broadcast(packet);
+            }
+        }
+        if world.age % LATENCY_INTERVAL == 0 {
+            broadcast_latency(&registry);
+        }
+        if world.age % save_every == 0 {
+            save(&world, &registry, &storage);
+        }
+
+        next += period;
+        let now = Instant::now();
+        if next > now {
+            std::thread::sleep(next - now);
+        } else if now - next > MAX_LAG {
+            warn!(
+                "can't keep up, skipping ticks; behind={:?}; age={}",
+                now - next,
+                world.age
+            );
+            next = now;
+        }
+    }
+
+    // players who left are saved by their event, everyone else is still registered
+    for event in events.try_iter() {
+        handle(&world, &registry, &storage, event);
+    }
+    let saved = save(&world, &registry, &storage);
+    info!(
+        "tick loop stopped; age={}; saved={}; players={}",
+        world.age,
+        saved,
+        registry.online()
+    );
+}
+
+/// Writes the world and every online player, returns whether all of it was saved
+fn save(world: &World, registry: &Registry, storage: &Storage) -> bool {
+    let mut saved = true;
+    if let Err(err) = storage.save_world(&world.data()) {
+        error!("unable to save world; err={}", err);
+        saved = false;
+    }
+    for player in registry.players() {
+        if let Err(err) = storage.save_player(player.uuid, &player.location) {
+            error!("unable to save player; name={}; err={}", player.name, err);
+            saved = false;
+        }
+    }
+    debug!("saved world; age={}; saved={}", world.age, saved);
+    saved
+}
+
+fn handle(world: &World, registry: &Registry, storage: &Storage, event: Event) {
+    match event {
+        Event::Joined(broadcasts) => {
+            if let Some(packet) = world.time_update() {
+                let _ = broadcasts.send(Arc::new(packet));
+            }
+        }
+        Event::Left(uuid, location) => {
+            if let Err(err) = storage.save_player(uuid, &location) {
+                error!("unable to save player; uuid={}; err={}", uuid, err);
+            }
+            match PreparedPacket::new(PlayerInfo(PlayerInfoAction::RemovePlayer(vec![uuid]))) {
+                Ok(packet) => registry.broadcast(packet),
+                Err(err) => error!("unable to encode player info; err={}", err),
+            }
+        }
+    }
+}
+
+fn broadcast_latency(registry: &Registry) {
+    let latencies = registry
+        .players()
+        .into_iter()
+        .map(|player| (player.uuid, VarInt(player.latency as i32)))
+        .collect::<Vec<_>>();
+    if latencies.is_empty() {
+        return;
+    }
+    match PreparedPacket::new(PlayerInfo(PlayerInfoAction::UpdateLatency(latencies))) {
+        Ok(packet) => registry.broadcast(packet),
+        Err(err) => error!("unable to encode player info; err={}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameMode;
+    use crate::registry::Registration;
+    use crate::send_queue;
+    use protocol::codec::Packet;
+    use protocol::packets::{decode, read_header};
+    use std::sync::atomic::AtomicU32;
+    use std::sync::mpsc as std_mpsc;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+
+    type Received = mpsc::UnboundedReceiver<Arc<PreparedPacket>>;
+
+    fn register(registry: &Arc<Registry>, name: &str) -> (Registration, Received) {
+        let (broadcasts, received) = mpsc::unbounded_channel();
+        let (queue, _) = send_queue::channel(Default::default());
+        let registration = registry.register(
+            name.to_string(),
+            Uuid::from_u128(name.len() as u128),
+            GameMode::Creative,
+            broadcasts,
+            Arc::new(AtomicU32::new(42)),
+            queue.depth(),
+            Arc::default(),
+        );
+        (registration, received)
+    }
+
+    fn directory(test: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("mcsrs-tick-{}-{}", test, std::process::id()))
+    }
+
+    /// The tab list changes a client was sent once it left, other packets are skipped
+    fn tab_list(mut received: Received) -> Vec<PlayerInfoAction> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let mut actions = vec![];
+        while let Some(packet) = runtime.block_on(received.recv()) {
+            let frame = packet.frame();
+            if read_header(&mut frame.clone()).unwrap().1 == PlayerInfo::id() {
+                actions.push(decode::<PlayerInfo, _>(frame).unwrap().0);
+            }
+        }
+        actions
+    }
+
+    #[test]
+    fn added_before_latency() {
+        let (tx, events) = std_mpsc::channel();
+        let registry = Registry::new(tx);
+        let world = World {
+            age: 0,
+            time_of_day: 0,
+        };
+        let storage = Storage::new(&directory("latency"));
+        let process = || {
+            for event in events.try_iter() {
+                handle(&world, &registry, &storage, event);
+            }
+        };
+
+        let (alice, alice_received) = register(&registry, "alice");
+        let (bob, bob_received) = register(&registry, "bob");
+        process();
+        broadcast_latency(&registry);
+
+        match registry.tab_list().0 {
+            PlayerInfoAction::AddPlayer(players) => {
+                let names = players.iter().map(|(_, player)| &*player.name.0);
+                assert_eq!(names.collect::<Vec<_>>(), ["alice", "bob"]);
+            }
+            _ => panic!("not adding players"),
+        }
+        drop((alice, bob));
+        process();
+
+        let actions = tab_list(alice_received);
+        assert!(
+            matches!(
+                &actions[..],
+                [PlayerInfoAction::AddPlayer(added), PlayerInfoAction::UpdateLatency(both), ..]
+                    if added.len() == 1
+                    && added[0].0 == Uuid::from_u128(3)
+                    && added[0].1.name.0 == "bob"
+                    && *added[0].1.gamemode == 1
+                    && *added[0].1.ping == 42
+                    && both.len() == 2
+            ),
+            "{} actions",
+            actions.len()
+        );
+        // bob learns about alice from the tab list sent on join, not through broadcasts
+        let actions = tab_list(bob_received);
+        assert!(matches!(&actions[..], [PlayerInfoAction::UpdateLatency(_)]));
+        std::fs::remove_dir_all(directory("latency")).unwrap();
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let (tx, events) = std_mpsc::channel();
+        let registry = Registry::new(tx);
+        let world = World {
+            age: 0,
+            time_of_day: 0,
+        };
+        let storage = Storage::new(&directory("removed"));
+        let process = || {
+            for event in events.try_iter() {
+                handle(&world, &registry, &storage, event);
+            }
+        };
+
+        let (alice, alice_received) = register(&registry, "alice");
+        let (bob, _) = register(&registry, "bob");
+        process();
+        drop(bob);
+        process();
+        // bob reconnects
+        let (bob, _) = register(&registry, "bob");
+        process();
+        drop(alice);
+        process();
+
+        let bob_uuid = Uuid::from_u128(3);
+        let actions = tab_list(alice_received);
+        assert!(
+            matches!(
+                &actions[..],
+                [
+                    PlayerInfoAction::AddPlayer(added),
+                    PlayerInfoAction::RemovePlayer(removed),
+                    PlayerInfoAction::AddPlayer(again),
+                ] if added.len() == 1
+                    && added[0].0 == bob_uuid
+                    && removed[..] == [bob_uuid]
+                    && again.len() == 1
+                    && again[0].0 == bob_uuid
+            ),
+            "{} actions",
+            actions.len()
+        );
+        assert!(matches!(
+            registry.tab_list().0,
+            PlayerInfoAction::AddPlayer(players) if players.len() == 1 && players[0].0 == bob_uuid
+        ));
+        drop(bob);
+        std::fs::remove_dir_all(directory("removed")).unwrap();
+    }
+
+    #[test]
+    fn saving() {
+        let (tx, events) = std_mpsc::channel();
+        let registry = Registry::new(tx);
+        let storage = Storage::new(&directory("saving"));
+        let world = World {
+            age: 1200,
+            time_of_day: 6000,
+        };
+        let alice_location = Arc::new(Mutex::new(Location::default()));
+        let bob_location = Arc::new(Mutex::new(Location::default()));
+        let (queue, _) = send_queue::channel(Default::default());
+        let register = |name: &str, location: &Arc<Mutex<Location>>| {
+            registry.register(
+                name.to_string(),
+                Uuid::from_u128(name.len() as u128),
+                GameMode::Survival,
+                mpsc::unbounded_channel().0,
+                Arc::default(),
+                queue.depth(),
+                location.clone(),
+            )
+        };
+        let alice = register("alice", &alice_location);
+        let bob = register("bob", &bob_location);
+
+        alice_location.lock().unwrap().x = 10.5;
+        bob_location.lock().unwrap().y = 80.0;
+        assert!(save(&world, &registry, &storage));
+        assert_eq!(storage.load_world().unwrap(), Some(world.data()));
+        let alice_uuid = Uuid::from_u128(5);
+        let bob_uuid = Uuid::from_u128(3);
+        assert_eq!(storage.load_player(alice_uuid).unwrap().unwrap().x, 10.5);
+        assert_eq!(storage.load_player(bob_uuid).unwrap().unwrap().y, 80.0);
+
+        // players who left are saved where they left, even if nobody is online anymore
+        bob_location.lock().unwrap().y = 90.0;
+        drop((alice, bob));
+        for event in events.try_iter() {
+            handle(&world, &registry, &storage, event);
+        }
+        assert_eq!(storage.load_player(bob_uuid).unwrap().unwrap().y, 90.0);
+        std::fs::remove_dir_all(directory("saving")).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use protocol::codec::Transcodeable;
+use protocol::packets::compression::{Compression, Compressor, Flate2};
+use protocol::types::VarInt;
+
+const LEVEL: u32 = 4;
+const SIZES: [usize; 3] = [1 << 12, 1 << 15, 1 << 17];
+
+/// Mostly repetitive data with some noise, compresses about as well as chunk sections do
+fn payload(len: usize) -> Bytes {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut data = BytesMut::with_capacity(len + 8);
+    VarInt((len + 1) as i32).encode(&mut data).unwrap();
+    // chunk data id
+    VarInt(0x22).encode(&mut data).unwrap();
+    for i in 0..len {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        data.put_u8(if state & 7 == 0 {
+            state as u8
+        } else {
+            (i % 16) as u8
+        });
+    }
+    data.freeze()
+}
+
+type Backend = (&'static str, fn() -> Box<dyn Compressor>);
+
+fn backends() -> Vec<Backend> {
+    vec![
+        ("flate2", || Box::new(Flate2::new(LEVEL))),
+        #[cfg(feature = "libdeflate")]
+        ("libdeflate", || {
+            Box::new(protocol::packets::compression::Libdeflate::new(LEVEL))
+        }),
+    ]
+}
+
+fn compress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compress");
+    for size in SIZES.iter().copied() {
+        let frame = payload(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        for (name, backend) in backends() {
+            let mut compression = Compression::with_compressor(backend(), LEVEL);
+            group.bench_with_input(BenchmarkId::new(name, size), &frame, |b, frame| {
+                b.iter(|| compression.compress(frame.clone(), 0))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn decompress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decompress");
+    for size in SIZES.iter().copied() {
+        let compressed = Compression::with_compressor(Box::new(Flate2::new(LEVEL)), LEVEL)
+            .compress(payload(size), 0);
+        group.throughput(Throughput::Bytes(size as u64));
+        for (name, backend) in backends() {
+            let mut compression = Compression::with_compressor(backend(), LEVEL);
+            group.bench_with_input(BenchmarkId::new(name, size), &compressed, |b, frame| {
+                b.iter(|| {
+                    compression
+                        .decompress(frame.clone(), 0, usize::MAX)
+                        .unwrap()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compress, decompress);
+criterion_main!(benches);
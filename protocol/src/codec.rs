@@ -8,6 +8,7 @@ pub enum DecodeError {
     InvalidData,
     ToLittleData,
     OversizeString { max: usize, recv: usize },
+    OversizePacket { max: usize, recv: usize },
 }
 
 #[derive(Debug)]
@@ -106,6 +107,48 @@ impl_int! {
     [f64, put_f64, get_f64];
 }
 
+impl<A: Transcodeable, C: Transcodeable> Transcodeable for (A, C) {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.0.encode(&mut buf)?;
+        self.1.encode(&mut buf)
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        Ok((A::decode(&mut buf)?, C::decode(&mut buf)?))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.size_hint()? + self.1.size_hint()?)
+    }
+}
+
+/// Prefixed with a boolean telling if the value is present
+impl<T: Transcodeable> Transcodeable for Option<T> {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        match self {
+            Some(value) => {
+                true.encode(&mut buf)?;
+                value.encode(buf)
+            }
+            None => false.encode(buf),
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        Ok(match bool::decode(&mut buf)? {
+            true => Some(T::decode(buf)?),
+            false => None,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(match self {
+            Some(value) => 1 + value.size_hint()?,
+            None => 1,
+        })
+    }
+}
+
 impl Display for EncodeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -3,7 +3,9 @@
 pub mod codec;
 pub mod iob;
 pub mod packets;
+pub mod registry;
 pub mod types;
+pub mod world;
 
 pub const VERSION: i32 = 756;
-pub const MC_VERSION: &'static str = "1.17.1";
+pub const MC_VERSION: &str = "1.17.1";
@@ -0,0 +1,218 @@
+use crate::codec::{DecodeError, Transcodeable};
+use crate::packets::read_compression_header;
+use crate::types::VarInt;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// Room left in front of the compressed data for the frame length and the data length
+const HEADER_ROOM: usize = 10;
+
+/// A zlib implementation, instances keep their state between packets
+pub trait Compressor: Send {
+    /// Upper bound for the compressed size of `len` bytes
+    fn compress_bound(&mut self, len: usize) -> usize;
+    /// Compresses `input` into `output` and returns the compressed length, `output` is at least
+    /// [`Compressor::compress_bound`] bytes long
+    fn compress(&mut self, input: &[u8], output: &mut [u8]) -> usize;
+    /// Inflates `input` into `output`, which has to be filled exactly
+    fn decompress(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), DecodeError>;
+}
+
+pub struct Flate2 {
+    compress: flate2::Compress,
+    decompress: flate2::Decompress,
+}
+
+impl Flate2 {
+    pub fn new(level: u32) -> Self {
+        Self {
+            compress: flate2::Compress::new(flate2::Compression::new(level), true),
+            decompress: flate2::Decompress::new(true),
+        }
+    }
+}
+
+impl Compressor for Flate2 {
+    fn compress_bound(&mut self, len: usize) -> usize {
+        // the conservative deflateBound from zlib plus the zlib header and checksum
+        len + ((len + 7) >> 3) + ((len + 63) >> 6) + 5 + 6
+    }
+
+    fn compress(&mut self, input: &[u8], output: &mut [u8]) -> usize {
+        self.compress.reset();
+        let status = self
+            .compress
+            .compress(input, output, flate2::FlushCompress::Finish)
+            .expect("compressing into a large enough buffer can not fail");
+        assert_eq!(status, flate2::Status::StreamEnd);
+        self.compress.total_out() as usize
+    }
+
+    fn decompress(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), DecodeError> {
+        self.decompress.reset(true);
+        // output is exactly as long as announced, anything past that makes the stream not end
+        match self
+            .decompress
+            .decompress(input, output, flate2::FlushDecompress::Finish)
+        {
+            Ok(flate2::Status::StreamEnd)
+                if self.decompress.total_out() as usize == output.len() =>
+            {
+                Ok(())
+            }
+            _ => Err(DecodeError::InvalidData),
+        }
+    }
+}
+
+#[cfg(feature = "libdeflate")]
+pub struct Libdeflate {
+    compressor: libdeflater::Compressor,
+    decompressor: libdeflater::Decompressor,
+}
+
+#[cfg(feature = "libdeflate")]
+impl Libdeflate {
+    pub fn new(level: u32) -> Self {
+        let level = libdeflater::CompressionLvl::new(level as i32)
+            .unwrap_or_else(|_| libdeflater::CompressionLvl::fastest());
+        Self {
+            compressor: libdeflater::Compressor::new(level),
+            decompressor: libdeflater::Decompressor::new(),
+        }
+    }
+}
+
+#[cfg(feature = "libdeflate")]
+impl Compressor for Libdeflate {
+    fn compress_bound(&mut self, len: usize) -> usize {
+        self.compressor.zlib_compress_bound(len)
+    }
+
+    fn compress(&mut self, input: &[u8], output: &mut [u8]) -> usize {
+        self.compressor
+            .zlib_compress(input, output)
+            .expect("compressing into a large enough buffer can not fail")
+    }
+
+    fn decompress(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), DecodeError> {
+        match self.decompressor.zlib_decompress(input, output) {
+            Ok(len) if len == output.len() => Ok(()),
+            _ => Err(DecodeError::InvalidData),
+        }
+    }
+}
+
+/// Compression state of a single connection.
+///
+/// Output is written into a buffer which is reused between packets, so only the compressor
+/// itself allocates when it is created.
+pub struct Compression {
+    compressor: Box<dyn Compressor>,
+    level: u32,
+    buf: BytesMut,
+}
+
+impl Compression {
+    /// Uses libdeflate if the feature is enabled and flate2 otherwise
+    pub fn new(level: u32) -> Self {
+        #[cfg(feature = "libdeflate")]
+        let compressor = Box::new(Libdeflate::new(level));
+        #[cfg(not(feature = "libdeflate"))]
+        let compressor = Box::new(Flate2::new(level));
+        Self::with_compressor(compressor, level)
+    }
+
+    pub fn with_compressor(compressor: Box<dyn Compressor>, level: u32) -> Self {
+        Self {
+            compressor,
+            level,
+            buf: BytesMut::new(),
+        }
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Converts a frame produced by [`encode`](super::encode) into the compressed format,
+    /// packets shorter than `threshold` are only wrapped but not compressed
+    pub fn compress(&mut self, mut frame: Bytes, threshold: usize) -> Bytes {
+        VarInt::decode(&mut frame).expect("frame has to start with its length");
+        let data = frame;
+
+        if data.len() < threshold {
+            // a data length of 0 tells the receiver that the packet is not compressed
+            let dlen = VarInt(0);
+            let len = VarInt((data.len() + dlen.size_hint().unwrap()) as i32);
+
+            self.buf.reserve(*len as usize + len.size_hint().unwrap());
+            len.encode(&mut self.buf).unwrap();
+            dlen.encode(&mut self.buf).unwrap();
+            self.buf.put(data);
+            return self.buf.split().freeze();
+        }
+
+        let bound = self.compressor.compress_bound(data.len());
+        self.buf.resize(HEADER_ROOM + bound, 0);
+        let clen = self
+            .compressor
+            .compress(&data, &mut self.buf[HEADER_ROOM..]);
+        self.buf.truncate(HEADER_ROOM + clen);
+
+        // the header goes right in front of the compressed data, so it never has to be moved
+        let dlen = VarInt(data.len() as i32);
+        let len = VarInt((clen + dlen.size_hint().unwrap()) as i32);
+        let start = HEADER_ROOM - len.size_hint().unwrap() - dlen.size_hint().unwrap();
+        let mut header = &mut self.buf[start..HEADER_ROOM];
+        len.encode(&mut header).unwrap();
+        dlen.encode(&mut header).unwrap();
+        self.buf.advance(start);
+        self.buf.split().freeze()
+    }
+
+    /// Turns a compressed frame back into a regular frame, so it can be passed to
+    /// [`decode`](super::decode). Like vanilla, packets shorter than `threshold` have to be sent
+    /// uncompressed.
+    pub fn decompress(
+        &mut self,
+        mut data: Bytes,
+        threshold: usize,
+        max_len: usize,
+    ) -> Result<Bytes, DecodeError> {
+        let (rl, dl) = read_compression_header(&mut data)?;
+        let remaining = rl.saturating_sub(VarInt(dl as i32).size_hint().unwrap());
+        if data.remaining() < remaining {
+            return Err(DecodeError::ToLittleData);
+        }
+        if dl > max_len {
+            return Err(DecodeError::OversizePacket {
+                max: max_len,
+                recv: dl,
+            });
+        }
+        if dl != 0 && dl < threshold {
+            return Err(DecodeError::InvalidData);
+        }
+
+        if dl == 0 {
+            let len = VarInt(remaining as i32);
+            self.buf.reserve(remaining + len.size_hint().unwrap());
+            len.encode(&mut self.buf).unwrap();
+            self.buf.put(data.slice(..remaining));
+            return Ok(self.buf.split().freeze());
+        }
+
+        let len = VarInt(dl as i32);
+        let start = len.size_hint().unwrap();
+        self.buf.resize(start + dl, 0);
+        len.encode(&mut self.buf[..start]).unwrap();
+        if let Err(err) = self
+            .compressor
+            .decompress(&data[..remaining], &mut self.buf[start..])
+        {
+            self.buf.clear();
+            return Err(err);
+        }
+        Ok(self.buf.split().freeze())
+    }
+}
@@ -20,7 +20,7 @@ pub mod clientbound {
     }
 
     #[packet(id = 0x03)]
-    pub struct SetCompression(VarInt);
+    pub struct SetCompression(pub VarInt);
 }
 
 pub mod serverbound {
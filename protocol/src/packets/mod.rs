@@ -1,3 +1,4 @@
+pub mod compression;
 pub mod login;
 pub mod play;
 pub mod status;
@@ -5,10 +6,14 @@ pub mod status;
 use crate::codec::{DecodeError, EncodeError, Packet, Transcodeable};
 use crate::types::{StringN, VarInt};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use flate2::Compression;
+use compression::Compression;
 use protocol_derive::packet;
-use std::io::Read;
-use tracing::*;
+use std::sync::Mutex;
+
+/// Largest frame the vanilla client and server accept, the length prefix is at most a 3 byte VarInt
+pub const MAX_FRAME_SIZE: usize = (1 << 21) - 1;
+/// Largest packet the vanilla server is willing to inflate
+pub const MAX_DECOMPRESSED_SIZE: usize = 1 << 23;
 
 #[packet(id = 0x00)]
 #[derive(Debug)]
@@ -54,8 +59,11 @@ pub fn encode(packet: impl Packet) -> Result<Bytes, EncodeError> {
 }
 
 pub fn decode<P: Packet, B: Buf>(mut buf: B) -> Result<P, DecodeError> {
-    let (mut len, id) = read_header(&mut buf)?;
-    len -= VarInt(len as i32).size_hint().unwrap();
+    let (len, id) = read_header(&mut buf)?;
+    // the length includes the packet id
+    let len = len
+        .checked_sub(VarInt(id).size_hint().unwrap())
+        .ok_or(DecodeError::InvalidData)?;
     if id != P::id() {
         return Err(DecodeError::InvalidData);
     }
@@ -71,55 +79,40 @@ pub fn read_header<B: Buf>(mut buf: B) -> Result<(usize, i32), DecodeError> {
     Ok((*len as usize, *id))
 }
 
-lazy_static::lazy_static! {
-    pub static ref COMPRESSION_LEVEL: u32 = {
-        std::env::var("MC_GZIP_LEVEL").map_or(4, |var| var.parse::<u32>().map(|v| match v {
-            0..=9 => v,
-            x => {
-                eprintln!("MC_GZIP_LEVEL is set INVALIDLY, must be a number between 0-9; defaulting to 4");
-                4
-            }
-        }).unwrap_or_else(|_| {
-            eprintln!("MC_GZIP_LEVEL is set INVALIDLY, must be a number between 0-9; defaulting to 4");
-            4
-        }))
-    };
-}
-
-pub fn compress(data: Bytes) -> Bytes {
-    let mut cbuf = Vec::with_capacity(data.len());
-    let mut zlib =
-        flate2::bufread::ZlibEncoder::new(data.chunk(), Compression::new(*COMPRESSION_LEVEL));
-    let _ = zlib
-        .read_to_end(&mut cbuf)
-        .expect("unable to compress data");
-
-    let dlen = VarInt(data.len() as i32);
-    let len = VarInt((cbuf.len() + dlen.size_hint().unwrap()) as i32);
-
-    let mut buf = BytesMut::with_capacity(*len as usize + len.size_hint().unwrap());
-    len.encode(&mut buf).unwrap();
-    dlen.encode(&mut buf).unwrap();
-    buf.put_slice(cbuf.as_slice());
-    buf.freeze()
+/// A packet which is encoded once and can then be sent to any number of connections.
+///
+/// The compressed form is created on first use and cached per threshold and level, as those are
+/// usually the same for every connection.
+pub struct PreparedPacket {
+    frame: Bytes,
+    compressed: Mutex<Vec<((usize, u32), Bytes)>>,
 }
 
-pub fn decompess(mut data: Bytes) -> Result<Bytes, DecodeError> {
-    let (rl, dl) = read_compression_header(&mut data)?;
-    if data.remaining() < (rl - VarInt(rl as i32).size_hint().unwrap()) {
-        return Err(DecodeError::ToLittleData);
+impl PreparedPacket {
+    pub fn new(packet: impl Packet) -> Result<Self, EncodeError> {
+        Ok(Self {
+            frame: encode(packet)?,
+            compressed: Mutex::new(Vec::new()),
+        })
     }
 
-    let mut buf = Vec::with_capacity(dl);
-    let mut zlib = flate2::bufread::ZlibDecoder::new(data.chunk());
-    let read = zlib
-        .read_to_end(&mut buf)
-        .map_err(|_| DecodeError::InvalidData)?;
-    if read != dl {
-        return Err(DecodeError::InvalidData);
+    /// The frame as produced by [`encode`]
+    pub fn frame(&self) -> Bytes {
+        self.frame.clone()
     }
 
-    Ok(Bytes::from(buf))
+    /// The frame as produced by [`Compression::compress`]
+    pub fn compressed(&self, threshold: usize, compression: &mut Compression) -> Bytes {
+        let key = (threshold, compression.level());
+        // the lock is held while compressing, so concurrent senders don't compress it twice
+        let mut compressed = self.compressed.lock().unwrap();
+        if let Some((_, frame)) = compressed.iter().find(|(cached, _)| *cached == key) {
+            return frame.clone();
+        }
+        let frame = compression.compress(self.frame.clone(), threshold);
+        compressed.push((key, frame.clone()));
+        frame
+    }
 }
 
 pub fn read_compression_header<B: Buf>(mut buf: B) -> Result<(usize, usize), DecodeError> {
@@ -0,0 +1,1737 @@
+use crate::codec::{DecodeError, EncodeError, Packet, Transcodeable};
+use crate::types::{
+    Angle, Array, BitSet, Chat, Identifier, MaxString, Metadata, OptionalNbt, ParticleData,
+    Position, Remaining, Slot, StringN, VarInt, VarLong, VibrationDestination,
+};
+use bytes::{Buf, BufMut};
+use protocol_derive::{packet, Transcodeable};
+use uuid::Uuid;
+
+#[packet(id = 0x00)]
+pub struct SpawnEntity {
+    pub eid: VarInt,
+    pub uuid: Uuid,
+    pub kind: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub pitch: Angle,
+    pub yaw: Angle,
+    pub data: i32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+#[packet(id = 0x01)]
+pub struct SpawnExperienceOrb {
+    pub id: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub count: i16,
+}
+
+#[packet(id = 0x02)]
+pub struct SpawnLivingEntity {
+    pub eid: VarInt,
+    pub uuid: Uuid,
+    pub kind: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub head_pitch: Angle,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+#[packet(id = 0x03)]
+pub struct SpawnPainting {
+    pub eid: VarInt,
+    pub uuid: Uuid,
+    pub motive: VarInt,
+    /// Center of the painting
+    pub location: Position,
+    pub direction: u8,
+}
+
+#[packet(id = 0x04)]
+pub struct SpawnPlayer {
+    pub eid: VarInt,
+    pub uuid: Uuid,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: Angle,
+    pub pitch: Angle,
+}
+
+#[packet(id = 0x05)]
+pub struct SculkVibrationSignal {
+    pub source: Position,
+    pub destination: VibrationDestination,
+    pub arrival_ticks: VarInt,
+}
+
+#[packet(id = 0x06)]
+pub struct EntityAnimation {
+    pub eid: VarInt,
+    pub animation: u8,
+}
+
+#[packet(id = 0x07)]
+pub struct Statistics(pub Array<Statistic, VarInt>);
+
+#[derive(Transcodeable)]
+pub struct Statistic {
+    pub category: VarInt,
+    pub id: VarInt,
+    pub value: VarInt,
+}
+
+#[packet(id = 0x08)]
+pub struct AcknowledgePlayerDigging {
+    pub location: Position,
+    pub block: VarInt,
+    pub status: VarInt,
+    pub successful: bool,
+}
+
+#[packet(id = 0x09)]
+pub struct BlockBreakAnimation {
+    pub eid: VarInt,
+    pub location: Position,
+    /// 0 to 9, anything else removes the animation
+    pub stage: i8,
+}
+
+#[packet(id = 0x0A)]
+pub struct BlockEntityData {
+    pub location: Position,
+    pub action: u8,
+    pub data: OptionalNbt,
+}
+
+#[packet(id = 0x0B)]
+pub struct BlockAction {
+    pub location: Position,
+    pub action: u8,
+    pub param: u8,
+    pub block: VarInt,
+}
+
+#[packet(id = 0x0C)]
+pub struct BlockChange {
+    pub location: Position,
+    pub state: VarInt,
+}
+
+pub struct BossBar {
+    pub uuid: Uuid,
+    pub action: BossBarAction,
+}
+
+pub enum BossBarAction {
+    Add {
+        title: Chat,
+        health: f32,
+        color: VarInt,
+        division: VarInt,
+        flags: u8,
+    },
+    Remove,
+    UpdateHealth(f32),
+    UpdateTitle(Chat),
+    UpdateStyle {
+        color: VarInt,
+        division: VarInt,
+    },
+    UpdateFlags(u8),
+}
+
+impl Packet for BossBar {
+    fn id() -> i32 {
+        0x0D
+    }
+}
+
+impl Transcodeable for BossBar {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.uuid.encode(&mut buf)?;
+        match &self.action {
+            BossBarAction::Add {
+                title,
+                health,
+                color,
+                division,
+                flags,
+            } => {
+                VarInt(0).encode(&mut buf)?;
+                title.encode(&mut buf)?;
+                health.encode(&mut buf)?;
+                color.encode(&mut buf)?;
+                division.encode(&mut buf)?;
+                flags.encode(buf)
+            }
+            BossBarAction::Remove => VarInt(1).encode(buf),
+            BossBarAction::UpdateHealth(health) => {
+                VarInt(2).encode(&mut buf)?;
+                health.encode(buf)
+            }
+            BossBarAction::UpdateTitle(title) => {
+                VarInt(3).encode(&mut buf)?;
+                title.encode(buf)
+            }
+            BossBarAction::UpdateStyle { color, division } => {
+                VarInt(4).encode(&mut buf)?;
+                color.encode(&mut buf)?;
+                division.encode(buf)
+            }
+            BossBarAction::UpdateFlags(flags) => {
+                VarInt(5).encode(&mut buf)?;
+                flags.encode(buf)
+            }
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let uuid = Uuid::decode(&mut buf)?;
+        let action = match *VarInt::decode(&mut buf)? {
+            0 => BossBarAction::Add {
+                title: Chat::decode(&mut buf)?,
+                health: f32::decode(&mut buf)?,
+                color: VarInt::decode(&mut buf)?,
+                division: VarInt::decode(&mut buf)?,
+                flags: u8::decode(buf)?,
+            },
+            1 => BossBarAction::Remove,
+            2 => BossBarAction::UpdateHealth(f32::decode(buf)?),
+            3 => BossBarAction::UpdateTitle(Chat::decode(buf)?),
+            4 => BossBarAction::UpdateStyle {
+                color: VarInt::decode(&mut buf)?,
+                division: VarInt::decode(buf)?,
+            },
+            5 => BossBarAction::UpdateFlags(u8::decode(buf)?),
+            _ => return Err(DecodeError::InvalidData),
+        };
+        Ok(BossBar { uuid, action })
+    }
+}
+
+#[packet(id = 0x0E)]
+pub struct ServerDifficulty {
+    pub difficulty: u8,
+    pub locked: bool,
+}
+
+#[packet(id = 0x0F)]
+pub struct ChatMessage {
+    pub message: Chat,
+    /// 0 for chat, 1 for system messages and 2 above the hotbar
+    pub position: i8,
+    pub sender: Uuid,
+}
+
+#[packet(id = 0x10)]
+pub struct ClearTitles {
+    pub reset: bool,
+}
+
+#[packet(id = 0x11)]
+pub struct TabComplete {
+    pub id: VarInt,
+    /// Start of the text to replace
+    pub start: VarInt,
+    pub length: VarInt,
+    pub matches: Array<TabMatch, VarInt>,
+}
+
+#[derive(Transcodeable)]
+pub struct TabMatch {
+    pub text: MaxString,
+    pub tooltip: Option<Chat>,
+}
+
+#[packet(id = 0x12)]
+pub struct DeclareCommands {
+    pub nodes: Array<CommandNode, VarInt>,
+    pub root: VarInt,
+}
+
+pub const NODE_ROOT: u8 = 0x00;
+pub const NODE_LITERAL: u8 = 0x01;
+pub const NODE_ARGUMENT: u8 = 0x02;
+pub const NODE_EXECUTABLE: u8 = 0x04;
+
+/// A node of the command graph, the node type and executable bit are stored in `flags`, the
+/// remaining flag bits follow from the optional fields
+pub struct CommandNode {
+    pub flags: u8,
+    pub children: Array<VarInt, VarInt>,
+    pub redirect: Option<VarInt>,
+    /// Only present on literal and argument nodes
+    pub name: Option<MaxString>,
+    /// Only present on argument nodes
+    pub parser: Option<(Identifier, ParserProperties)>,
+    /// Only present on argument nodes
+    pub suggestions: Option<Identifier>,
+}
+
+pub enum ParserProperties {
+    None,
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Float {
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Long {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    /// 0 for a single word, 1 for a quotable phrase and 2 for the rest of the input
+    String(VarInt),
+    /// `minecraft:entity` and `minecraft:score_holder` flags
+    Flags(u8),
+    /// `minecraft:range`, if decimals are allowed
+    Range(bool),
+}
+
+const NODE_TYPE: u8 = 0x03;
+const NODE_REDIRECT: u8 = 0x08;
+const NODE_SUGGESTIONS: u8 = 0x10;
+
+fn encode_bounds<T: Transcodeable, B: BufMut>(
+    min: &Option<T>,
+    max: &Option<T>,
+    mut buf: B,
+) -> Result<(), EncodeError> {
+    (min.is_some() as u8 | (max.is_some() as u8) << 1).encode(&mut buf)?;
+    if let Some(min) = min {
+        min.encode(&mut buf)?;
+    }
+    if let Some(max) = max {
+        max.encode(&mut buf)?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::type_complexity)]
+fn decode_bounds<T: Transcodeable, B: Buf>(
+    mut buf: B,
+) -> Result<(Option<T>, Option<T>), DecodeError> {
+    let flags = u8::decode(&mut buf)?;
+    let min = match flags & 0x01 {
+        0 => None,
+        _ => Some(T::decode(&mut buf)?),
+    };
+    let max = match flags & 0x02 {
+        0 => None,
+        _ => Some(T::decode(&mut buf)?),
+    };
+    Ok((min, max))
+}
+
+impl ParserProperties {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        match self {
+            ParserProperties::None => Ok(()),
+            ParserProperties::Double { min, max } => encode_bounds(min, max, buf),
+            ParserProperties::Float { min, max } => encode_bounds(min, max, buf),
+            ParserProperties::Integer { min, max } => encode_bounds(min, max, buf),
+            ParserProperties::Long { min, max } => encode_bounds(min, max, buf),
+            ParserProperties::String(kind) => kind.encode(&mut buf),
+            ParserProperties::Flags(flags) => flags.encode(&mut buf),
+            ParserProperties::Range(decimals) => decimals.encode(&mut buf),
+        }
+    }
+
+    fn decode<B: Buf>(parser: &str, mut buf: B) -> Result<Self, DecodeError> {
+        Ok(match parser {
+            "brigadier:double" => {
+                let (min, max) = decode_bounds(buf)?;
+                ParserProperties::Double { min, max }
+            }
+            "brigadier:float" => {
+                let (min, max) = decode_bounds(buf)?;
+                ParserProperties::Float { min, max }
+            }
+            "brigadier:integer" => {
+                let (min, max) = decode_bounds(buf)?;
+                ParserProperties::Integer { min, max }
+            }
+            "brigadier:long" => {
+                let (min, max) = decode_bounds(buf)?;
+                ParserProperties::Long { min, max }
+            }
+            "brigadier:string" => ParserProperties::String(VarInt::decode(&mut buf)?),
+            "minecraft:entity" | "minecraft:score_holder" => {
+                ParserProperties::Flags(u8::decode(&mut buf)?)
+            }
+            "minecraft:range" => ParserProperties::Range(bool::decode(&mut buf)?),
+            _ => ParserProperties::None,
+        })
+    }
+}
+
+impl Transcodeable for CommandNode {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        let mut flags = self.flags & (NODE_TYPE | NODE_EXECUTABLE);
+        if self.redirect.is_some() {
+            flags |= NODE_REDIRECT;
+        }
+        if self.suggestions.is_some() {
+            flags |= NODE_SUGGESTIONS;
+        }
+        flags.encode(&mut buf)?;
+        self.children.encode(&mut buf)?;
+        if let Some(redirect) = &self.redirect {
+            redirect.encode(&mut buf)?;
+        }
+        if let Some(name) = &self.name {
+            name.encode(&mut buf)?;
+        }
+        if let Some((parser, properties)) = &self.parser {
+            parser.encode(&mut buf)?;
+            properties.encode(&mut buf)?;
+        }
+        if let Some(suggestions) = &self.suggestions {
+            suggestions.encode(&mut buf)?;
+        }
+        Ok(())
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let flags = u8::decode(&mut buf)?;
+        let children = Array::decode(&mut buf)?;
+        let redirect = match flags & NODE_REDIRECT {
+            0 => None,
+            _ => Some(VarInt::decode(&mut buf)?),
+        };
+        let name = match flags & NODE_TYPE {
+            NODE_LITERAL | NODE_ARGUMENT => Some(MaxString::decode(&mut buf)?),
+            _ => None,
+        };
+        let parser = match flags & NODE_TYPE {
+            NODE_ARGUMENT => {
+                let parser = Identifier::decode(&mut buf)?;
+                let properties = ParserProperties::decode(&parser, &mut buf)?;
+                Some((parser, properties))
+            }
+            _ => None,
+        };
+        let suggestions = match flags & NODE_SUGGESTIONS {
+            0 => None,
+            _ => Some(Identifier::decode(&mut buf)?),
+        };
+        Ok(CommandNode {
+            flags,
+            children,
+            redirect,
+            name,
+            parser,
+            suggestions,
+        })
+    }
+}
+
+#[packet(id = 0x13)]
+pub struct CloseWindow(pub u8);
+
+#[packet(id = 0x14)]
+pub struct WindowItems {
+    pub window: u8,
+    pub state: VarInt,
+    pub slots: Array<Slot, VarInt>,
+    pub carried: Slot,
+}
+
+#[packet(id = 0x15)]
+pub struct WindowProperty {
+    pub window: u8,
+    pub property: i16,
+    pub value: i16,
+}
+
+#[packet(id = 0x16)]
+pub struct SetSlot {
+    pub window: i8,
+    pub state: VarInt,
+    pub slot: i16,
+    pub data: Slot,
+}
+
+#[packet(id = 0x17)]
+pub struct SetCooldown {
+    pub item: VarInt,
+    pub ticks: VarInt,
+}
+
+#[packet(id = 0x18)]
+pub struct PluginMessage {
+    pub channel: Identifier,
+    pub data: Remaining,
+}
+
+#[packet(id = 0x19)]
+pub struct NamedSoundEffect {
+    pub sound: Identifier,
+    pub category: VarInt,
+    /// Coordinates multiplied by 8
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+#[packet(id = 0x1A)]
+pub struct Disconnect(pub Chat);
+
+#[packet(id = 0x1B)]
+pub struct EntityStatus {
+    pub eid: i32,
+    pub status: i8,
+}
+
+#[packet(id = 0x1C)]
+pub struct Explosion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub strength: f32,
+    pub records: Array<ExplosionRecord, VarInt>,
+    pub motion_x: f32,
+    pub motion_y: f32,
+    pub motion_z: f32,
+}
+
+/// Offset of a destroyed block from the center of the explosion
+#[derive(Transcodeable)]
+pub struct ExplosionRecord {
+    pub x: i8,
+    pub y: i8,
+    pub z: i8,
+}
+
+#[packet(id = 0x1D)]
+pub struct UnloadChunk {
+    pub x: i32,
+    pub z: i32,
+}
+
+#[packet(id = 0x1E)]
+pub struct ChangeGameState {
+    pub reason: u8,
+    pub value: f32,
+}
+
+#[packet(id = 0x1F)]
+pub struct OpenHorseWindow {
+    pub window: u8,
+    pub slots: VarInt,
+    pub eid: i32,
+}
+
+#[packet(id = 0x20)]
+pub struct InitializeWorldBorder {
+    pub x: f64,
+    pub z: f64,
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    /// Milliseconds until the new diameter is reached
+    pub speed: VarLong,
+    pub portal_teleport_boundary: VarInt,
+    pub warning_blocks: VarInt,
+    pub warning_time: VarInt,
+}
+
+#[packet(id = 0x21)]
+pub struct KeepAlive(pub i64);
+
+#[packet(id = 0x22)]
+pub struct ChunkData {
+    pub x: i32,
+    pub z: i32,
+    /// Sections which are sent
+    pub mask: BitSet,
+    pub heightmaps: nbt::Blob,
+    pub biomes: Array<VarInt, VarInt>,
+    pub data: Array<u8, VarInt>,
+    pub block_entities: Array<nbt::Blob, VarInt>,
+}
+
+#[packet(id = 0x23)]
+pub struct Effect {
+    pub id: i32,
+    pub location: Position,
+    pub data: i32,
+    pub disable_relative_volume: bool,
+}
+
+/// Writes the particle id as an int, unlike entity metadata
+pub struct Particle {
+    pub id: i32,
+    pub long_distance: bool,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub offset_z: f32,
+    pub particle_data: f32,
+    pub count: i32,
+    pub data: ParticleData,
+}
+
+impl Packet for Particle {
+    fn id() -> i32 {
+        0x24
+    }
+}
+
+impl Transcodeable for Particle {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.id.encode(&mut buf)?;
+        self.long_distance.encode(&mut buf)?;
+        self.x.encode(&mut buf)?;
+        self.y.encode(&mut buf)?;
+        self.z.encode(&mut buf)?;
+        self.offset_x.encode(&mut buf)?;
+        self.offset_y.encode(&mut buf)?;
+        self.offset_z.encode(&mut buf)?;
+        self.particle_data.encode(&mut buf)?;
+        self.count.encode(&mut buf)?;
+        self.data.encode(buf)
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let id = i32::decode(&mut buf)?;
+        Ok(Particle {
+            id,
+            long_distance: bool::decode(&mut buf)?,
+            x: f64::decode(&mut buf)?,
+            y: f64::decode(&mut buf)?,
+            z: f64::decode(&mut buf)?,
+            offset_x: f32::decode(&mut buf)?,
+            offset_y: f32::decode(&mut buf)?,
+            offset_z: f32::decode(&mut buf)?,
+            particle_data: f32::decode(&mut buf)?,
+            count: i32::decode(&mut buf)?,
+            data: ParticleData::decode(id, buf)?,
+        })
+    }
+}
+
+#[packet(id = 0x25)]
+pub struct UpdateLight {
+    pub x: VarInt,
+    pub z: VarInt,
+    pub trust_edges: bool,
+    /// Bit 0 is the section below the world, the last bit the one above it
+    pub sky_light_mask: BitSet,
+    pub block_light_mask: BitSet,
+    /// Sections whose light is all zeros
+    pub empty_sky_light_mask: BitSet,
+    pub empty_block_light_mask: BitSet,
+    /// 2048 bytes for every section in the sky light mask
+    pub sky_light: Array<Array<u8, VarInt>, VarInt>,
+    pub block_light: Array<Array<u8, VarInt>, VarInt>,
+}
+
+#[packet(id = 0x26)]
+pub struct JoinGame {
+    pub eid: i32,
+    pub hardcore: bool,
+    pub gamemode: u8,
+    pub prev_gamemode: i8,
+    pub worlds: Array<Identifier, VarInt>,
+    pub dim_codec: nbt::Blob,
+    pub dim: nbt::Blob,
+    pub world_name: Identifier,
+    pub hashed_seed: i64,
+    pub max_players: VarInt,
+    pub view_distance: VarInt,
+    pub reduce_debug: bool,
+    pub respawn_screen: bool,
+    pub is_debug: bool,
+    pub is_flat: bool,
+}
+
+pub struct MapData {
+    pub id: VarInt,
+    pub scale: i8,
+    pub locked: bool,
+    /// `None` if the map does not track positions
+    pub icons: Option<Array<MapIcon, VarInt>>,
+    /// Changed part of the map, if any
+    pub patch: Option<MapPatch>,
+}
+
+#[derive(Transcodeable)]
+pub struct MapIcon {
+    pub kind: VarInt,
+    pub x: i8,
+    pub z: i8,
+    pub direction: i8,
+    pub display_name: Option<Chat>,
+}
+
+pub struct MapPatch {
+    pub columns: u8,
+    pub rows: u8,
+    pub x: u8,
+    pub z: u8,
+    pub data: Array<u8, VarInt>,
+}
+
+impl Packet for MapData {
+    fn id() -> i32 {
+        0x27
+    }
+}
+
+impl Transcodeable for MapData {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.id.encode(&mut buf)?;
+        self.scale.encode(&mut buf)?;
+        self.locked.encode(&mut buf)?;
+        self.icons.encode(&mut buf)?;
+        match &self.patch {
+            // no columns means nothing changed
+            None => 0u8.encode(buf),
+            Some(patch) => {
+                patch.columns.encode(&mut buf)?;
+                patch.rows.encode(&mut buf)?;
+                patch.x.encode(&mut buf)?;
+                patch.z.encode(&mut buf)?;
+                patch.data.encode(buf)
+            }
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let id = VarInt::decode(&mut buf)?;
+        let scale = i8::decode(&mut buf)?;
+        let locked = bool::decode(&mut buf)?;
+        let icons = Option::decode(&mut buf)?;
+        let patch = match u8::decode(&mut buf)? {
+            0 => None,
+            columns => Some(MapPatch {
+                columns,
+                rows: u8::decode(&mut buf)?,
+                x: u8::decode(&mut buf)?,
+                z: u8::decode(&mut buf)?,
+                data: Array::decode(buf)?,
+            }),
+        };
+        Ok(MapData {
+            id,
+            scale,
+            locked,
+            icons,
+            patch,
+        })
+    }
+}
+
+#[packet(id = 0x28)]
+pub struct TradeList {
+    pub window: VarInt,
+    pub trades: Array<Trade, u8>,
+    pub villager_level: VarInt,
+    pub experience: VarInt,
+    pub regular_villager: bool,
+    pub can_restock: bool,
+}
+
+#[derive(Transcodeable)]
+pub struct Trade {
+    pub input: Slot,
+    pub output: Slot,
+    pub second_input: Option<Slot>,
+    pub disabled: bool,
+    pub uses: i32,
+    pub max_uses: i32,
+    pub experience: i32,
+    pub special_price: i32,
+    pub price_multiplier: f32,
+    pub demand: i32,
+}
+
+#[packet(id = 0x29)]
+pub struct EntityPosition {
+    pub eid: VarInt,
+    /// Change in position multiplied by 4096
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x2A)]
+pub struct EntityPositionAndRotation {
+    pub eid: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x2B)]
+pub struct EntityRotation {
+    pub eid: VarInt,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x2C)]
+pub struct VehicleMove {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+#[packet(id = 0x2D)]
+pub struct OpenBook {
+    pub hand: VarInt,
+}
+
+#[packet(id = 0x2E)]
+pub struct OpenWindow {
+    pub window: VarInt,
+    pub kind: VarInt,
+    pub title: Chat,
+}
+
+#[packet(id = 0x2F)]
+pub struct OpenSignEditor {
+    pub location: Position,
+}
+
+#[packet(id = 0x30)]
+pub struct Ping(pub i32);
+
+#[packet(id = 0x31)]
+pub struct CraftRecipeResponse {
+    pub window: i8,
+    pub recipe: Identifier,
+}
+
+#[packet(id = 0x32)]
+pub struct PlayerAbilities {
+    pub flags: i8,
+    pub flying_speed: f32,
+    pub fov_modifier: f32,
+}
+
+#[packet(id = 0x33)]
+pub struct EndCombatEvent {
+    pub duration: VarInt,
+    pub eid: i32,
+}
+
+#[packet(id = 0x34)]
+pub struct EnterCombatEvent;
+
+#[packet(id = 0x35)]
+pub struct DeathCombatEvent {
+    pub player: VarInt,
+    /// The killer, -1 if there is none
+    pub eid: i32,
+    pub message: Chat,
+}
+
+pub struct PlayerInfo(pub PlayerInfoAction);
+
+pub enum PlayerInfoAction {
+    AddPlayer(Vec<(Uuid, NewPlayer)>),
+    UpdateGamemode(Vec<(Uuid, VarInt)>),
+    /// Latency of each player in milliseconds
+    UpdateLatency(Vec<(Uuid, VarInt)>),
+    UpdateDisplayName(Vec<(Uuid, Option<Chat>)>),
+    RemovePlayer(Vec<Uuid>),
+}
+
+#[derive(Transcodeable)]
+pub struct NewPlayer {
+    pub name: StringN<16>,
+    pub properties: Array<Property, VarInt>,
+    pub gamemode: VarInt,
+    pub ping: VarInt,
+    pub display_name: Option<Chat>,
+}
+
+/// A profile property like the skin texture
+#[derive(Transcodeable)]
+pub struct Property {
+    pub name: MaxString,
+    pub value: MaxString,
+    pub signature: Option<MaxString>,
+}
+
+impl Packet for PlayerInfo {
+    fn id() -> i32 {
+        0x36
+    }
+}
+
+fn encode_players<T: Transcodeable, B: BufMut>(
+    action: i32,
+    players: &[T],
+    mut buf: B,
+) -> Result<(), EncodeError> {
+    VarInt(action).encode(&mut buf)?;
+    VarInt(players.len() as i32).encode(&mut buf)?;
+    for player in players {
+        player.encode(&mut buf)?;
+    }
+    Ok(())
+}
+
+impl Transcodeable for PlayerInfo {
+    fn encode<B: BufMut>(&self, buf: B) -> Result<(), EncodeError> {
+        match &self.0 {
+            PlayerInfoAction::AddPlayer(players) => encode_players(0, players, buf),
+            PlayerInfoAction::UpdateGamemode(players) => encode_players(1, players, buf),
+            PlayerInfoAction::UpdateLatency(players) => encode_players(2, players, buf),
+            PlayerInfoAction::UpdateDisplayName(players) => encode_players(3, players, buf),
+            PlayerInfoAction::RemovePlayer(players) => encode_players(4, players, buf),
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let action = VarInt::decode(&mut buf)?;
+        Ok(PlayerInfo(match *action {
+            0 => PlayerInfoAction::AddPlayer(Array::<_, VarInt>::decode(buf)?.0),
+            1 => PlayerInfoAction::UpdateGamemode(Array::<_, VarInt>::decode(buf)?.0),
+            2 => PlayerInfoAction::UpdateLatency(Array::<_, VarInt>::decode(buf)?.0),
+            3 => PlayerInfoAction::UpdateDisplayName(Array::<_, VarInt>::decode(buf)?.0),
+            4 => PlayerInfoAction::RemovePlayer(Array::<_, VarInt>::decode(buf)?.0),
+            _ => return Err(DecodeError::InvalidData),
+        }))
+    }
+}
+
+#[packet(id = 0x37)]
+pub struct FacePlayer {
+    /// 0 to aim with the feet, 1 with the eyes
+    pub anchor: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// Entity to look at and which of its anchors, the position is ignored if present
+    pub entity: Option<(VarInt, VarInt)>,
+}
+
+#[packet(id = 0x38)]
+pub struct PlayerPositionAndLook {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Bits of the fields which are relative instead of absolute
+    pub flags: i8,
+    pub teleport_id: VarInt,
+    pub dismount: bool,
+}
+
+pub struct UnlockRecipes {
+    /// 0 to initialize, 1 to add and 2 to remove recipes
+    pub action: VarInt,
+    pub crafting: RecipeBookState,
+    pub smelting: RecipeBookState,
+    pub blast_furnace: RecipeBookState,
+    pub smoker: RecipeBookState,
+    pub recipes: Array<Identifier, VarInt>,
+    /// All unlocked recipes, only sent when initializing
+    pub init: Option<Array<Identifier, VarInt>>,
+}
+
+#[derive(Transcodeable)]
+pub struct RecipeBookState {
+    pub open: bool,
+    pub filter_active: bool,
+}
+
+impl Packet for UnlockRecipes {
+    fn id() -> i32 {
+        0x39
+    }
+}
+
+impl Transcodeable for UnlockRecipes {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.action.encode(&mut buf)?;
+        self.crafting.encode(&mut buf)?;
+        self.smelting.encode(&mut buf)?;
+        self.blast_furnace.encode(&mut buf)?;
+        self.smoker.encode(&mut buf)?;
+        self.recipes.encode(&mut buf)?;
+        match (*self.action, &self.init) {
+            (0, Some(init)) => init.encode(buf),
+            (0, None) => VarInt(0).encode(buf),
+            _ => Ok(()),
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let action = VarInt::decode(&mut buf)?;
+        Ok(UnlockRecipes {
+            action,
+            crafting: Transcodeable::decode(&mut buf)?,
+            smelting: Transcodeable::decode(&mut buf)?,
+            blast_furnace: Transcodeable::decode(&mut buf)?,
+            smoker: Transcodeable::decode(&mut buf)?,
+            recipes: Transcodeable::decode(&mut buf)?,
+            init: match *action {
+                0 => Some(Transcodeable::decode(buf)?),
+                _ => None,
+            },
+        })
+    }
+}
+
+#[packet(id = 0x3A)]
+pub struct DestroyEntities(pub Array<VarInt, VarInt>);
+
+#[packet(id = 0x3B)]
+pub struct RemoveEntityEffect {
+    pub eid: VarInt,
+    pub effect: i8,
+}
+
+#[packet(id = 0x3C)]
+pub struct ResourcePackSend {
+    pub url: MaxString,
+    /// Hex encoded SHA-1 of the pack
+    pub hash: StringN<40>,
+    pub forced: bool,
+    pub prompt: Option<Chat>,
+}
+
+#[packet(id = 0x3D)]
+pub struct Respawn {
+    pub dim: nbt::Blob,
+    pub world_name: Identifier,
+    pub hashed_seed: i64,
+    pub gamemode: u8,
+    pub prev_gamemode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub copy_metadata: bool,
+}
+
+#[packet(id = 0x3E)]
+pub struct EntityHeadLook {
+    pub eid: VarInt,
+    pub head_yaw: Angle,
+}
+
+#[packet(id = 0x3F)]
+pub struct MultiBlockChange {
+    /// Section coordinates packed as x (22 bits), z (22 bits) and y (20 bits)
+    pub section: i64,
+    pub trust_edges: bool,
+    /// Block state shifted left by 12 bits, followed by the x, z and y offset with 4 bits each
+    pub blocks: Array<VarLong, VarInt>,
+}
+
+#[packet(id = 0x40)]
+pub struct SelectAdvancementTab(pub Option<Identifier>);
+
+#[packet(id = 0x41)]
+pub struct ActionBar(pub Chat);
+
+#[packet(id = 0x42)]
+pub struct WorldBorderCenter {
+    pub x: f64,
+    pub z: f64,
+}
+
+#[packet(id = 0x43)]
+pub struct WorldBorderLerpSize {
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    pub speed: VarLong,
+}
+
+#[packet(id = 0x44)]
+pub struct WorldBorderSize(pub f64);
+
+#[packet(id = 0x45)]
+pub struct WorldBorderWarningDelay(pub VarInt);
+
+#[packet(id = 0x46)]
+pub struct WorldBorderWarningReach(pub VarInt);
+
+#[packet(id = 0x47)]
+pub struct Camera(pub VarInt);
+
+#[packet(id = 0x48)]
+pub struct HeldItemChange(pub i8);
+
+#[packet(id = 0x49)]
+pub struct UpdateViewPosition {
+    pub x: VarInt,
+    pub z: VarInt,
+}
+
+#[packet(id = 0x4A)]
+pub struct UpdateViewDistance(pub VarInt);
+
+#[packet(id = 0x4B)]
+pub struct SpawnPosition {
+    pub location: Position,
+    pub angle: f32,
+}
+
+#[packet(id = 0x4C)]
+pub struct DisplayScoreboard {
+    pub position: i8,
+    pub name: StringN<16>,
+}
+
+#[packet(id = 0x4D)]
+pub struct EntityMetadata {
+    pub eid: VarInt,
+    pub metadata: Metadata,
+}
+
+#[packet(id = 0x4E)]
+pub struct AttachEntity {
+    pub attached: i32,
+    /// -1 to detach
+    pub holding: i32,
+}
+
+#[packet(id = 0x4F)]
+pub struct EntityVelocity {
+    pub eid: VarInt,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+#[packet(id = 0x50)]
+pub struct EntityEquipment {
+    pub eid: VarInt,
+    pub equipment: Equipment,
+}
+
+/// Equipment slots and their items, the top bit of each slot tells if another one follows
+pub struct Equipment(pub Vec<(i8, Slot)>);
+
+impl Transcodeable for Equipment {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        if self.0.is_empty() {
+            return Err(EncodeError::InputToLong);
+        }
+        let last = self.0.len() - 1;
+        for (i, (slot, item)) in self.0.iter().enumerate() {
+            let more = if i == last { 0 } else { 0x80u8 as i8 };
+            (slot | more).encode(&mut buf)?;
+            item.encode(&mut buf)?;
+        }
+        Ok(())
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let mut equipment = Vec::new();
+        loop {
+            let slot = i8::decode(&mut buf)?;
+            equipment.push((slot & 0x7F, Slot::decode(&mut buf)?));
+            if slot >= 0 {
+                return Ok(Equipment(equipment));
+            }
+        }
+    }
+}
+
+#[packet(id = 0x51)]
+pub struct SetExperience {
+    pub bar: f32,
+    pub level: VarInt,
+    pub total: VarInt,
+}
+
+#[packet(id = 0x52)]
+pub struct UpdateHealth {
+    pub health: f32,
+    pub food: VarInt,
+    pub saturation: f32,
+}
+
+pub struct ScoreboardObjective {
+    pub name: StringN<16>,
+    pub action: ObjectiveAction,
+}
+
+pub enum ObjectiveAction {
+    /// The value is displayed as an integer with type 0 and as hearts with type 1
+    Create {
+        value: Chat,
+        kind: VarInt,
+    },
+    Remove,
+    Update {
+        value: Chat,
+        kind: VarInt,
+    },
+}
+
+impl Packet for ScoreboardObjective {
+    fn id() -> i32 {
+        0x53
+    }
+}
+
+impl Transcodeable for ScoreboardObjective {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.name.encode(&mut buf)?;
+        match &self.action {
+            ObjectiveAction::Create { value, kind } => {
+                0i8.encode(&mut buf)?;
+                value.encode(&mut buf)?;
+                kind.encode(buf)
+            }
+            ObjectiveAction::Remove => 1i8.encode(buf),
+            ObjectiveAction::Update { value, kind } => {
+                2i8.encode(&mut buf)?;
+                value.encode(&mut buf)?;
+                kind.encode(buf)
+            }
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let name = StringN::decode(&mut buf)?;
+        let action = match i8::decode(&mut buf)? {
+            0 => ObjectiveAction::Create {
+                value: Chat::decode(&mut buf)?,
+                kind: VarInt::decode(buf)?,
+            },
+            1 => ObjectiveAction::Remove,
+            2 => ObjectiveAction::Update {
+                value: Chat::decode(&mut buf)?,
+                kind: VarInt::decode(buf)?,
+            },
+            _ => return Err(DecodeError::InvalidData),
+        };
+        Ok(ScoreboardObjective { name, action })
+    }
+}
+
+#[packet(id = 0x54)]
+pub struct SetPassengers {
+    pub eid: VarInt,
+    pub passengers: Array<VarInt, VarInt>,
+}
+
+pub struct Teams {
+    pub name: StringN<16>,
+    pub action: TeamAction,
+}
+
+pub enum TeamAction {
+    Create(TeamInfo, Array<StringN<40>, VarInt>),
+    Remove,
+    Update(TeamInfo),
+    AddEntities(Array<StringN<40>, VarInt>),
+    RemoveEntities(Array<StringN<40>, VarInt>),
+}
+
+#[derive(Transcodeable)]
+pub struct TeamInfo {
+    pub display_name: Chat,
+    pub friendly_flags: i8,
+    pub name_tag_visibility: StringN<40>,
+    pub collision_rule: StringN<40>,
+    pub color: VarInt,
+    pub prefix: Chat,
+    pub suffix: Chat,
+}
+
+impl Packet for Teams {
+    fn id() -> i32 {
+        0x55
+    }
+}
+
+impl Transcodeable for Teams {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.name.encode(&mut buf)?;
+        match &self.action {
+            TeamAction::Create(info, entities) => {
+                0i8.encode(&mut buf)?;
+                info.encode(&mut buf)?;
+                entities.encode(buf)
+            }
+            TeamAction::Remove => 1i8.encode(buf),
+            TeamAction::Update(info) => {
+                2i8.encode(&mut buf)?;
+                info.encode(buf)
+            }
+            TeamAction::AddEntities(entities) => {
+                3i8.encode(&mut buf)?;
+                entities.encode(buf)
+            }
+            TeamAction::RemoveEntities(entities) => {
+                4i8.encode(&mut buf)?;
+                entities.encode(buf)
+            }
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let name = StringN::decode(&mut buf)?;
+        let action = match i8::decode(&mut buf)? {
+            0 => TeamAction::Create(TeamInfo::decode(&mut buf)?, Array::decode(buf)?),
+            1 => TeamAction::Remove,
+            2 => TeamAction::Update(TeamInfo::decode(buf)?),
+            3 => TeamAction::AddEntities(Array::decode(buf)?),
+            4 => TeamAction::RemoveEntities(Array::decode(buf)?),
+            _ => return Err(DecodeError::InvalidData),
+        };
+        Ok(Teams { name, action })
+    }
+}
+
+pub struct UpdateScore {
+    pub entity: StringN<40>,
+    pub objective: StringN<16>,
+    /// `None` removes the score
+    pub value: Option<VarInt>,
+}
+
+impl Packet for UpdateScore {
+    fn id() -> i32 {
+        0x56
+    }
+}
+
+impl Transcodeable for UpdateScore {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.entity.encode(&mut buf)?;
+        (self.value.is_none() as i8).encode(&mut buf)?;
+        self.objective.encode(&mut buf)?;
+        match &self.value {
+            Some(value) => value.encode(buf),
+            None => Ok(()),
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let entity = StringN::decode(&mut buf)?;
+        let action = i8::decode(&mut buf)?;
+        let objective = StringN::decode(&mut buf)?;
+        let value = match action {
+            0 => Some(VarInt::decode(buf)?),
+            1 => None,
+            _ => return Err(DecodeError::InvalidData),
+        };
+        Ok(UpdateScore {
+            entity,
+            objective,
+            value,
+        })
+    }
+}
+
+#[packet(id = 0x57)]
+pub struct SetTitleSubtitle(pub Chat);
+
+#[packet(id = 0x58)]
+pub struct TimeUpdate {
+    pub world_age: i64,
+    /// Ticks since the start of the first day, negative values stop the sun from moving
+    pub time_of_day: i64,
+}
+
+#[packet(id = 0x59)]
+pub struct SetTitleText(pub Chat);
+
+#[packet(id = 0x5A)]
+pub struct SetTitleTimes {
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+#[packet(id = 0x5B)]
+pub struct EntitySoundEffect {
+    pub sound: VarInt,
+    pub category: VarInt,
+    pub eid: VarInt,
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+#[packet(id = 0x5C)]
+pub struct SoundEffect {
+    pub sound: VarInt,
+    pub category: VarInt,
+    /// Coordinates multiplied by 8
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+/// Stops matching sounds, or all of them if neither is given
+pub struct StopSound {
+    pub category: Option<VarInt>,
+    pub sound: Option<Identifier>,
+}
+
+impl Packet for StopSound {
+    fn id() -> i32 {
+        0x5D
+    }
+}
+
+impl Transcodeable for StopSound {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        (self.category.is_some() as i8 | (self.sound.is_some() as i8) << 1).encode(&mut buf)?;
+        if let Some(category) = &self.category {
+            category.encode(&mut buf)?;
+        }
+        if let Some(sound) = &self.sound {
+            sound.encode(&mut buf)?;
+        }
+        Ok(())
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let flags = i8::decode(&mut buf)?;
+        let category = match flags & 0x01 {
+            0 => None,
+            _ => Some(VarInt::decode(&mut buf)?),
+        };
+        let sound = match flags & 0x02 {
+            0 => None,
+            _ => Some(Identifier::decode(&mut buf)?),
+        };
+        Ok(StopSound { category, sound })
+    }
+}
+
+#[packet(id = 0x5E)]
+pub struct PlayerListHeaderAndFooter {
+    pub header: Chat,
+    pub footer: Chat,
+}
+
+#[packet(id = 0x5F)]
+pub struct NbtQueryResponse {
+    pub transaction: VarInt,
+    pub nbt: OptionalNbt,
+}
+
+#[packet(id = 0x60)]
+pub struct CollectItem {
+    pub collected: VarInt,
+    pub collector: VarInt,
+    pub count: VarInt,
+}
+
+#[packet(id = 0x61)]
+pub struct EntityTeleport {
+    pub eid: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x62)]
+pub struct Advancements {
+    pub reset: bool,
+    pub advancements: Array<(Identifier, Advancement), VarInt>,
+    pub removed: Array<Identifier, VarInt>,
+    pub progress: Array<(Identifier, AdvancementProgress), VarInt>,
+}
+
+#[derive(Transcodeable)]
+pub struct Advancement {
+    pub parent: Option<Identifier>,
+    pub display: Option<AdvancementDisplay>,
+    pub criteria: Array<Identifier, VarInt>,
+    pub requirements: Array<Array<MaxString, VarInt>, VarInt>,
+}
+
+pub struct AdvancementDisplay {
+    pub title: Chat,
+    pub description: Chat,
+    pub icon: Slot,
+    pub frame: VarInt,
+    /// 0x02 shows a toast and 0x04 hides the advancement, 0x01 follows from `background`
+    pub flags: i32,
+    pub background: Option<Identifier>,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Transcodeable for AdvancementDisplay {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.title.encode(&mut buf)?;
+        self.description.encode(&mut buf)?;
+        self.icon.encode(&mut buf)?;
+        self.frame.encode(&mut buf)?;
+        (self.flags & !0x01 | self.background.is_some() as i32).encode(&mut buf)?;
+        if let Some(background) = &self.background {
+            background.encode(&mut buf)?;
+        }
+        self.x.encode(&mut buf)?;
+        self.y.encode(buf)
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let title = Chat::decode(&mut buf)?;
+        let description = Chat::decode(&mut buf)?;
+        let icon = Slot::decode(&mut buf)?;
+        let frame = VarInt::decode(&mut buf)?;
+        let flags = i32::decode(&mut buf)?;
+        let background = match flags & 0x01 {
+            0 => None,
+            _ => Some(Identifier::decode(&mut buf)?),
+        };
+        Ok(AdvancementDisplay {
+            title,
+            description,
+            icon,
+            frame,
+            flags,
+            background,
+            x: f32::decode(&mut buf)?,
+            y: f32::decode(buf)?,
+        })
+    }
+}
+
+/// Criteria and when they were achieved, in milliseconds since the epoch
+#[derive(Transcodeable)]
+pub struct AdvancementProgress(pub Array<(Identifier, Option<i64>), VarInt>);
+
+#[packet(id = 0x63)]
+pub struct EntityProperties {
+    pub eid: VarInt,
+    pub properties: Array<EntityProperty, VarInt>,
+}
+
+#[derive(Transcodeable)]
+pub struct EntityProperty {
+    pub key: Identifier,
+    pub value: f64,
+    pub modifiers: Array<Modifier, VarInt>,
+}
+
+#[derive(Transcodeable)]
+pub struct Modifier {
+    pub uuid: Uuid,
+    pub amount: f64,
+    pub operation: i8,
+}
+
+#[packet(id = 0x64)]
+pub struct EntityEffect {
+    pub eid: VarInt,
+    pub effect: i8,
+    pub amplifier: i8,
+    /// In ticks
+    pub duration: VarInt,
+    pub flags: i8,
+}
+
+#[packet(id = 0x65)]
+pub struct DeclareRecipes(pub Array<Recipe, VarInt>);
+
+pub struct Recipe {
+    /// The recipe serializer, e.g. `minecraft:crafting_shaped`
+    pub kind: Identifier,
+    pub id: Identifier,
+    pub data: RecipeData,
+}
+
+/// Any of the listed items is accepted
+pub type Ingredient = Array<Slot, VarInt>;
+
+pub enum RecipeData {
+    Shapeless {
+        group: MaxString,
+        ingredients: Array<Ingredient, VarInt>,
+        result: Slot,
+    },
+    Shaped {
+        width: VarInt,
+        height: VarInt,
+        group: MaxString,
+        /// `width * height` ingredients, row by row
+        ingredients: Vec<Ingredient>,
+        result: Slot,
+    },
+    /// Smelting, blasting, smoking and campfire cooking
+    Cooking {
+        group: MaxString,
+        ingredient: Ingredient,
+        result: Slot,
+        experience: f32,
+        cooking_time: VarInt,
+    },
+    Stonecutting {
+        group: MaxString,
+        ingredient: Ingredient,
+        result: Slot,
+    },
+    Smithing {
+        base: Ingredient,
+        addition: Ingredient,
+        result: Slot,
+    },
+    /// Recipes like map cloning which are implemented by the client itself
+    Special,
+}
+
+impl Transcodeable for Recipe {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.kind.encode(&mut buf)?;
+        self.id.encode(&mut buf)?;
+        match &self.data {
+            RecipeData::Shapeless {
+                group,
+                ingredients,
+                result,
+            } => {
+                group.encode(&mut buf)?;
+                ingredients.encode(&mut buf)?;
+                result.encode(buf)
+            }
+            RecipeData::Shaped {
+                width,
+                height,
+                group,
+                ingredients,
+                result,
+            } => {
+                if ingredients.len() != (**width * **height) as usize {
+                    return Err(EncodeError::InputToLong);
+                }
+                width.encode(&mut buf)?;
+                height.encode(&mut buf)?;
+                group.encode(&mut buf)?;
+                for ingredient in ingredients {
+                    ingredient.encode(&mut buf)?;
+                }
+                result.encode(buf)
+            }
+            RecipeData::Cooking {
+                group,
+                ingredient,
+                result,
+                experience,
+                cooking_time,
+            } => {
+                group.encode(&mut buf)?;
+                ingredient.encode(&mut buf)?;
+                result.encode(&mut buf)?;
+                experience.encode(&mut buf)?;
+                cooking_time.encode(buf)
+            }
+            RecipeData::Stonecutting {
+                group,
+                ingredient,
+                result,
+            } => {
+                group.encode(&mut buf)?;
+                ingredient.encode(&mut buf)?;
+                result.encode(buf)
+            }
+            RecipeData::Smithing {
+                base,
+                addition,
+                result,
+            } => {
+                base.encode(&mut buf)?;
+                addition.encode(&mut buf)?;
+                result.encode(buf)
+            }
+            RecipeData::Special => Ok(()),
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let kind = Identifier::decode(&mut buf)?;
+        let id = Identifier::decode(&mut buf)?;
+        let data = match kind.strip_prefix("minecraft:").unwrap_or(&kind) {
+            "crafting_shapeless" => RecipeData::Shapeless {
+                group: MaxString::decode(&mut buf)?,
+                ingredients: Array::decode(&mut buf)?,
+                result: Slot::decode(buf)?,
+            },
+            "crafting_shaped" => {
+                let width = VarInt::decode(&mut buf)?;
+                let height = VarInt::decode(&mut buf)?;
+                let group = MaxString::decode(&mut buf)?;
+                let count = (*width).max(0) as usize * (*height).max(0) as usize;
+                let ingredients = (0..count)
+                    .map(|_| Ingredient::decode(&mut buf))
+                    .collect::<Result<_, _>>()?;
+                RecipeData::Shaped {
+                    width,
+                    height,
+                    group,
+                    ingredients,
+                    result: Slot::decode(buf)?,
+                }
+            }
+            "smelting" | "blasting" | "smoking" | "campfire_cooking" => RecipeData::Cooking {
+                group: MaxString::decode(&mut buf)?,
+                ingredient: Ingredient::decode(&mut buf)?,
+                result: Slot::decode(&mut buf)?,
+                experience: f32::decode(&mut buf)?,
+                cooking_time: VarInt::decode(buf)?,
+            },
+            "stonecutting" => RecipeData::Stonecutting {
+                group: MaxString::decode(&mut buf)?,
+                ingredient: Ingredient::decode(&mut buf)?,
+                result: Slot::decode(buf)?,
+            },
+            "smithing" => RecipeData::Smithing {
+                base: Ingredient::decode(&mut buf)?,
+                addition: Ingredient::decode(&mut buf)?,
+                result: Slot::decode(buf)?,
+            },
+            special if special.starts_with("crafting_special_") => RecipeData::Special,
+            _ => return Err(DecodeError::InvalidData),
+        };
+        Ok(Recipe { kind, id, data })
+    }
+}
+
+/// Tags grouped by registry, e.g. `minecraft:block`
+#[packet(id = 0x66)]
+pub struct Tags(pub Array<(Identifier, Array<Tag, VarInt>), VarInt>);
+
+#[derive(Transcodeable)]
+pub struct Tag {
+    pub name: Identifier,
+    pub entries: Array<VarInt, VarInt>,
+}
@@ -0,0 +1,311 @@
+//! Models of the registries sent in [`JoinGame`](super::clientbound::JoinGame)
+
+mod vanilla;
+
+use serde::{Deserialize, Serialize};
+
+/// The `dim_codec` of `JoinGame`, [`Default`] holds the vanilla dimension types and biomes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DimensionCodec {
+    #[serde(rename = "minecraft:dimension_type")]
+    pub dimension_types: Registry<DimensionType>,
+    #[serde(rename = "minecraft:worldgen/biome")]
+    pub biomes: Registry<Biome>,
+}
+
+impl DimensionCodec {
+    /// Codec without any dimension types or biomes
+    pub fn empty() -> Self {
+        Self {
+            dimension_types: Registry::new("minecraft:dimension_type"),
+            biomes: Registry::new("minecraft:worldgen/biome"),
+        }
+    }
+
+    pub fn to_blob(&self) -> Result<nbt::Blob, nbt::Error> {
+        to_blob(self)
+    }
+}
+
+impl Default for DimensionCodec {
+    fn default() -> Self {
+        let mut codec = Self::empty();
+        vanilla::dimension_types(&mut codec.dimension_types);
+        vanilla::biomes(&mut codec.biomes);
+        codec
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Registry<T> {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Vec<RegistryEntry<T>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegistryEntry<T> {
+    pub name: String,
+    pub id: i32,
+    pub element: T,
+}
+
+impl<T> Registry<T> {
+    pub fn new(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            value: Vec::new(),
+        }
+    }
+
+    /// Adds an element after the highest id and returns its id, an element with the same name is
+    /// replaced and keeps its id
+    pub fn insert(&mut self, name: &str, element: T) -> i32 {
+        if let Some(entry) = self.value.iter_mut().find(|entry| entry.name == name) {
+            entry.element = element;
+            return entry.id;
+        }
+        let id = self
+            .value
+            .iter()
+            .map(|entry| entry.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.insert_at(name, id, element);
+        id
+    }
+
+    /// Adds an element with a fixed id, replacing any element with the same name or id
+    pub fn insert_at(&mut self, name: &str, id: i32, element: T) {
+        self.value
+            .retain(|entry| entry.name != name && entry.id != id);
+        self.value.push(RegistryEntry {
+            name: name.to_string(),
+            id,
+            element,
+        });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegistryEntry<T>> {
+        self.value.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<RegistryEntry<T>> {
+        let index = self.value.iter().position(|entry| entry.name == name)?;
+        Some(self.value.remove(index))
+    }
+}
+
+/// An element of `minecraft:dimension_type`, also sent on its own as the `dim` of `JoinGame`
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DimensionType {
+    pub piglin_safe: bool,
+    pub natural: bool,
+    pub ambient_light: f32,
+    /// Freezes the time of day, in ticks
+    pub fixed_time: Option<i64>,
+    /// Block tag of blocks which burn forever
+    pub infiniburn: String,
+    pub respawn_anchor_works: bool,
+    pub has_skylight: bool,
+    pub bed_works: bool,
+    /// One of `minecraft:overworld`, `minecraft:the_nether` or `minecraft:the_end`
+    pub effects: String,
+    pub has_raids: bool,
+    /// Multiple of 16
+    pub min_y: i32,
+    /// Multiple of 16, at most 4064 together with `min_y`
+    pub height: i32,
+    /// Highest block portals and chorus fruit teleport to
+    pub logical_height: i32,
+    pub coordinate_scale: f64,
+    pub ultrawarm: bool,
+    pub has_ceiling: bool,
+}
+
+impl DimensionType {
+    pub fn to_blob(&self) -> Result<nbt::Blob, nbt::Error> {
+        to_blob(self)
+    }
+}
+
+/// An element of `minecraft:worldgen/biome`
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Biome {
+    pub precipitation: Precipitation,
+    pub depth: f32,
+    pub temperature: f32,
+    pub scale: f32,
+    pub downfall: f32,
+    pub category: BiomeCategory,
+    pub temperature_modifier: Option<TemperatureModifier>,
+    pub effects: BiomeEffects,
+    pub particle: Option<BiomeParticle>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BiomeCategory {
+    None,
+    Taiga,
+    ExtremeHills,
+    Jungle,
+    Mesa,
+    Plains,
+    Savanna,
+    Icy,
+    TheEnd,
+    Beach,
+    Forest,
+    Ocean,
+    Desert,
+    River,
+    Swamp,
+    Mushroom,
+    Nether,
+    Underground,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureModifier {
+    None,
+    Frozen,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrassColorModifier {
+    None,
+    DarkForest,
+    Swamp,
+}
+
+/// Colors are packed as `0xRRGGBB`
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BiomeEffects {
+    pub sky_color: i32,
+    pub water_fog_color: i32,
+    pub fog_color: i32,
+    pub water_color: i32,
+    /// Derived from temperature and downfall if missing
+    pub foliage_color: Option<i32>,
+    /// Derived from temperature and downfall if missing
+    pub grass_color: Option<i32>,
+    pub grass_color_modifier: Option<GrassColorModifier>,
+    pub music: Option<Music>,
+    pub ambient_sound: Option<String>,
+    pub additions_sound: Option<AdditionsSound>,
+    pub mood_sound: Option<MoodSound>,
+}
+
+impl BiomeEffects {
+    /// Vanilla overworld colors and cave sounds for a biome of the given temperature
+    pub fn new(temperature: f32) -> Self {
+        Self {
+            sky_color: sky_color(temperature),
+            water_fog_color: 329011,
+            fog_color: 12638463,
+            water_color: 4159204,
+            foliage_color: None,
+            grass_color: None,
+            grass_color_modifier: None,
+            music: None,
+            ambient_sound: None,
+            additions_sound: None,
+            mood_sound: Some(MoodSound::cave()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Music {
+    pub sound: String,
+    /// In ticks
+    pub min_delay: i32,
+    /// In ticks
+    pub max_delay: i32,
+    pub replace_current_music: bool,
+}
+
+/// Played at random while standing in the biome
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdditionsSound {
+    pub sound: String,
+    pub tick_chance: f64,
+}
+
+/// Played in dark places
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoodSound {
+    pub sound: String,
+    pub tick_delay: i32,
+    pub offset: f64,
+    pub block_search_extent: i32,
+}
+
+impl MoodSound {
+    pub fn cave() -> Self {
+        Self {
+            sound: "minecraft:ambient.cave".to_string(),
+            tick_delay: 6000,
+            offset: 2.0,
+            block_search_extent: 8,
+        }
+    }
+}
+
+/// Ambient particles, only particles without extra data are supported
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BiomeParticle {
+    pub probability: f32,
+    pub options: ParticleOptions,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParticleOptions {
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// The sky color vanilla picks for a biome of the given temperature
+pub fn sky_color(temperature: f32) -> i32 {
+    let t = (temperature / 3.0).clamp(-1.0, 1.0);
+    hsv_to_rgb(0.622_222_24 - t * 0.05, 0.5 + t * 0.1, 1.0)
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> i32 {
+    let sector = (hue * 6.0) as i32 % 6;
+    let f = hue * 6.0 - sector as f32;
+    let p = value * (1.0 - saturation);
+    let q = value * (1.0 - f * saturation);
+    let t = value * (1.0 - (1.0 - f) * saturation);
+    let (r, g, b) = match sector {
+        0 => (value, t, p),
+        1 => (q, value, p),
+        2 => (p, value, t),
+        3 => (p, q, value),
+        4 => (t, p, value),
+        _ => (value, p, q),
+    };
+    let channel = |c: f32| ((c * 255.0) as i32).clamp(0, 255);
+    channel(r) << 16 | channel(g) << 8 | channel(b)
+}
+
+// serializing straight into a blob is not supported, so the value takes a detour through bytes
+fn to_blob<T: Serialize>(value: &T) -> Result<nbt::Blob, nbt::Error> {
+    let mut buf = Vec::new();
+    nbt::to_writer(&mut buf, value, None)?;
+    nbt::Blob::from_reader(&mut buf.as_slice())
+}
@@ -0,0 +1,266 @@
+//! Registry contents of a vanilla 1.17.1 server
+
+use super::*;
+use BiomeCategory::*;
+use Precipitation::{None as Dry, Rain, Snow};
+
+pub(super) fn dimension_types(registry: &mut Registry<DimensionType>) {
+    let overworld = DimensionType {
+        piglin_safe: false,
+        natural: true,
+        ambient_light: 0.0,
+        fixed_time: Option::None,
+        infiniburn: "minecraft:infiniburn_overworld".to_string(),
+        respawn_anchor_works: false,
+        has_skylight: true,
+        bed_works: true,
+        effects: "minecraft:overworld".to_string(),
+        has_raids: true,
+        min_y: 0,
+        height: 256,
+        logical_height: 256,
+        coordinate_scale: 1.0,
+        ultrawarm: false,
+        has_ceiling: false,
+    };
+    let overworld_caves = DimensionType {
+        has_ceiling: true,
+        ..overworld.clone()
+    };
+    let the_nether = DimensionType {
+        piglin_safe: true,
+        natural: false,
+        ambient_light: 0.1,
+        fixed_time: Some(18000),
+        infiniburn: "minecraft:infiniburn_nether".to_string(),
+        respawn_anchor_works: true,
+        has_skylight: false,
+        bed_works: false,
+        effects: "minecraft:the_nether".to_string(),
+        has_raids: false,
+        min_y: 0,
+        height: 256,
+        logical_height: 128,
+        coordinate_scale: 8.0,
+        ultrawarm: true,
+        has_ceiling: true,
+    };
+    let the_end = DimensionType {
+        piglin_safe: false,
+        natural: false,
+        ambient_light: 0.0,
+        fixed_time: Some(6000),
+        infiniburn: "minecraft:infiniburn_end".to_string(),
+        respawn_anchor_works: false,
+        has_skylight: false,
+        bed_works: false,
+        effects: "minecraft:the_end".to_string(),
+        has_raids: true,
+        min_y: 0,
+        height: 256,
+        logical_height: 256,
+        coordinate_scale: 1.0,
+        ultrawarm: false,
+        has_ceiling: false,
+    };
+    registry.insert_at("minecraft:overworld", 0, overworld);
+    registry.insert_at("minecraft:overworld_caves", 1, overworld_caves);
+    registry.insert_at("minecraft:the_nether", 2, the_nether);
+    registry.insert_at("minecraft:the_end", 3, the_end);
+}
+
+/// Id, name, precipitation, category, depth, scale, temperature and downfall
+type BiomeRow = (
+    i32,
+    &'static str,
+    Precipitation,
+    BiomeCategory,
+    f32,
+    f32,
+    f32,
+    f32,
+);
+
+#[rustfmt::skip]
+const BIOMES: &[BiomeRow] = &[
+    (0, "ocean", Rain, Ocean, -1.0, 0.1, 0.5, 0.5),
+    (1, "plains", Rain, Plains, 0.125, 0.05, 0.8, 0.4),
+    (2, "desert", Dry, Desert, 0.125, 0.05, 2.0, 0.0),
+    (3, "mountains", Rain, ExtremeHills, 1.0, 0.5, 0.2, 0.3),
+    (4, "forest", Rain, Forest, 0.1, 0.2, 0.7, 0.8),
+    (5, "taiga", Rain, Taiga, 0.2, 0.2, 0.25, 0.8),
+    (6, "swamp", Rain, Swamp, -0.2, 0.1, 0.8, 0.9),
+    (7, "river", Rain, River, -0.5, 0.0, 0.5, 0.5),
+    (8, "nether_wastes", Dry, Nether, 0.1, 0.2, 2.0, 0.0),
+    (9, "the_end", Dry, TheEnd, 0.1, 0.2, 0.5, 0.5),
+    (10, "frozen_ocean", Snow, Ocean, -1.0, 0.1, 0.0, 0.5),
+    (11, "frozen_river", Snow, River, -0.5, 0.0, 0.0, 0.5),
+    (12, "snowy_tundra", Snow, Icy, 0.125, 0.05, 0.0, 0.5),
+    (13, "snowy_mountains", Snow, Icy, 0.45, 0.3, 0.0, 0.5),
+    (14, "mushroom_fields", Rain, Mushroom, 0.2, 0.3, 0.9, 1.0),
+    (15, "mushroom_field_shore", Rain, Mushroom, 0.0, 0.025, 0.9, 1.0),
+    (16, "beach", Rain, Beach, 0.0, 0.025, 0.8, 0.4),
+    (17, "desert_hills", Dry, Desert, 0.45, 0.3, 2.0, 0.0),
+    (18, "wooded_hills", Rain, Forest, 0.45, 0.3, 0.7, 0.8),
+    (19, "taiga_hills", Rain, Taiga, 0.45, 0.3, 0.25, 0.8),
+    (20, "mountain_edge", Rain, ExtremeHills, 0.8, 0.3, 0.2, 0.3),
+    (21, "jungle", Rain, Jungle, 0.1, 0.2, 0.95, 0.9),
+    (22, "jungle_hills", Rain, Jungle, 0.45, 0.3, 0.95, 0.9),
+    (23, "jungle_edge", Rain, Jungle, 0.1, 0.2, 0.95, 0.8),
+    (24, "deep_ocean", Rain, Ocean, -1.8, 0.1, 0.5, 0.5),
+    (25, "stone_shore", Rain, None, 0.1, 0.8, 0.2, 0.3),
+    (26, "snowy_beach", Snow, Beach, 0.0, 0.025, 0.05, 0.3),
+    (27, "birch_forest", Rain, Forest, 0.1, 0.2, 0.6, 0.6),
+    (28, "birch_forest_hills", Rain, Forest, 0.45, 0.3, 0.6, 0.6),
+    (29, "dark_forest", Rain, Forest, 0.1, 0.2, 0.7, 0.8),
+    (30, "snowy_taiga", Snow, Taiga, 0.2, 0.2, -0.5, 0.4),
+    (31, "snowy_taiga_hills", Snow, Taiga, 0.45, 0.3, -0.5, 0.4),
+    (32, "giant_tree_taiga", Rain, Taiga, 0.2, 0.2, 0.3, 0.8),
+    (33, "giant_tree_taiga_hills", Rain, Taiga, 0.45, 0.3, 0.3, 0.8),
+    (34, "wooded_mountains", Rain, ExtremeHills, 1.0, 0.5, 0.2, 0.3),
+    (35, "savanna", Dry, Savanna, 0.125, 0.05, 1.2, 0.0),
+    (36, "savanna_plateau", Dry, Savanna, 1.5, 0.025, 1.0, 0.0),
+    (37, "badlands", Dry, Mesa, 0.1, 0.2, 2.0, 0.0),
+    (38, "wooded_badlands_plateau", Dry, Mesa, 1.5, 0.025, 2.0, 0.0),
+    (39, "badlands_plateau", Dry, Mesa, 1.5, 0.025, 2.0, 0.0),
+    (40, "small_end_islands", Dry, TheEnd, 0.1, 0.2, 0.5, 0.5),
+    (41, "end_midlands", Dry, TheEnd, 0.1, 0.2, 0.5, 0.5),
+    (42, "end_highlands", Dry, TheEnd, 0.1, 0.2, 0.5, 0.5),
+    (43, "end_barrens", Dry, TheEnd, 0.1, 0.2, 0.5, 0.5),
+    (44, "warm_ocean", Rain, Ocean, -1.0, 0.1, 0.5, 0.5),
+    (45, "lukewarm_ocean", Rain, Ocean, -1.0, 0.1, 0.5, 0.5),
+    (46, "cold_ocean", Rain, Ocean, -1.0, 0.1, 0.5, 0.5),
+    (47, "deep_warm_ocean", Rain, Ocean, -1.8, 0.1, 0.5, 0.5),
+    (48, "deep_lukewarm_ocean", Rain, Ocean, -1.8, 0.1, 0.5, 0.5),
+    (49, "deep_cold_ocean", Rain, Ocean, -1.8, 0.1, 0.5, 0.5),
+    (50, "deep_frozen_ocean", Rain, Ocean, -1.8, 0.1, 0.5, 0.5),
+    (127, "the_void", Dry, None, 0.1, 0.2, 0.5, 0.5),
+    (129, "sunflower_plains", Rain, Plains, 0.125, 0.05, 0.8, 0.4),
+    (130, "desert_lakes", Dry, Desert, 0.225, 0.25, 2.0, 0.0),
+    (131, "gravelly_mountains", Rain, ExtremeHills, 1.0, 0.5, 0.2, 0.3),
+    (132, "flower_forest", Rain, Forest, 0.1, 0.4, 0.7, 0.8),
+    (133, "taiga_mountains", Rain, Taiga, 0.3, 0.4, 0.25, 0.8),
+    (134, "swamp_hills", Rain, Swamp, -0.1, 0.3, 0.8, 0.9),
+    (140, "ice_spikes", Snow, Icy, 0.425, 0.45, 0.0, 0.5),
+    (149, "modified_jungle", Rain, Jungle, 0.2, 0.4, 0.95, 0.9),
+    (151, "modified_jungle_edge", Rain, Jungle, 0.2, 0.4, 0.95, 0.8),
+    (155, "tall_birch_forest", Rain, Forest, 0.2, 0.4, 0.6, 0.6),
+    (156, "tall_birch_hills", Rain, Forest, 0.55, 0.5, 0.6, 0.6),
+    (157, "dark_forest_hills", Rain, Forest, 0.2, 0.4, 0.7, 0.8),
+    (158, "snowy_taiga_mountains", Snow, Taiga, 0.3, 0.4, -0.5, 0.4),
+    (160, "giant_spruce_taiga", Rain, Taiga, 0.2, 0.2, 0.25, 0.8),
+    (161, "giant_spruce_taiga_hills", Rain, Taiga, 0.2, 0.2, 0.25, 0.8),
+    (162, "modified_gravelly_mountains", Rain, ExtremeHills, 1.0, 0.5, 0.2, 0.3),
+    (163, "shattered_savanna", Dry, Savanna, 0.3625, 1.225, 1.1, 0.0),
+    (164, "shattered_savanna_plateau", Dry, Savanna, 1.05, 1.2125, 1.0, 0.0),
+    (165, "eroded_badlands", Dry, Mesa, 0.1, 0.2, 2.0, 0.0),
+    (166, "modified_wooded_badlands_plateau", Dry, Mesa, 0.45, 0.3, 2.0, 0.0),
+    (167, "modified_badlands_plateau", Dry, Mesa, 0.45, 0.3, 2.0, 0.0),
+    (168, "bamboo_jungle", Rain, Jungle, 0.1, 0.2, 0.95, 0.9),
+    (169, "bamboo_jungle_hills", Rain, Jungle, 0.45, 0.3, 0.95, 0.9),
+    (170, "soul_sand_valley", Dry, Nether, 0.1, 0.2, 2.0, 0.0),
+    (171, "crimson_forest", Dry, Nether, 0.1, 0.2, 2.0, 0.0),
+    (172, "warped_forest", Dry, Nether, 0.1, 0.2, 2.0, 0.0),
+    (173, "basalt_deltas", Dry, Nether, 0.1, 0.2, 2.0, 0.0),
+    (174, "dripstone_caves", Rain, Underground, 0.125, 0.05, 0.8, 0.4),
+    (175, "lush_caves", Rain, Underground, 0.125, 0.05, 0.5, 0.5),
+];
+
+pub(super) fn biomes(registry: &mut Registry<Biome>) {
+    for &(id, name, precipitation, category, depth, scale, temperature, downfall) in BIOMES {
+        let mut biome = Biome {
+            precipitation,
+            depth,
+            temperature,
+            scale,
+            downfall,
+            category,
+            temperature_modifier: Option::None,
+            effects: BiomeEffects::new(temperature),
+            particle: Option::None,
+        };
+        customize(name, &mut biome);
+        registry.insert_at(&format!("minecraft:{}", name), id, biome);
+    }
+}
+
+/// Applies everything which differs from the overworld defaults
+fn customize(name: &str, biome: &mut Biome) {
+    let effects = &mut biome.effects;
+    match name {
+        "frozen_ocean" | "deep_frozen_ocean" => {
+            biome.temperature_modifier = Some(TemperatureModifier::Frozen);
+            effects.water_color = 3750089;
+        }
+        "frozen_river" => effects.water_color = 3750089,
+        "cold_ocean"
+        | "deep_cold_ocean"
+        | "snowy_beach"
+        | "snowy_taiga"
+        | "snowy_taiga_hills"
+        | "snowy_taiga_mountains" => effects.water_color = 4020182,
+        "lukewarm_ocean" | "deep_lukewarm_ocean" => {
+            effects.water_color = 4566514;
+            effects.water_fog_color = 267827;
+        }
+        "warm_ocean" | "deep_warm_ocean" => {
+            effects.water_color = 4445678;
+            effects.water_fog_color = 270131;
+        }
+        "swamp" | "swamp_hills" => {
+            effects.water_color = 6388580;
+            effects.water_fog_color = 2302743;
+            effects.foliage_color = Some(6975545);
+            effects.grass_color_modifier = Some(GrassColorModifier::Swamp);
+        }
+        "dark_forest" | "dark_forest_hills" => {
+            effects.grass_color_modifier = Some(GrassColorModifier::DarkForest);
+        }
+        "badlands"
+        | "wooded_badlands_plateau"
+        | "badlands_plateau"
+        | "eroded_badlands"
+        | "modified_wooded_badlands_plateau"
+        | "modified_badlands_plateau" => {
+            effects.grass_color = Some(9470285);
+            effects.foliage_color = Some(10387789);
+        }
+        "the_end" | "small_end_islands" | "end_midlands" | "end_highlands" | "end_barrens" => {
+            effects.sky_color = 0;
+            effects.fog_color = 10518688;
+        }
+        "nether_wastes" => nether(name, biome, 3344392, Option::None),
+        "soul_sand_valley" => nether(name, biome, 1787717, Some(("ash", 0.00625))),
+        "crimson_forest" => nether(name, biome, 3343107, Some(("crimson_spore", 0.025))),
+        "warped_forest" => nether(name, biome, 1705242, Some(("warped_spore", 0.01428))),
+        "basalt_deltas" => nether(name, biome, 6840176, Some(("white_ash", 0.118_093_33))),
+        _ => (),
+    }
+}
+
+/// Nether biomes have their own fog, sounds and music which are all named after the biome
+fn nether(name: &str, biome: &mut Biome, fog_color: i32, particle: Option<(&str, f32)>) {
+    let effects = &mut biome.effects;
+    effects.fog_color = fog_color;
+    effects.ambient_sound = Some(format!("minecraft:ambient.{}.loop", name));
+    effects.additions_sound = Some(AdditionsSound {
+        sound: format!("minecraft:ambient.{}.additions", name),
+        tick_chance: 0.0111,
+    });
+    effects.mood_sound = Some(MoodSound {
+        sound: format!("minecraft:ambient.{}.mood", name),
+        ..MoodSound::cave()
+    });
+    effects.music = Some(Music {
+        sound: format!("minecraft:music.nether.{}", name),
+        min_delay: 12000,
+        max_delay: 24000,
+        replace_current_music: false,
+    });
+    biome.particle = particle.map(|(kind, probability)| BiomeParticle {
+        probability,
+        options: ParticleOptions {
+            kind: format!("minecraft:{}", kind),
+        },
+    });
+}
@@ -0,0 +1,399 @@
+use crate::codec::{DecodeError, EncodeError, Packet, Transcodeable};
+use crate::types::{
+    Array, Identifier, MaxString, Position, Remaining, Slot, StringN, VarInt, VarLong,
+};
+use bytes::{Buf, BufMut};
+use protocol_derive::{packet, Transcodeable};
+use uuid::Uuid;
+
+#[packet(id = 0x00)]
+pub struct TeleportConfirm(pub VarInt);
+
+#[packet(id = 0x01)]
+pub struct QueryBlockNbt {
+    pub transaction: VarInt,
+    pub location: Position,
+}
+
+#[packet(id = 0x02)]
+pub struct SetDifficulty(pub u8);
+
+#[packet(id = 0x03)]
+pub struct ChatMessage(pub StringN<256>);
+
+#[packet(id = 0x04)]
+pub struct ClientStatus {
+    /// 0 to respawn, 1 to request statistics
+    pub action: VarInt,
+}
+
+#[packet(id = 0x05)]
+pub struct ClientSettings {
+    pub locale: StringN<16>,
+    pub view_distance: i8,
+    pub chat_mode: VarInt,
+    pub chat_colors: bool,
+    pub skin_parts: u8,
+    pub main_hand: VarInt,
+    pub disable_text_filtering: bool,
+}
+
+#[packet(id = 0x06)]
+pub struct TabComplete {
+    pub transaction: VarInt,
+    pub text: StringN<32500>,
+}
+
+#[packet(id = 0x07)]
+pub struct ClickWindowButton {
+    pub window: i8,
+    pub button: i8,
+}
+
+#[packet(id = 0x08)]
+pub struct ClickWindow {
+    pub window: u8,
+    pub state: VarInt,
+    pub slot: i16,
+    pub button: i8,
+    pub mode: VarInt,
+    /// Slots the client expects to have changed
+    pub changed: Array<(i16, Slot), VarInt>,
+    pub carried: Slot,
+}
+
+#[packet(id = 0x09)]
+pub struct CloseWindow(pub u8);
+
+#[packet(id = 0x0A)]
+pub struct PluginMessage {
+    pub channel: Identifier,
+    pub data: Remaining,
+}
+
+#[packet(id = 0x0B)]
+pub struct EditBook {
+    pub hand: VarInt,
+    pub pages: Array<StringN<8192>, VarInt>,
+    /// Present if the book is being signed
+    pub title: Option<StringN<128>>,
+}
+
+#[packet(id = 0x0C)]
+pub struct QueryEntityNbt {
+    pub transaction: VarInt,
+    pub eid: VarInt,
+}
+
+pub struct InteractEntity {
+    pub eid: VarInt,
+    pub action: InteractAction,
+    pub sneaking: bool,
+}
+
+pub enum InteractAction {
+    Interact {
+        hand: VarInt,
+    },
+    Attack,
+    InteractAt {
+        x: f32,
+        y: f32,
+        z: f32,
+        hand: VarInt,
+    },
+}
+
+impl Packet for InteractEntity {
+    fn id() -> i32 {
+        0x0D
+    }
+}
+
+impl Transcodeable for InteractEntity {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.eid.encode(&mut buf)?;
+        match &self.action {
+            InteractAction::Interact { hand } => {
+                VarInt(0).encode(&mut buf)?;
+                hand.encode(&mut buf)?;
+            }
+            InteractAction::Attack => VarInt(1).encode(&mut buf)?,
+            InteractAction::InteractAt { x, y, z, hand } => {
+                VarInt(2).encode(&mut buf)?;
+                x.encode(&mut buf)?;
+                y.encode(&mut buf)?;
+                z.encode(&mut buf)?;
+                hand.encode(&mut buf)?;
+            }
+        }
+        self.sneaking.encode(buf)
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let eid = VarInt::decode(&mut buf)?;
+        let action = match *VarInt::decode(&mut buf)? {
+            0 => InteractAction::Interact {
+                hand: VarInt::decode(&mut buf)?,
+            },
+            1 => InteractAction::Attack,
+            2 => InteractAction::InteractAt {
+                x: f32::decode(&mut buf)?,
+                y: f32::decode(&mut buf)?,
+                z: f32::decode(&mut buf)?,
+                hand: VarInt::decode(&mut buf)?,
+            },
+            _ => return Err(DecodeError::InvalidData),
+        };
+        Ok(InteractEntity {
+            eid,
+            action,
+            sneaking: bool::decode(buf)?,
+        })
+    }
+}
+
+#[packet(id = 0x0E)]
+pub struct GenerateStructure {
+    pub location: Position,
+    pub levels: VarInt,
+    pub keep_jigsaws: bool,
+}
+
+#[packet(id = 0x0F)]
+pub struct KeepAlive(pub i64);
+
+#[packet(id = 0x10)]
+pub struct LockDifficulty(pub bool);
+
+#[packet(id = 0x11)]
+pub struct PlayerPosition {
+    pub x: f64,
+    /// Position of the feet
+    pub y: f64,
+    pub z: f64,
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x12)]
+pub struct PlayerPositionAndRotation {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x13)]
+pub struct PlayerRotation {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x14)]
+pub struct PlayerMovement {
+    pub on_ground: bool,
+}
+
+#[packet(id = 0x15)]
+pub struct VehicleMove {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+#[packet(id = 0x16)]
+pub struct SteerBoat {
+    pub left_paddle: bool,
+    pub right_paddle: bool,
+}
+
+#[packet(id = 0x17)]
+pub struct PickItem(pub VarInt);
+
+#[packet(id = 0x18)]
+pub struct CraftRecipeRequest {
+    pub window: i8,
+    pub recipe: Identifier,
+    pub make_all: bool,
+}
+
+#[packet(id = 0x19)]
+pub struct PlayerAbilities {
+    /// Only 0x02 for flying is read by the server
+    pub flags: i8,
+}
+
+#[packet(id = 0x1A)]
+pub struct PlayerDigging {
+    pub status: VarInt,
+    pub location: Position,
+    pub face: i8,
+}
+
+#[packet(id = 0x1B)]
+pub struct EntityAction {
+    pub eid: VarInt,
+    pub action: VarInt,
+    /// Only used while jumping with a horse
+    pub jump_boost: VarInt,
+}
+
+#[packet(id = 0x1C)]
+pub struct SteerVehicle {
+    pub sideways: f32,
+    pub forward: f32,
+    /// 0x01 to jump, 0x02 to dismount
+    pub flags: u8,
+}
+
+#[packet(id = 0x1D)]
+pub struct Pong(pub i32);
+
+#[packet(id = 0x1E)]
+pub struct SetRecipeBookState {
+    pub book: VarInt,
+    pub open: bool,
+    pub filter_active: bool,
+}
+
+#[packet(id = 0x1F)]
+pub struct SetDisplayedRecipe(pub Identifier);
+
+#[packet(id = 0x20)]
+pub struct NameItem(pub MaxString);
+
+#[packet(id = 0x21)]
+pub struct ResourcePackStatus(pub VarInt);
+
+/// The tab is only sent when it was opened
+#[packet(id = 0x22)]
+pub struct AdvancementTab(pub AdvancementTabAction);
+
+pub enum AdvancementTabAction {
+    Opened(Identifier),
+    Closed,
+}
+
+impl Transcodeable for AdvancementTabAction {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        match self {
+            AdvancementTabAction::Opened(tab) => {
+                VarInt(0).encode(&mut buf)?;
+                tab.encode(buf)
+            }
+            AdvancementTabAction::Closed => VarInt(1).encode(buf),
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        match *VarInt::decode(&mut buf)? {
+            0 => Ok(AdvancementTabAction::Opened(Identifier::decode(buf)?)),
+            1 => Ok(AdvancementTabAction::Closed),
+            _ => Err(DecodeError::InvalidData),
+        }
+    }
+}
+
+#[packet(id = 0x23)]
+pub struct SelectTrade(pub VarInt);
+
+#[packet(id = 0x24)]
+pub struct SetBeaconEffect {
+    pub primary: VarInt,
+    pub secondary: VarInt,
+}
+
+#[packet(id = 0x25)]
+pub struct HeldItemChange(pub i16);
+
+#[packet(id = 0x26)]
+pub struct UpdateCommandBlock {
+    pub location: Position,
+    pub command: MaxString,
+    pub mode: VarInt,
+    pub flags: i8,
+}
+
+#[packet(id = 0x27)]
+pub struct UpdateCommandBlockMinecart {
+    pub eid: VarInt,
+    pub command: MaxString,
+    pub track_output: bool,
+}
+
+#[packet(id = 0x28)]
+pub struct CreativeInventoryAction {
+    pub slot: i16,
+    pub item: Slot,
+}
+
+#[packet(id = 0x29)]
+pub struct UpdateJigsawBlock {
+    pub location: Position,
+    pub name: Identifier,
+    pub target: Identifier,
+    pub pool: Identifier,
+    pub final_state: MaxString,
+    pub joint_type: MaxString,
+}
+
+#[packet(id = 0x2A)]
+pub struct UpdateStructureBlock {
+    pub location: Position,
+    pub action: VarInt,
+    pub mode: VarInt,
+    pub name: MaxString,
+    pub offset: StructureVector,
+    pub size: StructureVector,
+    pub mirror: VarInt,
+    pub rotation: VarInt,
+    pub metadata: MaxString,
+    pub integrity: f32,
+    pub seed: VarLong,
+    pub flags: i8,
+}
+
+#[derive(Transcodeable)]
+pub struct StructureVector {
+    pub x: i8,
+    pub y: i8,
+    pub z: i8,
+}
+
+#[packet(id = 0x2B)]
+pub struct UpdateSign {
+    pub location: Position,
+    pub line_1: StringN<384>,
+    pub line_2: StringN<384>,
+    pub line_3: StringN<384>,
+    pub line_4: StringN<384>,
+}
+
+#[packet(id = 0x2C)]
+pub struct Animation {
+    pub hand: VarInt,
+}
+
+#[packet(id = 0x2D)]
+pub struct Spectate(pub Uuid);
+
+#[packet(id = 0x2E)]
+pub struct PlayerBlockPlacement {
+    pub hand: VarInt,
+    pub location: Position,
+    pub face: VarInt,
+    pub cursor_x: f32,
+    pub cursor_y: f32,
+    pub cursor_z: f32,
+    pub inside_block: bool,
+}
+
+#[packet(id = 0x2F)]
+pub struct UseItem {
+    pub hand: VarInt,
+}
@@ -0,0 +1,246 @@
+use super::{Registry, RegistryError};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Every block and its states, state ids are global and shared by all blocks
+#[derive(Clone)]
+pub struct Blocks {
+    /// Ordered by their first state
+    blocks: Vec<Block>,
+    by_name: HashMap<String, usize>,
+    states: i32,
+}
+
+#[derive(Clone)]
+pub struct Block {
+    pub name: String,
+    /// Id in the `minecraft:block` registry
+    pub id: i32,
+    /// Sorted by name, the last property changes the fastest between consecutive state ids
+    pub properties: Vec<Property>,
+    pub first_state: i32,
+    pub default_state: i32,
+}
+
+#[derive(Clone)]
+pub struct Property {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// A single state of a block
+#[derive(Clone, Copy)]
+pub struct BlockState<'a> {
+    pub block: &'a Block,
+    pub id: i32,
+}
+
+#[derive(Deserialize)]
+struct RawBlock {
+    // sorted, which is also the order vanilla numbers the states in
+    #[serde(default)]
+    properties: BTreeMap<String, Vec<String>>,
+    states: Vec<RawState>,
+}
+
+#[derive(Deserialize)]
+struct RawState {
+    id: i32,
+    #[serde(default)]
+    default: bool,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+}
+
+impl Blocks {
+    pub(super) fn from_json(json: &str, registry: &Registry) -> Result<Self, RegistryError> {
+        let raw: HashMap<String, RawBlock> =
+            serde_json::from_str(json).map_err(|err| RegistryError::Json("blocks.json", err))?;
+
+        let mut blocks = raw
+            .into_iter()
+            .map(|(name, raw)| Block::new(name, raw, registry))
+            .collect::<Result<Vec<_>, _>>()?;
+        blocks.sort_by_key(|block| block.first_state);
+
+        let mut states = 0;
+        for block in &blocks {
+            if block.first_state != states {
+                return Err(RegistryError::Invalid(format!(
+                    "states of {} do not start at {}",
+                    block.name, states
+                )));
+            }
+            states += block.states();
+        }
+        let by_name = blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (block.name.clone(), index))
+            .collect();
+        Ok(Self {
+            blocks,
+            by_name,
+            states,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Block> {
+        self.by_name.get(name).map(|&index| &self.blocks[index])
+    }
+
+    pub fn default_state(&self, name: &str) -> Option<i32> {
+        self.get(name).map(|block| block.default_state)
+    }
+
+    /// Properties which are not given keep the value of the default state
+    pub fn state_id(&self, name: &str, properties: &[(&str, &str)]) -> Option<i32> {
+        self.get(name)?.state_id(properties)
+    }
+
+    pub fn state(&self, id: i32) -> Option<BlockState<'_>> {
+        if !(0..self.states).contains(&id) {
+            return None;
+        }
+        let index = self.blocks.partition_point(|block| block.first_state <= id) - 1;
+        Some(BlockState {
+            block: &self.blocks[index],
+            id,
+        })
+    }
+
+    /// Total number of block states
+    pub fn states(&self) -> i32 {
+        self.states
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter()
+    }
+}
+
+impl Block {
+    fn new(name: String, raw: RawBlock, registry: &Registry) -> Result<Self, RegistryError> {
+        let invalid = |msg: &str| RegistryError::Invalid(format!("{} {}", name, msg));
+        let id = registry
+            .id(&name)
+            .ok_or_else(|| invalid("is not in the block registry"))?;
+        let first_state = raw
+            .states
+            .iter()
+            .map(|state| state.id)
+            .min()
+            .ok_or_else(|| invalid("has no states"))?;
+        let default_state = raw
+            .states
+            .iter()
+            .find(|state| state.default)
+            .ok_or_else(|| invalid("has no default state"))?
+            .id;
+        let block = Self {
+            name: name.clone(),
+            id,
+            properties: raw
+                .properties
+                .into_iter()
+                .map(|(name, values)| Property { name, values })
+                .collect(),
+            first_state,
+            default_state,
+        };
+
+        // make sure the ids can be computed from the property values
+        if raw.states.len() != block.states() as usize {
+            return Err(invalid("has an unexpected number of states"));
+        }
+        for state in &raw.states {
+            let properties = state
+                .properties
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect::<Vec<_>>();
+            if block.state_id(&properties) != Some(state.id) {
+                return Err(invalid(&format!("has an unexpected state id {}", state.id)));
+            }
+        }
+        Ok(block)
+    }
+
+    /// Number of states of this block
+    pub fn states(&self) -> i32 {
+        self.properties
+            .iter()
+            .map(|property| property.values.len() as i32)
+            .product()
+    }
+
+    /// Properties which are not given keep the value of the default state
+    pub fn state_id(&self, properties: &[(&str, &str)]) -> Option<i32> {
+        let mut indices = self.indices(self.default_state);
+        for (name, value) in properties {
+            let (index, property) = self
+                .properties
+                .iter()
+                .enumerate()
+                .find(|(_, property)| property.name == *name)?;
+            indices[index] = property.values.iter().position(|v| v == value)?;
+        }
+        let mut offset = 0;
+        for (property, index) in self.properties.iter().zip(indices) {
+            offset = offset * property.values.len() + index;
+        }
+        Some(self.first_state + offset as i32)
+    }
+
+    /// Index into the values of each property for a state of this block
+    fn indices(&self, state: i32) -> Vec<usize> {
+        let mut offset = (state - self.first_state) as usize;
+        let mut indices = vec![0; self.properties.len()];
+        for (index, property) in self.properties.iter().enumerate().rev() {
+            indices[index] = offset % property.values.len();
+            offset /= property.values.len();
+        }
+        indices
+    }
+}
+
+impl<'a> BlockState<'a> {
+    pub fn name(&self) -> &'a str {
+        &self.block.name
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.id == self.block.default_state
+    }
+
+    pub fn get(&self, property: &str) -> Option<&'a str> {
+        self.properties()
+            .find(|(name, _)| *name == property)
+            .map(|(_, value)| value)
+    }
+
+    pub fn properties(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let block = self.block;
+        block
+            .properties
+            .iter()
+            .zip(block.indices(self.id))
+            .map(|(property, index)| (property.name.as_str(), property.values[index].as_str()))
+    }
+
+    /// Id of the same block with one property changed
+    pub fn with(&self, property: &str, value: &str) -> Option<i32> {
+        let mut properties = self.properties().collect::<Vec<_>>();
+        properties.retain(|(name, _)| *name != property);
+        properties.push((property, value));
+        self.block.state_id(&properties)
+    }
+}
@@ -0,0 +1,187 @@
+//! Numeric ids shared with the client, loaded from the reports of the vanilla data generator
+//!
+//! The reports are created with `java -cp server.jar net.minecraft.data.Main --reports`.
+
+mod blocks;
+mod tags;
+
+pub use blocks::{Block, BlockState, Blocks, Property};
+pub use tags::Tags;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(PathBuf, std::io::Error),
+    /// Name of the report and the parse error
+    Json(&'static str, serde_json::Error),
+    /// Path of a tag file and the parse error
+    Tag(PathBuf, serde_json::Error),
+    /// A registry every server needs is not part of the report
+    Missing(&'static str),
+    Invalid(String),
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Every registry of `registries.json` together with the block states of `blocks.json`
+pub struct Registries {
+    pub blocks: Blocks,
+    pub block_types: Registry,
+    pub items: Registry,
+    pub fluids: Registry,
+    pub entity_types: Registry,
+    pub game_events: Registry,
+    pub sounds: Registry,
+    pub particles: Registry,
+    /// Everything which has no field of its own
+    others: HashMap<String, Registry>,
+}
+
+impl Registries {
+    /// Reads `blocks.json` and `registries.json` from the reports directory
+    pub fn load(dir: &Path) -> Result<Self, RegistryError> {
+        let read = |name: &str| {
+            let path = dir.join(name);
+            std::fs::read_to_string(&path).map_err(|err| RegistryError::Io(path, err))
+        };
+        Self::from_json(&read("blocks.json")?, &read("registries.json")?)
+    }
+
+    pub fn from_json(blocks: &str, registries: &str) -> Result<Self, RegistryError> {
+        let raw: HashMap<String, RawRegistry> = serde_json::from_str(registries)
+            .map_err(|err| RegistryError::Json("registries.json", err))?;
+        let mut others = raw
+            .into_iter()
+            .map(|(name, raw)| Ok((name.clone(), Registry::new(name, raw)?)))
+            .collect::<Result<HashMap<_, _>, RegistryError>>()?;
+        let mut take = |name: &'static str| others.remove(name).ok_or(RegistryError::Missing(name));
+
+        let block_types = take("minecraft:block")?;
+        let items = take("minecraft:item")?;
+        let fluids = take("minecraft:fluid")?;
+        let entity_types = take("minecraft:entity_type")?;
+        let game_events = take("minecraft:game_event")?;
+        let sounds = take("minecraft:sound_event")?;
+        let particles = take("minecraft:particle_type")?;
+        Ok(Self {
+            blocks: Blocks::from_json(blocks, &block_types)?,
+            block_types,
+            items,
+            fluids,
+            entity_types,
+            game_events,
+            sounds,
+            particles,
+            others,
+        })
+    }
+
+    /// Looks up any registry by its name, e.g. `minecraft:motive`
+    pub fn get(&self, name: &str) -> Option<&Registry> {
+        match name {
+            "minecraft:block" => Some(&self.block_types),
+            "minecraft:item" => Some(&self.items),
+            "minecraft:fluid" => Some(&self.fluids),
+            "minecraft:entity_type" => Some(&self.entity_types),
+            "minecraft:game_event" => Some(&self.game_events),
+            "minecraft:sound_event" => Some(&self.sounds),
+            "minecraft:particle_type" => Some(&self.particles),
+            _ => self.others.get(name),
+        }
+    }
+}
+
+/// Maps the names of one registry to their protocol ids and back
+pub struct Registry {
+    name: String,
+    default: Option<String>,
+    /// Indexed by id
+    names: Vec<String>,
+    ids: HashMap<String, i32>,
+}
+
+#[derive(Deserialize)]
+struct RawRegistry {
+    default: Option<String>,
+    entries: HashMap<String, RawEntry>,
+}
+
+#[derive(Deserialize)]
+struct RawEntry {
+    protocol_id: i32,
+}
+
+impl Registry {
+    fn new(name: String, raw: RawRegistry) -> Result<Self, RegistryError> {
+        let mut names = vec![None; raw.entries.len()];
+        for (entry, RawEntry { protocol_id }) in &raw.entries {
+            match names.get_mut(*protocol_id as usize) {
+                Some(slot @ None) => *slot = Some(entry.clone()),
+                _ => {
+                    return Err(RegistryError::Invalid(format!(
+                        "{} has an unexpected id {} for {}",
+                        name, protocol_id, entry
+                    )))
+                }
+            }
+        }
+        let names = names.into_iter().flatten().collect::<Vec<_>>();
+        let ids = raw
+            .entries
+            .into_iter()
+            .map(|(entry, raw)| (entry, raw.protocol_id))
+            .collect();
+        Ok(Self {
+            name,
+            default: raw.default,
+            names,
+            ids,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self, name: &str) -> Option<i32> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn get(&self, id: i32) -> Option<&str> {
+        self.names
+            .get(usize::try_from(id).ok()?)
+            .map(String::as_str)
+    }
+
+    /// Id used in place of unknown names, if the registry has a default
+    pub fn default_id(&self) -> Option<i32> {
+        self.id(self.default.as_ref()?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Names ordered by id
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (id as i32, name.as_str()))
+    }
+}
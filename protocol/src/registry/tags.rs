@@ -0,0 +1,229 @@
+use super::{Registries, Registry, RegistryError};
+use crate::packets::play::clientbound;
+use crate::types::{Array, Identifier, StringN, VarInt};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Directory below `data/<namespace>/tags` and the registry its tags refer to
+const KINDS: &[(&str, &str)] = &[
+    ("blocks", "minecraft:block"),
+    ("items", "minecraft:item"),
+    ("fluids", "minecraft:fluid"),
+    ("entity_types", "minecraft:entity_type"),
+    ("game_events", "minecraft:game_event"),
+];
+
+/// Named groups of registry entries, as defined by the `tags` of data packs
+pub struct Tags {
+    /// Registry name and the resolved tags of that registry
+    kinds: Vec<(&'static str, BTreeMap<String, Vec<i32>>)>,
+}
+
+#[derive(Deserialize)]
+struct RawTag {
+    #[serde(default)]
+    replace: bool,
+    values: Vec<RawValue>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawValue {
+    Id(String),
+    Entry {
+        id: String,
+        #[serde(default = "required")]
+        required: bool,
+    },
+}
+
+fn required() -> bool {
+    true
+}
+
+impl RawValue {
+    fn split(self) -> (String, bool) {
+        match self {
+            RawValue::Id(id) => (id, true),
+            RawValue::Entry { id, required } => (id, required),
+        }
+    }
+}
+
+impl Tags {
+    /// Reads `data/*/tags/**/*.json` of every pack, a later pack adds to the tags of an earlier
+    /// one unless it sets `replace`
+    pub fn load(packs: &[PathBuf], registries: &Registries) -> Result<Self, RegistryError> {
+        let mut kinds = Vec::with_capacity(KINDS.len());
+        for &(dir, name) in KINDS {
+            let registry = registries.get(name).ok_or(RegistryError::Missing(name))?;
+            let mut raw = BTreeMap::new();
+            for pack in packs {
+                read_pack(pack, dir, &mut raw)?;
+            }
+            let mut resolved = BTreeMap::new();
+            for tag in raw.keys() {
+                resolve(tag, &raw, registry, &mut resolved, &mut Vec::new())?;
+            }
+            kinds.push((name, resolved));
+        }
+        Ok(Self { kinds })
+    }
+
+    /// Ids of a tag, e.g. `get("minecraft:block", "minecraft:climbable")`
+    pub fn get(&self, registry: &str, tag: &str) -> Option<&[i32]> {
+        self.kinds
+            .iter()
+            .find(|(name, _)| *name == registry)?
+            .1
+            .get(tag)
+            .map(Vec::as_slice)
+    }
+
+    pub fn contains(&self, registry: &str, tag: &str, id: i32) -> bool {
+        self.get(registry, tag).is_some_and(|ids| ids.contains(&id))
+    }
+
+    /// Number of tags over all registries
+    pub fn len(&self) -> usize {
+        self.kinds.iter().map(|(_, tags)| tags.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn packet(&self) -> clientbound::Tags {
+        let ident = |name: &str| StringN(Cow::Owned(name.to_string()));
+        clientbound::Tags(Array::new(
+            self.kinds
+                .iter()
+                .map(|(registry, tags)| {
+                    let tags = tags
+                        .iter()
+                        .map(|(name, ids)| clientbound::Tag {
+                            name: ident(name),
+                            entries: Array::new(ids.iter().copied().map(VarInt).collect()),
+                        })
+                        .collect();
+                    (Identifier::from_static(registry), Array::new(tags))
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Merges the unresolved entries of a pack into `raw`
+fn read_pack(
+    pack: &Path,
+    kind: &str,
+    raw: &mut BTreeMap<String, Vec<(String, bool)>>,
+) -> Result<(), RegistryError> {
+    let data = pack.join("data");
+    for namespace in read_dir(&data)? {
+        let dir = namespace.join("tags").join(kind);
+        if !dir.is_dir() {
+            continue;
+        }
+        let namespace = namespace.file_name().unwrap_or_default().to_string_lossy();
+        let mut files = Vec::new();
+        find_files(&dir, "", &mut files)?;
+        for (path, file) in files {
+            let json = std::fs::read_to_string(&file)
+                .map_err(|err| RegistryError::Io(file.clone(), err))?;
+            let tag: RawTag =
+                serde_json::from_str(&json).map_err(|err| RegistryError::Tag(file, err))?;
+            let entries = raw.entry(format!("{}:{}", namespace, path)).or_default();
+            if tag.replace {
+                entries.clear();
+            }
+            entries.extend(tag.values.into_iter().map(RawValue::split));
+        }
+    }
+    Ok(())
+}
+
+/// Collects the `.json` files below `dir`, together with their path relative to it without the
+/// extension
+fn find_files(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, PathBuf)>,
+) -> Result<(), RegistryError> {
+    for path in read_dir(dir)? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            find_files(&path, &format!("{}{}/", prefix, name), files)?;
+        } else if let Some(name) = name.strip_suffix(".json") {
+            files.push((format!("{}{}", prefix, name), path.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Entries of a directory, sorted so the tags are read in the same order everywhere
+fn read_dir(dir: &Path) -> Result<Vec<PathBuf>, RegistryError> {
+    let io = |err| RegistryError::Io(dir.to_path_buf(), err);
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(io)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io)?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn resolve(
+    tag: &str,
+    raw: &BTreeMap<String, Vec<(String, bool)>>,
+    registry: &Registry,
+    resolved: &mut BTreeMap<String, Vec<i32>>,
+    stack: &mut Vec<String>,
+) -> Result<(), RegistryError> {
+    if resolved.contains_key(tag) {
+        return Ok(());
+    }
+    if stack.iter().any(|name| name == tag) {
+        return Err(RegistryError::Invalid(format!(
+            "tag {} references itself through {}",
+            tag,
+            stack.join(" -> ")
+        )));
+    }
+    stack.push(tag.to_string());
+
+    let mut ids = Vec::new();
+    for (entry, required) in &raw[tag] {
+        let found = match entry.strip_prefix('#') {
+            Some(reference) if raw.contains_key(reference) => {
+                resolve(reference, raw, registry, resolved, stack)?;
+                ids.extend_from_slice(&resolved[reference]);
+                true
+            }
+            Some(_) => false,
+            None => match registry.id(entry) {
+                Some(id) => {
+                    ids.push(id);
+                    true
+                }
+                None => false,
+            },
+        };
+        if !found && *required {
+            return Err(RegistryError::Invalid(format!(
+                "tag {} of {} references the unknown {}",
+                tag,
+                registry.name(),
+                entry
+            )));
+        }
+    }
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+
+    stack.pop();
+    resolved.insert(tag.to_string(), ids);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::codec::{DecodeError, EncodeError, Transcodeable};
+use crate::types::VarInt;
+use bytes::{Buf, BufMut};
+use std::iter::FromIterator;
+
+/// Bits packed into longs, sent like `java.util.BitSet::toLongArray` as a length prefixed array
+/// without trailing zero longs
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct BitSet(Vec<u64>);
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_longs(mut longs: Vec<u64>) -> Self {
+        while longs.last() == Some(&0) {
+            longs.pop();
+        }
+        Self(longs)
+    }
+
+    pub fn longs(&self) -> &[u64] {
+        &self.0
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.0
+            .get(index / 64)
+            .is_some_and(|long| long & 1 << (index % 64) != 0)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        let long = index / 64;
+        if value {
+            if self.0.len() <= long {
+                self.0.resize(long + 1, 0);
+            }
+            self.0[long] |= 1 << (index % 64);
+        } else if let Some(bits) = self.0.get_mut(long) {
+            *bits &= !(1 << (index % 64));
+            while self.0.last() == Some(&0) {
+                self.0.pop();
+            }
+        }
+    }
+
+    /// Number of set bits
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|long| long.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Indices of the set bits in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(index, &long)| {
+            (0..64)
+                .filter(move |bit| long & 1 << bit != 0)
+                .map(move |bit| index * 64 + bit)
+        })
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for index in iter {
+            set.set(index, true);
+        }
+        set
+    }
+}
+
+impl Transcodeable for BitSet {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        VarInt(self.0.len() as i32).encode(&mut buf)?;
+        for long in &self.0 {
+            buf.put_u64(*long);
+        }
+        Ok(())
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let len = *VarInt::decode(&mut buf)?;
+        if len < 0 {
+            return Err(DecodeError::InvalidData);
+        }
+        // checked before allocating, the length is sent by the client
+        if buf.remaining() / 8 < len as usize {
+            return Err(DecodeError::ToLittleData);
+        }
+        Ok(Self((0..len).map(|_| buf.get_u64()).collect()))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(VarInt(self.0.len() as i32).size_hint()? + self.0.len() * 8)
+    }
+}
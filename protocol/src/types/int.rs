@@ -1,8 +1,7 @@
 use crate::codec::{DecodeError, EncodeError, SizeTranscodable, Transcodeable};
 use bytes::{Buf, BufMut};
 use std::fmt::{Debug, Formatter};
-use std::ops::{Deref, DerefMut, Div};
-use tracing::field::debug;
+use std::ops::{Deref, DerefMut};
 
 #[derive(Copy, Clone)]
 pub struct VarInt(pub i32);
@@ -98,6 +97,10 @@ impl Transcodeable for VarLong {
     }
 
     fn size_hint(&self) -> Option<usize> {
+        if self.0 == 0 {
+            return Some(1);
+        }
+
         let used = 64 - self.0.leading_zeros() as usize;
         let bytes = used / 7;
         Some(match used % 7 {
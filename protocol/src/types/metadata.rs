@@ -0,0 +1,164 @@
+use crate::codec::{DecodeError, EncodeError, Transcodeable};
+use crate::types::{Chat, MaxString, OptionalNbt, Particle, Position, Slot, VarInt};
+use bytes::{Buf, BufMut};
+use uuid::Uuid;
+
+/// Marks the end of the entries instead of an index
+const END: u8 = 0xFF;
+
+/// Entity metadata entries, each one is an index and a value
+pub struct Metadata(pub Vec<(u8, MetadataValue)>);
+
+pub enum MetadataValue {
+    Byte(i8),
+    VarInt(VarInt),
+    Float(f32),
+    String(MaxString),
+    Chat(Chat),
+    OptChat(Option<Chat>),
+    Slot(Slot),
+    Boolean(bool),
+    Rotation(f32, f32, f32),
+    Position(Position),
+    OptPosition(Option<Position>),
+    Direction(VarInt),
+    OptUuid(Option<Uuid>),
+    /// 0 is air, which also stands for no block
+    OptBlockState(VarInt),
+    Nbt(OptionalNbt),
+    Particle(Particle),
+    VillagerData {
+        kind: VarInt,
+        profession: VarInt,
+        level: VarInt,
+    },
+    OptVarInt(Option<VarInt>),
+    Pose(VarInt),
+}
+
+impl MetadataValue {
+    fn kind(&self) -> i32 {
+        match self {
+            MetadataValue::Byte(_) => 0,
+            MetadataValue::VarInt(_) => 1,
+            MetadataValue::Float(_) => 2,
+            MetadataValue::String(_) => 3,
+            MetadataValue::Chat(_) => 4,
+            MetadataValue::OptChat(_) => 5,
+            MetadataValue::Slot(_) => 6,
+            MetadataValue::Boolean(_) => 7,
+            MetadataValue::Rotation(..) => 8,
+            MetadataValue::Position(_) => 9,
+            MetadataValue::OptPosition(_) => 10,
+            MetadataValue::Direction(_) => 11,
+            MetadataValue::OptUuid(_) => 12,
+            MetadataValue::OptBlockState(_) => 13,
+            MetadataValue::Nbt(_) => 14,
+            MetadataValue::Particle(_) => 15,
+            MetadataValue::VillagerData { .. } => 16,
+            MetadataValue::OptVarInt(_) => 17,
+            MetadataValue::Pose(_) => 18,
+        }
+    }
+}
+
+impl Transcodeable for MetadataValue {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        VarInt(self.kind()).encode(&mut buf)?;
+        match self {
+            MetadataValue::Byte(value) => value.encode(buf),
+            MetadataValue::VarInt(value)
+            | MetadataValue::Direction(value)
+            | MetadataValue::OptBlockState(value)
+            | MetadataValue::Pose(value) => value.encode(buf),
+            MetadataValue::Float(value) => value.encode(buf),
+            MetadataValue::String(value) => value.encode(buf),
+            MetadataValue::Chat(value) => value.encode(buf),
+            MetadataValue::OptChat(value) => value.encode(buf),
+            MetadataValue::Slot(value) => value.encode(buf),
+            MetadataValue::Boolean(value) => value.encode(buf),
+            MetadataValue::Rotation(x, y, z) => {
+                x.encode(&mut buf)?;
+                y.encode(&mut buf)?;
+                z.encode(buf)
+            }
+            MetadataValue::Position(value) => value.encode(buf),
+            MetadataValue::OptPosition(value) => value.encode(buf),
+            MetadataValue::OptUuid(value) => value.encode(buf),
+            MetadataValue::Nbt(value) => value.encode(buf),
+            MetadataValue::Particle(value) => value.encode(buf),
+            MetadataValue::VillagerData {
+                kind,
+                profession,
+                level,
+            } => {
+                kind.encode(&mut buf)?;
+                profession.encode(&mut buf)?;
+                level.encode(buf)
+            }
+            // shifted by one, so 0 can mean absent
+            MetadataValue::OptVarInt(value) => {
+                VarInt(value.map_or(0, |value| *value + 1)).encode(buf)
+            }
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        Ok(match *VarInt::decode(&mut buf)? {
+            0 => MetadataValue::Byte(Transcodeable::decode(buf)?),
+            1 => MetadataValue::VarInt(Transcodeable::decode(buf)?),
+            2 => MetadataValue::Float(Transcodeable::decode(buf)?),
+            3 => MetadataValue::String(Transcodeable::decode(buf)?),
+            4 => MetadataValue::Chat(Transcodeable::decode(buf)?),
+            5 => MetadataValue::OptChat(Transcodeable::decode(buf)?),
+            6 => MetadataValue::Slot(Transcodeable::decode(buf)?),
+            7 => MetadataValue::Boolean(Transcodeable::decode(buf)?),
+            8 => MetadataValue::Rotation(
+                f32::decode(&mut buf)?,
+                f32::decode(&mut buf)?,
+                f32::decode(buf)?,
+            ),
+            9 => MetadataValue::Position(Transcodeable::decode(buf)?),
+            10 => MetadataValue::OptPosition(Transcodeable::decode(buf)?),
+            11 => MetadataValue::Direction(Transcodeable::decode(buf)?),
+            12 => MetadataValue::OptUuid(Transcodeable::decode(buf)?),
+            13 => MetadataValue::OptBlockState(Transcodeable::decode(buf)?),
+            14 => MetadataValue::Nbt(Transcodeable::decode(buf)?),
+            15 => MetadataValue::Particle(Transcodeable::decode(buf)?),
+            16 => MetadataValue::VillagerData {
+                kind: VarInt::decode(&mut buf)?,
+                profession: VarInt::decode(&mut buf)?,
+                level: VarInt::decode(buf)?,
+            },
+            17 => MetadataValue::OptVarInt(match *VarInt::decode(buf)? {
+                0 => None,
+                value => Some(VarInt(value - 1)),
+            }),
+            18 => MetadataValue::Pose(Transcodeable::decode(buf)?),
+            _ => return Err(DecodeError::InvalidData),
+        })
+    }
+}
+
+impl Transcodeable for Metadata {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        for (index, value) in &self.0 {
+            if *index == END {
+                return Err(EncodeError::InputToLong);
+            }
+            index.encode(&mut buf)?;
+            value.encode(&mut buf)?;
+        }
+        END.encode(buf)
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let mut entries = Vec::new();
+        loop {
+            match u8::decode(&mut buf)? {
+                END => return Ok(Metadata(entries)),
+                index => entries.push((index, MetadataValue::decode(&mut buf)?)),
+            }
+        }
+    }
+}
@@ -1,16 +1,25 @@
+mod bitset;
 mod chat;
 mod int;
 mod json;
+mod metadata;
 mod nbt;
+mod particle;
 mod pos;
+mod slot;
 mod string;
 mod uuid;
 mod vec;
 
+pub use self::nbt::*;
 pub use ::nbt::*;
+pub use bitset::*;
 pub use chat::*;
 pub use int::*;
 pub use json::*;
+pub use metadata::*;
+pub use particle::*;
 pub use pos::*;
+pub use slot::*;
 pub use string::*;
 pub use vec::*;
@@ -3,11 +3,39 @@ use crate::iob::{BufMutWrapper, BufWrapper};
 use bytes::{Buf, BufMut};
 
 impl Transcodeable for nbt::Blob {
+    // the serde functions guess the tag types when reading, so a blob would not survive a round trip
     fn encode<B: BufMut>(&self, buf: B) -> Result<(), EncodeError> {
-        nbt::to_writer(&mut BufMutWrapper(buf), self, None).map_err(EncodeError::NbtError)
+        self.to_writer(&mut BufMutWrapper(buf))
+            .map_err(EncodeError::NbtError)
     }
 
     fn decode<B: Buf>(buf: B) -> Result<Self, DecodeError> {
-        nbt::from_reader(&mut BufWrapper(buf)).map_err(|_| DecodeError::InvalidData)
+        nbt::Blob::from_reader(&mut BufWrapper(buf)).map_err(|_| DecodeError::InvalidData)
+    }
+}
+
+/// A compound which may be left out, a single `TAG_End` is sent in its place
+pub struct OptionalNbt(pub Option<nbt::Blob>);
+
+impl Transcodeable for OptionalNbt {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        match &self.0 {
+            Some(blob) => blob.encode(buf),
+            None => {
+                buf.put_u8(0);
+                Ok(())
+            }
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        match buf.chunk().first() {
+            None => Err(DecodeError::ToLittleData),
+            Some(0) => {
+                buf.advance(1);
+                Ok(OptionalNbt(None))
+            }
+            Some(_) => Ok(OptionalNbt(Some(nbt::Blob::decode(buf)?))),
+        }
     }
 }
@@ -0,0 +1,158 @@
+use crate::codec::{DecodeError, EncodeError, Transcodeable};
+use crate::types::{Identifier, Position, Slot, VarInt};
+use bytes::{Buf, BufMut};
+
+const BLOCK: i32 = 4;
+const DUST: i32 = 15;
+const DUST_COLOR_TRANSITION: i32 = 16;
+const FALLING_DUST: i32 = 25;
+const ITEM: i32 = 36;
+const VIBRATION: i32 = 37;
+
+/// A particle as sent in entity metadata, the particle packet writes the id as a plain int
+pub struct Particle {
+    pub id: VarInt,
+    pub data: ParticleData,
+}
+
+/// Extra data of the few particles which have some, which one is present depends on the id
+pub enum ParticleData {
+    None,
+    Block(VarInt),
+    Dust {
+        red: f32,
+        green: f32,
+        blue: f32,
+        scale: f32,
+    },
+    DustColorTransition {
+        from: (f32, f32, f32),
+        scale: f32,
+        to: (f32, f32, f32),
+    },
+    FallingDust(VarInt),
+    Item(Slot),
+    Vibration {
+        origin: (f64, f64, f64),
+        destination: VibrationDestination,
+        ticks: VarInt,
+    },
+}
+
+/// Where a sculk vibration travels to
+pub enum VibrationDestination {
+    Block(Position),
+    Entity(VarInt),
+}
+
+impl ParticleData {
+    pub fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        match self {
+            ParticleData::None => {}
+            ParticleData::Block(state) | ParticleData::FallingDust(state) => state.encode(buf)?,
+            ParticleData::Dust {
+                red,
+                green,
+                blue,
+                scale,
+            } => {
+                for value in [red, green, blue, scale] {
+                    value.encode(&mut buf)?;
+                }
+            }
+            ParticleData::DustColorTransition { from, scale, to } => {
+                for value in [from.0, from.1, from.2, *scale, to.0, to.1, to.2] {
+                    value.encode(&mut buf)?;
+                }
+            }
+            ParticleData::Item(item) => item.encode(buf)?,
+            ParticleData::Vibration {
+                origin,
+                destination,
+                ticks,
+            } => {
+                origin.0.encode(&mut buf)?;
+                origin.1.encode(&mut buf)?;
+                origin.2.encode(&mut buf)?;
+                destination.encode(&mut buf)?;
+                ticks.encode(buf)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the data belonging to particle `id`
+    pub fn decode<B: Buf>(id: i32, mut buf: B) -> Result<Self, DecodeError> {
+        Ok(match id {
+            BLOCK => ParticleData::Block(VarInt::decode(buf)?),
+            DUST => ParticleData::Dust {
+                red: f32::decode(&mut buf)?,
+                green: f32::decode(&mut buf)?,
+                blue: f32::decode(&mut buf)?,
+                scale: f32::decode(&mut buf)?,
+            },
+            DUST_COLOR_TRANSITION => ParticleData::DustColorTransition {
+                from: (
+                    f32::decode(&mut buf)?,
+                    f32::decode(&mut buf)?,
+                    f32::decode(&mut buf)?,
+                ),
+                scale: f32::decode(&mut buf)?,
+                to: (
+                    f32::decode(&mut buf)?,
+                    f32::decode(&mut buf)?,
+                    f32::decode(&mut buf)?,
+                ),
+            },
+            FALLING_DUST => ParticleData::FallingDust(VarInt::decode(buf)?),
+            ITEM => ParticleData::Item(Slot::decode(buf)?),
+            VIBRATION => ParticleData::Vibration {
+                origin: (
+                    f64::decode(&mut buf)?,
+                    f64::decode(&mut buf)?,
+                    f64::decode(&mut buf)?,
+                ),
+                destination: VibrationDestination::decode(&mut buf)?,
+                ticks: VarInt::decode(buf)?,
+            },
+            _ => ParticleData::None,
+        })
+    }
+}
+
+impl Transcodeable for Particle {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        self.id.encode(&mut buf)?;
+        self.data.encode(buf)
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let id = VarInt::decode(&mut buf)?;
+        let data = ParticleData::decode(*id, buf)?;
+        Ok(Particle { id, data })
+    }
+}
+
+impl Transcodeable for VibrationDestination {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        match self {
+            VibrationDestination::Block(position) => {
+                Identifier::from_static("minecraft:block").encode(&mut buf)?;
+                position.encode(buf)
+            }
+            VibrationDestination::Entity(entity) => {
+                Identifier::from_static("minecraft:entity").encode(&mut buf)?;
+                entity.encode(buf)
+            }
+        }
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        let kind = Identifier::decode(&mut buf)?;
+        match &*kind {
+            "minecraft:block" => Ok(VibrationDestination::Block(Position::decode(buf)?)),
+            "minecraft:entity" => Ok(VibrationDestination::Entity(VarInt::decode(buf)?)),
+            _ => Err(DecodeError::InvalidData),
+        }
+    }
+}
@@ -1,10 +1,21 @@
 use crate::codec::{DecodeError, EncodeError, Transcodeable};
 use bytes::{Buf, BufMut};
 
+/// Rotation in steps of 1/256 of a full turn
+pub type Angle = u8;
+
+/// A block position packed into 64 bits
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Position {
-    x: i32,
-    y: i32,
-    z: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
 }
 
 impl Transcodeable for Position {
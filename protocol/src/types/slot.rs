@@ -0,0 +1,12 @@
+use crate::types::{OptionalNbt, VarInt};
+use protocol_derive::Transcodeable;
+
+/// An inventory slot, `None` if it is empty
+pub type Slot = Option<ItemStack>;
+
+#[derive(Transcodeable)]
+pub struct ItemStack {
+    pub item: VarInt,
+    pub count: i8,
+    pub nbt: OptionalNbt,
+}
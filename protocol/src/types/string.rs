@@ -17,6 +17,12 @@ pub type Identifier = StringN<MAX_CHARS_IDENT>;
 #[derive(Clone)]
 pub struct StringN<const N: usize>(pub Cow<'static, str>);
 
+impl<const N: usize> StringN<N> {
+    pub const fn from_static(str: &'static str) -> Self {
+        StringN(Cow::Borrowed(str))
+    }
+}
+
 impl<const N: usize> Transcodeable for StringN<N> {
     fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
         let str = self.0.as_ref();
@@ -39,7 +45,7 @@ impl<const N: usize> Transcodeable for StringN<N> {
         if buf.remaining() < len {
             return Err(DecodeError::ToLittleData);
         }
-        let data = (&buf.chunk()[..len]).to_vec();
+        let data = buf.chunk()[..len].to_vec();
         buf.advance(len);
 
         let string = String::from_utf8(data).map_err(|_| DecodeError::InvalidData)?;
@@ -10,7 +10,7 @@ impl Transcodeable for Uuid {
 
     fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
         if buf.remaining() < 16 {
-            return Err(DecodeError::ToLittleData);
+            Err(DecodeError::ToLittleData)
         } else {
             Ok(Uuid::from_u128(buf.get_u128()))
         }
@@ -1,5 +1,5 @@
 use crate::codec::{DecodeError, EncodeError, SizeTranscodable, Transcodeable};
-use bytes::{Buf, BufMut};
+use bytes::{Buf, BufMut, Bytes};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
@@ -15,14 +15,15 @@ impl<T: Transcodeable, I: SizeTranscodable> Transcodeable for Array<T, I> {
     fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
         I::encode_usize(self.0.len(), &mut buf)?;
         for item in &self.0 {
-            item.encode(&mut buf);
+            item.encode(&mut buf)?;
         }
         Ok(())
     }
 
     fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
         let len = I::decode_usize(&mut buf)?;
-        let mut vec = Vec::with_capacity(len);
+        // the length comes from the peer, reserve no more than the remaining bytes could hold
+        let mut vec = Vec::with_capacity(len.min(buf.remaining()));
 
         for _ in 0..len {
             vec.push(T::decode(&mut buf)?);
@@ -45,3 +46,21 @@ impl<T: Transcodeable, I: SizeTranscodable> DerefMut for Array<T, I> {
         &mut self.0
     }
 }
+
+/// Raw bytes filling the rest of the packet, without a length prefix
+pub struct Remaining(pub Bytes);
+
+impl Transcodeable for Remaining {
+    fn encode<B: BufMut>(&self, mut buf: B) -> Result<(), EncodeError> {
+        buf.put_slice(&self.0);
+        Ok(())
+    }
+
+    fn decode<B: Buf>(mut buf: B) -> Result<Self, DecodeError> {
+        Ok(Remaining(buf.copy_to_bytes(buf.remaining())))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
@@ -0,0 +1,330 @@
+//! Reads chunks saved by vanilla 1.17 in the Anvil format, region files of 32x32 chunks in the
+//! `region` directory of a world
+
+use super::gen::Generator;
+use super::{BitArray, Chunk};
+use crate::registry::Blocks;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Region files are split into sectors of this many bytes, the header takes the first two
+const SECTOR: u64 = 4096;
+
+const GZIP: u8 = 1;
+const ZLIB: u8 = 2;
+const UNCOMPRESSED: u8 = 3;
+
+#[derive(Debug)]
+pub enum AnvilError {
+    Io(std::io::Error),
+    Nbt(nbt::Error),
+    /// An unknown compression scheme. Chunks which are too large for a region file live in a
+    /// `c.<x>.<z>.mcc` file instead, which is flagged with 128 and not supported.
+    Compression(u8),
+    /// The length of a chunk does not fit into its sectors
+    Length(u32),
+    /// Position of a chunk which is stored in the region file of another position
+    Position(i32, i32),
+    /// Section y of block states which do not match the palette
+    BlockStates(i8),
+    UnknownBlock(String),
+    UnknownBiome(i32),
+    /// Length of a biome array which does not match the height of the dimension
+    Biomes(usize),
+}
+
+impl Display for AnvilError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for AnvilError {}
+
+impl From<std::io::Error> for AnvilError {
+    fn from(err: std::io::Error) -> Self {
+        AnvilError::Io(err)
+    }
+}
+
+impl From<nbt::Error> for AnvilError {
+    fn from(err: nbt::Error) -> Self {
+        AnvilError::Nbt(err)
+    }
+}
+
+/// Header of a region file and the file itself, chunks are read on demand
+pub struct RegionFile<R> {
+    reader: R,
+    /// Offset in sectors shifted left by 8 and the number of sectors, 0 for missing chunks
+    locations: Vec<u32>,
+}
+
+impl RegionFile<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self, AnvilError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> RegionFile<R> {
+    /// Reads the header, the reader has to be at the start of the region file
+    pub fn new(mut reader: R) -> Result<Self, AnvilError> {
+        let mut header = [0; 4096];
+        reader.read_exact(&mut header)?;
+        let locations = header
+            .chunks_exact(4)
+            .map(|entry| u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]))
+            .collect();
+        Ok(Self { reader, locations })
+    }
+
+    /// Whether a chunk was saved, x and z are chunk coordinates of which only the lowest 5 bits
+    /// matter
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        self.locations[index(x, z)] != 0
+    }
+
+    /// The uncompressed NBT of a chunk, `None` if it was never saved
+    pub fn read(&mut self, x: i32, z: i32) -> Result<Option<Vec<u8>>, AnvilError> {
+        let location = self.locations[index(x, z)];
+        if location == 0 {
+            return Ok(None);
+        }
+        let (offset, sectors) = ((location >> 8) as u64, location & 0xFF);
+        self.reader.seek(SeekFrom::Start(offset * SECTOR))?;
+        let mut header = [0; 5];
+        self.reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        // the length includes the compression byte
+        if length == 0 || length as u64 + 4 > sectors as u64 * SECTOR {
+            return Err(AnvilError::Length(length));
+        }
+        let mut compressed = vec![0; length as usize - 1];
+        self.reader.read_exact(&mut compressed)?;
+
+        let mut nbt = Vec::new();
+        match header[4] {
+            GZIP => {
+                flate2::read::GzDecoder::new(&compressed[..]).read_to_end(&mut nbt)?;
+            }
+            ZLIB => {
+                flate2::read::ZlibDecoder::new(&compressed[..]).read_to_end(&mut nbt)?;
+            }
+            UNCOMPRESSED => nbt = compressed,
+            compression => return Err(AnvilError::Compression(compression)),
+        }
+        Ok(Some(nbt))
+    }
+
+    /// The parsed NBT of a chunk, `None` if it was never saved
+    pub fn chunk(&mut self, x: i32, z: i32) -> Result<Option<AnvilChunk>, AnvilError> {
+        let nbt = match self.read(x, z)? {
+            Some(nbt) => nbt,
+            None => return Ok(None),
+        };
+        let chunk: AnvilChunk = nbt::from_reader(&nbt[..])?;
+        Ok(Some(chunk))
+    }
+}
+
+fn index(x: i32, z: i32) -> usize {
+    ((z & 31) << 5 | (x & 31)) as usize
+}
+
+/// The parts of a saved chunk the server uses
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AnvilChunk {
+    #[serde(rename = "DataVersion", default)]
+    pub data_version: i32,
+    #[serde(rename = "Level")]
+    pub level: Level,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Level {
+    #[serde(rename = "xPos")]
+    pub x: i32,
+    #[serde(rename = "zPos")]
+    pub z: i32,
+    /// Generation step the chunk reached, chunks other than `full` are still being generated
+    #[serde(rename = "Status", default)]
+    pub status: String,
+    #[serde(rename = "Sections", default)]
+    pub sections: Vec<AnvilSection>,
+    /// A biome id for every 4x4x4 blocks from the bottom of the world up
+    #[serde(rename = "Biomes", default)]
+    pub biomes: Vec<i32>,
+    /// Packed like block states, with enough bits for the height of the world
+    #[serde(rename = "Heightmaps", default)]
+    pub heightmaps: HashMap<String, Vec<i64>>,
+    #[serde(rename = "TileEntities", default)]
+    pub block_entities: Vec<nbt::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AnvilSection {
+    /// Section y, the sections below and above the world only contain light
+    #[serde(rename = "Y")]
+    pub y: i8,
+    #[serde(rename = "Palette", default)]
+    pub palette: Vec<PaletteEntry>,
+    /// Indices into the palette with at least 4 bits, in the same order as sections on the wire
+    #[serde(rename = "BlockStates", default)]
+    pub block_states: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PaletteEntry {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Properties", default)]
+    pub properties: HashMap<String, String>,
+}
+
+impl AnvilChunk {
+    /// Whether the chunk finished generating
+    pub fn is_full(&self) -> bool {
+        matches!(self.level.status.as_str(), "full" | "minecraft:full")
+    }
+
+    /// Copies the blocks and biomes into a chunk of the same dimension
+    pub fn load(&self, chunk: &mut Chunk, blocks: &Blocks) -> Result<(), AnvilError> {
+        for section in &self.level.sections {
+            if section.palette.is_empty() {
+                continue;
+            }
+            let states = section
+                .palette
+                .iter()
+                .map(|entry| {
+                    let properties = entry
+                        .properties
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value.as_str()))
+                        .collect::<Vec<_>>();
+                    blocks
+                        .state_id(&entry.name, &properties)
+                        .ok_or_else(|| AnvilError::UnknownBlock(entry.name.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let target = match chunk.section_mut(section.y as i32 * 16) {
+                Some(target) => target,
+                None => continue,
+            };
+            if let [state] = states[..] {
+                target.blocks.fill(state);
+                continue;
+            }
+            let bits = super::bits_for(states.len()).max(4);
+            let longs = section
+                .block_states
+                .iter()
+                .map(|&long| long as u64)
+                .collect();
+            let data = BitArray::from_longs(bits, 4096, longs)
+                .ok_or(AnvilError::BlockStates(section.y))?;
+            for (index, entry) in data.iter().enumerate() {
+                let state = *states
+                    .get(entry as usize)
+                    .ok_or(AnvilError::BlockStates(section.y))?;
+                target.blocks.set(index, state);
+            }
+        }
+
+        let biomes = &self.level.biomes;
+        if !biomes.is_empty() {
+            if biomes.len() != chunk.sections().len() * 64 {
+                return Err(AnvilError::Biomes(biomes.len()));
+            }
+            for (section, biomes) in chunk.sections_mut().iter_mut().zip(biomes.chunks(64)) {
+                for (index, &biome) in biomes.iter().enumerate() {
+                    if !(0..1 << section.biomes.global_bits()).contains(&biome) {
+                        return Err(AnvilError::UnknownBiome(biome));
+                    }
+                    section.biomes.set(index, biome);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+type OpenRegion = RegionFile<BufReader<File>>;
+
+/// Serves the chunks of a vanilla world directory, chunks which were never saved or did not
+/// finish generating come from another generator
+pub struct Anvil {
+    /// The `region` directory of the world
+    regions: PathBuf,
+    blocks: Blocks,
+    fallback: Box<dyn Generator>,
+    /// Region files opened so far, `None` for regions without a file
+    files: Mutex<HashMap<(i32, i32), Option<OpenRegion>>>,
+}
+
+impl Anvil {
+    pub fn new(world: &Path, blocks: Blocks, fallback: Box<dyn Generator>) -> Self {
+        Self {
+            regions: world.join("region"),
+            blocks,
+            fallback,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A saved chunk, `None` if it was never saved
+    pub fn read(&self, x: i32, z: i32) -> Result<Option<AnvilChunk>, AnvilError> {
+        let mut files = self.files.lock().unwrap();
+        let region = (x >> 5, z >> 5);
+        let file = match files.get_mut(&region) {
+            Some(file) => file,
+            None => {
+                let path = self
+                    .regions
+                    .join(format!("r.{}.{}.mca", region.0, region.1));
+                let file = match RegionFile::open(&path) {
+                    Ok(file) => Some(file),
+                    Err(AnvilError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(err) => return Err(err),
+                };
+                files.entry(region).or_insert(file)
+            }
+        };
+        match file {
+            Some(file) => file.chunk(x, z),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Generator for Anvil {
+    fn generate(&self, chunk: &mut Chunk, x: i32, z: i32) {
+        let saved = match self.read(x, z) {
+            Ok(Some(saved)) if saved.is_full() => saved,
+            Ok(_) => return self.fallback.generate(chunk, x, z),
+            Err(err) => {
+                tracing::warn!("unable to read chunk; x={}; z={}; err={}", x, z, err);
+                return self.fallback.generate(chunk, x, z);
+            }
+        };
+        if saved.level.x != x || saved.level.z != z {
+            let err = AnvilError::Position(saved.level.x, saved.level.z);
+            tracing::warn!("unable to read chunk; x={}; z={}; err={}", x, z, err);
+            return self.fallback.generate(chunk, x, z);
+        }
+        // loaded into a copy, so a broken chunk leaves nothing behind for the fallback
+        let mut loaded = chunk.clone();
+        match saved.load(&mut loaded, &self.blocks) {
+            Ok(()) => *chunk = loaded,
+            Err(err) => {
+                tracing::warn!("unable to load chunk; x={}; z={}; err={}", x, z, err);
+                self.fallback.generate(chunk, x, z);
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use super::{BitArray, Chunk, PalettedContainer, Storage};
+use crate::codec::{EncodeError, Transcodeable};
+use crate::packets::play::clientbound::{ChunkData, UpdateLight};
+use crate::packets::play::dim::DimensionType;
+use crate::registry::Blocks;
+use crate::types::{Array, BitSet, VarInt};
+use bytes::BufMut;
+
+/// Blocks which do not count towards the block count of a section and the heightmap
+const AIR_BLOCKS: &[&str] = &["minecraft:air", "minecraft:cave_air", "minecraft:void_air"];
+
+/// Light data of a single section, two levels per byte
+const LIGHT_LEN: usize = 2048;
+
+/// Turns chunk columns into the packets sent to clients
+pub struct ChunkEncoder {
+    /// Sorted state ids of the air blocks
+    air: Vec<i32>,
+    sky_light: bool,
+}
+
+impl ChunkEncoder {
+    pub fn new(blocks: &Blocks, dimension: &DimensionType) -> Self {
+        let mut air = AIR_BLOCKS
+            .iter()
+            .filter_map(|name| blocks.get(name))
+            .flat_map(|block| block.first_state..block.first_state + block.states())
+            .collect::<Vec<_>>();
+        air.sort_unstable();
+        Self {
+            air,
+            sky_light: dimension.has_skylight,
+        }
+    }
+
+    fn is_air(&self, state: i32) -> bool {
+        self.air.binary_search(&state).is_ok()
+    }
+
+    /// Every section which contains more than air, without block entities
+    pub fn chunk_data(&self, chunk: &Chunk, x: i32, z: i32) -> Result<ChunkData, EncodeError> {
+        let mut mask = BitSet::new();
+        let mut data = Vec::new();
+        for (index, section) in chunk.sections().iter().enumerate() {
+            let blocks = section.blocks.count(|state| !self.is_air(state));
+            if blocks == 0 {
+                continue;
+            }
+            mask.set(index, true);
+            data.put_i16(blocks as i16);
+            encode_blocks(&section.blocks, &mut data)?;
+        }
+
+        let biomes = chunk
+            .sections()
+            .iter()
+            .flat_map(|section| (0..64).map(move |index| VarInt(section.biomes.get(index))))
+            .collect();
+
+        let surface = self.surface(chunk);
+        let mut heightmap = BitArray::new(super::bits_for(chunk.height() as usize + 1), 256);
+        for (index, height) in surface.iter().enumerate() {
+            heightmap.set(index, (height - chunk.min_y()) as u32);
+        }
+        let mut heightmaps = nbt::Blob::new();
+        heightmaps
+            .insert(
+                "MOTION_BLOCKING",
+                nbt::Value::LongArray(heightmap.longs().iter().map(|&long| long as i64).collect()),
+            )
+            .map_err(EncodeError::NbtError)?;
+
+        Ok(ChunkData {
+            x,
+            z,
+            mask,
+            heightmaps,
+            biomes: Array::new(biomes),
+            data: Array::new(data),
+            block_entities: Array::new(vec![]),
+        })
+    }
+
+    /// Sky light of 15 above the highest block of every column and none below it, there are no
+    /// light sources. Sections above the highest block are left out, the client assumes full sky
+    /// light above the highest section it knows.
+    pub fn update_light(&self, chunk: &Chunk, x: i32, z: i32) -> UpdateLight {
+        // the section below and the one above the column are part of the light data
+        let sections = chunk.sections().len() + 2;
+        let mut sky_light_mask = BitSet::new();
+        let mut empty_sky_light_mask = BitSet::new();
+        let mut sky_light = Vec::new();
+        if self.sky_light {
+            let surface = self.surface(chunk);
+            let top = surface.iter().copied().max().unwrap_or(chunk.min_y());
+            let last = ((top - chunk.min_y()).div_euclid(16) + 1).min(sections as i32 - 1);
+            for index in 0..=last as usize {
+                let min_y = chunk.min_y() + (index as i32 - 1) * 16;
+                let light = section_sky_light(&surface, min_y);
+                if light.iter().all(|&level| level == 0) {
+                    empty_sky_light_mask.set(index, true);
+                } else {
+                    sky_light_mask.set(index, true);
+                    sky_light.push(Array::new(light));
+                }
+            }
+        }
+        UpdateLight {
+            x: VarInt(x),
+            z: VarInt(z),
+            trust_edges: true,
+            sky_light_mask,
+            block_light_mask: BitSet::new(),
+            empty_sky_light_mask,
+            empty_block_light_mask: (0..sections).collect(),
+            sky_light: Array::new(sky_light),
+            block_light: Array::new(vec![]),
+        }
+    }
+
+    /// Lowest y above the highest block of each column, ordered by z then x
+    fn surface(&self, chunk: &Chunk) -> Vec<i32> {
+        let mut surface = vec![chunk.min_y(); 256];
+        for (index, height) in surface.iter_mut().enumerate() {
+            let (x, z) = (index & 15, index >> 4);
+            let column = chunk.sections().iter().enumerate().rev();
+            'sections: for (section_index, section) in column {
+                if section.is_empty() {
+                    continue;
+                }
+                for y in (0..16).rev() {
+                    if !self.is_air(section.block(x, y, z)) {
+                        *height = chunk.min_y() + section_index as i32 * 16 + y as i32 + 1;
+                        break 'sections;
+                    }
+                }
+            }
+        }
+        surface
+    }
+}
+
+fn section_sky_light(surface: &[i32], min_y: i32) -> Vec<u8> {
+    let mut light = vec![0; LIGHT_LEN];
+    for y in 0..16 {
+        for (column, &height) in surface.iter().enumerate() {
+            if min_y + y as i32 >= height {
+                let index = y << 8 | column;
+                // the first entry is in the low nibble
+                light[index / 2] |= 15 << (index % 2 * 4);
+            }
+        }
+    }
+    light
+}
+
+/// Bits per entry, palette and packed entries of a section's block states
+fn encode_blocks(blocks: &PalettedContainer, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+    let (min_bits, _) = blocks.kind().indirect_bits();
+    let single;
+    let (palette, data) = match blocks.storage() {
+        // the client has no single value palette, the value is sent as a palette of one
+        Storage::Single(value) => {
+            single = BitArray::new(min_bits, blocks.kind().entries());
+            (Some(std::slice::from_ref(value)), &single)
+        }
+        Storage::Indirect { palette, data } => (Some(palette.as_slice()), data),
+        Storage::Direct(data) => (None, data),
+    };
+    buf.put_u8(data.bits());
+    if let Some(palette) = palette {
+        VarInt(palette.len() as i32).encode(&mut *buf)?;
+        for &value in palette {
+            VarInt(value).encode(&mut *buf)?;
+        }
+    }
+    VarInt(data.longs().len() as i32).encode(&mut *buf)?;
+    for &long in data.longs() {
+        buf.put_u64(long);
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use super::Random;
+use crate::world::{Chunk, AIR};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+/// Caves may start this many chunks away from the chunks they are carved into
+const RANGE: i32 = 8;
+
+/// Worm-like tunnels and round rooms, carved one chunk at a time. Every chunk computes the caves
+/// starting in all chunks nearby and carves the parts inside of itself, so the result does not
+/// depend on the order chunks are generated in.
+pub(super) struct Caves<'a> {
+    chunk: &'a mut Chunk,
+    x: i32,
+    z: i32,
+    /// Whether a state may be replaced with air
+    carvable: &'a dyn Fn(i32) -> bool,
+    /// Caves stop before they would let water flow in
+    water: i32,
+}
+
+impl<'a> Caves<'a> {
+    pub(super) fn new(
+        chunk: &'a mut Chunk,
+        x: i32,
+        z: i32,
+        carvable: &'a dyn Fn(i32) -> bool,
+        water: i32,
+    ) -> Self {
+        Self {
+            chunk,
+            x,
+            z,
+            carvable,
+            water,
+        }
+    }
+
+    /// `salt` and the world seed seed a random for each chunk caves start in
+    pub(super) fn carve(&mut self, seed: i64, salt: i64) {
+        let bottom = self.chunk.min_y();
+        for source_x in self.x - RANGE..=self.x + RANGE {
+            for source_z in self.z - RANGE..=self.z + RANGE {
+                let mut random = Random::for_chunk(seed, source_x, source_z, salt);
+                // about one in seven chunks has caves
+                if random.next_bounded(7) != 0 {
+                    continue;
+                }
+                let systems = random.next_bounded(15) + 1;
+                let systems = random.next_bounded(systems) + 1;
+                let systems = random.next_bounded(systems);
+                for _ in 0..systems {
+                    let x = (source_x * 16 + random.next_bounded(16)) as f64;
+                    let y = random.next_bounded(120) + 8;
+                    let y = (bottom + random.next_bounded(y)) as f64;
+                    let z = (source_z * 16 + random.next_bounded(16)) as f64;
+                    let mut tunnels = 1;
+                    if random.next_bounded(4) == 0 {
+                        let radius = 1.0 + random.next_f64() * 6.0;
+                        self.sphere(x, y, z, radius, radius * 0.5);
+                        tunnels += random.next_bounded(4);
+                    }
+                    for _ in 0..tunnels {
+                        let tunnel = Tunnel {
+                            x,
+                            y,
+                            z,
+                            yaw: random.next_f64() * TAU,
+                            pitch: (random.next_f64() - 0.5) / 4.0,
+                            thickness: random.next_f64() * 2.0 + random.next_f64(),
+                        };
+                        let length = 112 - random.next_bounded(28);
+                        self.tunnel(random.next_i64(), tunnel, length, true);
+                    }
+                }
+            }
+        }
+    }
+
+    fn tunnel(&mut self, seed: i64, mut tunnel: Tunnel, length: i32, branches: bool) {
+        let mut random = Random::new(seed);
+        let branch_at = random.next_bounded((length / 2).max(1)) + length / 4;
+        let (center_x, center_z) = (self.x as f64 * 16.0 + 8.0, self.z as f64 * 16.0 + 8.0);
+        let mut yaw_change = 0.0;
+        let mut pitch_change = 0.0;
+        for step in 0..length {
+            let radius = 1.5 + (step as f64 * PI / length as f64).sin() * tunnel.thickness;
+            let cos = tunnel.pitch.cos();
+            tunnel.x += tunnel.yaw.cos() * cos;
+            tunnel.y += tunnel.pitch.sin();
+            tunnel.z += tunnel.yaw.sin() * cos;
+            tunnel.pitch = tunnel.pitch * 0.7 + pitch_change * 0.1;
+            tunnel.yaw += yaw_change * 0.1;
+            pitch_change = pitch_change * 0.9
+                + (random.next_f64() - random.next_f64()) * random.next_f64() * 2.0;
+            yaw_change = yaw_change * 0.75
+                + (random.next_f64() - random.next_f64()) * random.next_f64() * 4.0;
+
+            if branches && step == branch_at && tunnel.thickness > 1.0 {
+                for turn in [-FRAC_PI_2, FRAC_PI_2] {
+                    let branch = Tunnel {
+                        yaw: tunnel.yaw + turn,
+                        pitch: tunnel.pitch / 3.0,
+                        thickness: random.next_f64() * 0.5 + 0.5,
+                        ..tunnel
+                    };
+                    self.tunnel(random.next_i64(), branch, length - step, false);
+                }
+                return;
+            }
+            if random.next_bounded(4) == 0 {
+                continue;
+            }
+            // gives up once the rest of the tunnel can not reach the chunk anymore
+            let (dx, dz) = (tunnel.x - center_x, tunnel.z - center_z);
+            let remaining = (length - step) as f64;
+            let reach = remaining + tunnel.thickness + 2.0 + 16.0;
+            if dx * dx + dz * dz > reach * reach {
+                return;
+            }
+            self.sphere(tunnel.x, tunnel.y, tunnel.z, radius, radius * 0.7);
+        }
+    }
+
+    /// Carves an ellipsoid unless it touches water
+    fn sphere(&mut self, x: f64, y: f64, z: f64, radius: f64, vertical: f64) {
+        let (min_x, min_z) = (self.x * 16, self.z * 16);
+        let bottom = self.chunk.min_y();
+        // one block of margin to find water next to the cave
+        let range = |center: f64, radius: f64, min: i32, max: i32| {
+            let low = ((center - radius).floor() as i32 - 1).max(min);
+            let high = ((center + radius).floor() as i32 + 1).min(max);
+            low..=high
+        };
+        let xs = range(x, radius, min_x, min_x + 15);
+        let ys = range(y, vertical, bottom + 1, bottom + self.chunk.height() - 1);
+        let zs = range(z, radius, min_z, min_z + 15);
+        if xs.is_empty() || ys.is_empty() || zs.is_empty() {
+            return;
+        }
+        for block_x in xs.clone() {
+            for block_z in zs.clone() {
+                for block_y in ys.clone() {
+                    let local = ((block_x - min_x) as usize, (block_z - min_z) as usize);
+                    if self.chunk.block(local.0, block_y, local.1) == self.water {
+                        return;
+                    }
+                }
+            }
+        }
+
+        for block_x in xs {
+            let dx = (block_x as f64 + 0.5 - x) / radius;
+            for block_z in zs.clone() {
+                let dz = (block_z as f64 + 0.5 - z) / radius;
+                for block_y in ys.clone() {
+                    let dy = (block_y as f64 + 0.5 - y) / vertical;
+                    if dx * dx + dy * dy + dz * dz >= 1.0 {
+                        continue;
+                    }
+                    let local = ((block_x - min_x) as usize, (block_z - min_z) as usize);
+                    if (self.carvable)(self.chunk.block(local.0, block_y, local.1)) {
+                        self.chunk.set_block(local.0, block_y, local.1, AIR);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Tunnel {
+    x: f64,
+    y: f64,
+    z: f64,
+    yaw: f64,
+    pitch: f64,
+    thickness: f64,
+}
@@ -0,0 +1,234 @@
+use super::{GeneratorError, Random, Region};
+use crate::registry::Blocks;
+use crate::world::AIR;
+
+/// States of the blocks trees and ores are made of
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FeatureBlocks {
+    pub oak: Wood,
+    pub birch: Wood,
+    pub spruce: Wood,
+    pub jungle: Wood,
+    pub coal_ore: i32,
+    pub iron_ore: i32,
+    pub copper_ore: i32,
+    pub gold_ore: i32,
+    pub redstone_ore: i32,
+    pub lapis_ore: i32,
+    pub diamond_ore: i32,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Wood {
+    pub log: i32,
+    pub leaves: i32,
+}
+
+impl FeatureBlocks {
+    /// Upright logs and leaves which never decay
+    pub fn from_registry(blocks: &Blocks) -> Result<Self, GeneratorError> {
+        let state = |name: &str, properties: &[(&str, &str)]| {
+            blocks
+                .state_id(name, properties)
+                .ok_or_else(|| GeneratorError::UnknownBlock(name.to_string()))
+        };
+        let wood = |name: &str| {
+            Ok(Wood {
+                log: state(&format!("minecraft:{}_log", name), &[("axis", "y")])?,
+                leaves: state(
+                    &format!("minecraft:{}_leaves", name),
+                    &[("persistent", "true")],
+                )?,
+            })
+        };
+        Ok(Self {
+            oak: wood("oak")?,
+            birch: wood("birch")?,
+            spruce: wood("spruce")?,
+            jungle: wood("jungle")?,
+            coal_ore: state("minecraft:coal_ore", &[])?,
+            iron_ore: state("minecraft:iron_ore", &[])?,
+            copper_ore: state("minecraft:copper_ore", &[])?,
+            gold_ore: state("minecraft:gold_ore", &[])?,
+            redstone_ore: state("minecraft:redstone_ore", &[])?,
+            lapis_ore: state("minecraft:lapis_ore", &[])?,
+            diamond_ore: state("minecraft:diamond_ore", &[])?,
+        })
+    }
+
+    /// Ore veins of every chunk, heights are relative to the bottom of the world
+    pub(super) fn ores(&self) -> [Ore; 7] {
+        [
+            Ore::new(self.coal_ore, 17, 20, 0, 128),
+            Ore::new(self.iron_ore, 9, 20, 0, 64),
+            Ore::new(self.copper_ore, 10, 6, 0, 96),
+            Ore::new(self.gold_ore, 9, 2, 0, 32),
+            Ore::new(self.redstone_ore, 8, 8, 0, 16),
+            Ore::new(self.lapis_ore, 7, 1, 0, 32),
+            Ore::new(self.diamond_ore, 8, 1, 0, 16),
+        ]
+    }
+
+    /// Where decorations overlap the one which ranks highest stays, logs win over leaves and
+    /// everything else is ranked by state
+    fn rank(&self, state: i32) -> (u8, i32) {
+        let woods = [self.oak, self.birch, self.spruce, self.jungle];
+        let kind = if woods.iter().any(|wood| wood.log == state) {
+            2
+        } else if woods.iter().any(|wood| wood.leaves == state) {
+            1
+        } else {
+            0
+        };
+        (kind, state)
+    }
+}
+
+/// Decorates a block unless a decoration which ranks higher is already there. Chunks decorate
+/// parts of their neighbours, ranking their decorations makes the result the same whichever chunk
+/// is decorated first.
+fn decorate(region: &mut Region, features: &FeatureBlocks, x: i32, y: i32, z: i32, state: i32) {
+    match region.decoration(x, y, z) {
+        Some(current) if features.rank(current) >= features.rank(state) => (),
+        _ => {
+            region.set_block(x, y, z, state);
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(super) enum Tree {
+    /// A short trunk in a round crown
+    Oak(Wood),
+    /// A trunk in a cone of leaves
+    Spruce(Wood),
+    /// Like oak but taller
+    Tall(Wood),
+}
+
+impl Tree {
+    /// Grows a tree on the block above `ground`, returns false without changing anything if there
+    /// is no room or the ground is not `soil`
+    pub(super) fn place(
+        self,
+        region: &mut Region,
+        features: &FeatureBlocks,
+        random: &mut Random,
+        (x, ground, z): (i32, i32, i32),
+        soil: i32,
+        dirt: i32,
+    ) -> bool {
+        let height = match self {
+            Tree::Oak(_) => 4 + random.next_bounded(3),
+            Tree::Spruce(_) => 6 + random.next_bounded(4),
+            Tree::Tall(_) => 8 + random.next_bounded(5),
+        };
+        let max_y = region.min_y() + region.height() - 1;
+        if region.block(x, ground, z) != soil || ground + height + 1 > max_y {
+            return false;
+        }
+        if (1..=height).any(|dy| region.block(x, ground + dy, z) != AIR) {
+            return false;
+        }
+
+        let wood = match self {
+            Tree::Oak(wood) | Tree::Spruce(wood) | Tree::Tall(wood) => wood,
+        };
+        let top = ground + height;
+        match self {
+            Tree::Oak(_) | Tree::Tall(_) => {
+                for y in top - 2..=top + 1 {
+                    let radius: i32 = if y >= top { 1 } else { 2 };
+                    for dx in -radius..=radius {
+                        for dz in -radius..=radius {
+                            // the corners are cut off at random, and always at the top
+                            let corner = dx.abs() == radius && dz.abs() == radius;
+                            if corner && (y > top || random.next_bounded(2) == 0) {
+                                continue;
+                            }
+                            leaves(region, features, x + dx, y, z + dz, wood.leaves);
+                        }
+                    }
+                }
+            }
+            Tree::Spruce(_) => {
+                // from the tip down, every other layer is wider
+                for (layer, y) in (ground + 3..=top + 1).rev().enumerate() {
+                    let radius: i32 = match layer {
+                        0 => 0,
+                        layer if layer % 2 == 1 => 1,
+                        _ => 2,
+                    };
+                    for dx in -radius..=radius {
+                        for dz in -radius..=radius {
+                            if radius == 2 && dx.abs() == 2 && dz.abs() == 2 {
+                                continue;
+                            }
+                            leaves(region, features, x + dx, y, z + dz, wood.leaves);
+                        }
+                    }
+                }
+            }
+        }
+        for y in ground + 1..=top {
+            decorate(region, features, x, y, z, wood.log);
+        }
+        decorate(region, features, x, ground, z, dirt);
+        true
+    }
+}
+
+/// Leaves never replace anything but air
+fn leaves(region: &mut Region, features: &FeatureBlocks, x: i32, y: i32, z: i32, state: i32) {
+    if region.block(x, y, z) == AIR {
+        decorate(region, features, x, y, z, state);
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(super) struct Ore {
+    state: i32,
+    /// Blocks per vein at most
+    size: i32,
+    /// Veins per chunk
+    count: i32,
+    min_y: i32,
+    max_y: i32,
+}
+
+impl Ore {
+    const fn new(state: i32, size: i32, count: i32, min_y: i32, max_y: i32) -> Self {
+        Self {
+            state,
+            size,
+            count,
+            min_y,
+            max_y,
+        }
+    }
+
+    /// Places all veins of a chunk, ores only replace `host` blocks
+    pub(super) fn place(
+        &self,
+        region: &mut Region,
+        features: &FeatureBlocks,
+        random: &mut Random,
+        host: i32,
+    ) {
+        let bottom = region.min_y();
+        for _ in 0..self.count {
+            let mut x = region.x() * 16 + random.next_bounded(16);
+            let mut z = region.z() * 16 + random.next_bounded(16);
+            let mut y = bottom + self.min_y + random.next_bounded(self.max_y - self.min_y);
+            // a random walk, so veins are clumps of about the size of the ore
+            for _ in 0..self.size {
+                if region.block(x, y, z) == host {
+                    decorate(region, features, x, y, z, self.state);
+                }
+                x += random.next_bounded(3) - 1;
+                y += random.next_bounded(3) - 1;
+                z += random.next_bounded(3) - 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::{qualify, Generator, GeneratorError};
+use crate::packets::play::dim::DimensionCodec;
+use crate::registry::Blocks;
+use crate::world::{Chunk, AIR};
+
+/// Layers and biome of vanilla's "Classic Flat" preset
+pub const CLASSIC_FLAT: &str =
+    "minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains";
+
+/// Horizontal layers of blocks from the bottom of the world up, with air above
+pub struct Flat {
+    layers: Vec<Layer>,
+    biome: i32,
+    /// State of every y above the bottom of the world which is not air
+    column: Vec<i32>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Layer {
+    pub state: i32,
+    pub height: u32,
+}
+
+impl Flat {
+    pub fn new(layers: Vec<Layer>, biome: i32) -> Self {
+        let column = layers
+            .iter()
+            .flat_map(|layer| std::iter::repeat_n(layer.state, layer.height as usize))
+            .collect();
+        Self {
+            layers,
+            biome,
+            column,
+        }
+    }
+
+    /// Parses a preset string of the superflat customization screen, e.g. [`CLASSIC_FLAT`]. Layers are
+    /// separated by commas and may be prefixed with a count like `3*minecraft:stone`, the biome
+    /// defaults to plains and structures are ignored.
+    pub fn from_preset(
+        preset: &str,
+        blocks: &Blocks,
+        codec: &DimensionCodec,
+    ) -> Result<Self, GeneratorError> {
+        let mut parts = preset.split(';');
+        let layers = parts
+            .next()
+            .filter(|layers| !layers.trim().is_empty())
+            .ok_or_else(|| GeneratorError::Preset(preset.to_string()))?
+            .split(',')
+            .map(|layer| parse_layer(layer.trim(), blocks))
+            .collect::<Result<Vec<_>, _>>()?;
+        let biome = qualify(parts.next().map_or("plains", str::trim));
+        let biome = codec
+            .biomes
+            .get(&biome)
+            .ok_or(GeneratorError::UnknownBiome(biome))?
+            .id;
+        Ok(Self::new(layers, biome))
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn biome(&self) -> i32 {
+        self.biome
+    }
+}
+
+fn parse_layer(layer: &str, blocks: &Blocks) -> Result<Layer, GeneratorError> {
+    let (height, block) = match layer.split_once('*') {
+        Some((height, block)) => {
+            let height = height
+                .trim()
+                .parse()
+                .map_err(|_| GeneratorError::Preset(layer.to_string()))?;
+            (height, block.trim())
+        }
+        None => (1, layer),
+    };
+    let block = qualify(block);
+    let state = blocks
+        .default_state(&block)
+        .ok_or(GeneratorError::UnknownBlock(block))?;
+    Ok(Layer { state, height })
+}
+
+impl Generator for Flat {
+    fn generate(&self, chunk: &mut Chunk, _x: i32, _z: i32) {
+        for (index, section) in chunk.sections_mut().iter_mut().enumerate() {
+            section.biomes.fill(self.biome);
+            let states = (0..16)
+                .map(|y| self.column.get(index * 16 + y).copied().unwrap_or(AIR))
+                .collect::<Vec<_>>();
+            if states.iter().all(|&state| state == states[0]) {
+                section.blocks.fill(states[0]);
+                continue;
+            }
+            for (y, &state) in states.iter().enumerate() {
+                if state == AIR {
+                    continue;
+                }
+                for x in 0..16 {
+                    for z in 0..16 {
+                        section.set_block(x, y, z, state);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_flat(&self) -> bool {
+        true
+    }
+}
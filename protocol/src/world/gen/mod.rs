@@ -0,0 +1,90 @@
+//! Fills new chunks with blocks and biomes
+
+mod carver;
+mod feature;
+mod flat;
+mod noise;
+mod perlin;
+mod random;
+mod region;
+mod void;
+
+pub use feature::{FeatureBlocks, Wood};
+pub use flat::{Flat, Layer, CLASSIC_FLAT};
+pub use noise::{Noise, NoiseBlocks, SEA_LEVEL};
+pub use perlin::{OctaveNoise, Perlin};
+pub use random::Random;
+pub use region::Region;
+pub use void::Void;
+
+use super::Chunk;
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+
+pub trait Generator: Send + Sync {
+    /// Fills a chunk of air, x and z are chunk coordinates
+    fn generate(&self, chunk: &mut Chunk, x: i32, z: i32);
+
+    /// Places features like trees which may reach into the neighbouring chunks, once all of them
+    /// have been generated. Must only depend on the seed and the position of the region.
+    fn decorate(&self, _region: &mut Region) {}
+
+    /// Whether [`Generator::decorate`] does anything, chunks of generators without decorations
+    /// are handed out without generating their neighbours first
+    fn has_decorations(&self) -> bool {
+        false
+    }
+
+    /// Whether clients render the horizon at the bottom of the world and leave out the void fog
+    fn is_flat(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    /// A preset string which can not be parsed
+    Preset(String),
+    UnknownBlock(String),
+    UnknownBiome(String),
+}
+
+impl Display for GeneratorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+/// Prefixes names without a namespace with `minecraft:`, like vanilla resource locations
+fn qualify(name: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    }
+}
+
+/// Parses the `level-seed` of server.properties like vanilla: numbers are used as they are and any
+/// other text is hashed like `String.hashCode`. Empty seeds and 0 mean a random seed.
+pub fn parse_seed(seed: &str) -> Option<i64> {
+    let seed = seed.trim();
+    if seed.is_empty() {
+        return None;
+    }
+    let seed = seed.parse().unwrap_or_else(|_| {
+        seed.encode_utf16()
+            .fold(0i32, |hash, c| hash.wrapping_mul(31).wrapping_add(c as i32)) as i64
+    });
+    Some(seed).filter(|&seed| seed != 0)
+}
+
+/// The first 8 bytes of the SHA-256 of the seed, clients use it for biome noise without learning
+/// the seed itself
+pub fn hashed_seed(seed: i64) -> i64 {
+    let hash = Sha256::digest(&seed.to_le_bytes());
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    i64::from_le_bytes(bytes)
+}
@@ -0,0 +1,373 @@
+use super::carver::Caves;
+use super::feature::Tree;
+use super::{FeatureBlocks, Generator, GeneratorError, OctaveNoise, Random, Region};
+use crate::packets::play::dim::DimensionCodec;
+use crate::registry::Blocks;
+use crate::world::Chunk;
+
+/// Height of the water surface in oceans, the same as vanilla
+pub const SEA_LEVEL: i32 = 63;
+
+/// States of the blocks the terrain is made of
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NoiseBlocks {
+    pub stone: i32,
+    pub dirt: i32,
+    pub grass: i32,
+    pub sand: i32,
+    pub water: i32,
+    pub bedrock: i32,
+}
+
+impl NoiseBlocks {
+    /// Default states of the vanilla blocks
+    pub fn from_registry(blocks: &Blocks) -> Result<Self, GeneratorError> {
+        let state = |name: &str| {
+            blocks
+                .default_state(name)
+                .ok_or_else(|| GeneratorError::UnknownBlock(name.to_string()))
+        };
+        Ok(Self {
+            stone: state("minecraft:stone")?,
+            dirt: state("minecraft:dirt")?,
+            grass: state("minecraft:grass_block")?,
+            sand: state("minecraft:sand")?,
+            water: state("minecraft:water")?,
+            bedrock: state("minecraft:bedrock")?,
+        })
+    }
+}
+
+/// The biomes the generator picks from, in the order of their ids in [`Noise::biomes`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Biome {
+    FrozenOcean,
+    ColdOcean,
+    Ocean,
+    LukewarmOcean,
+    WarmOcean,
+    SnowyBeach,
+    Beach,
+    SnowyMountains,
+    Mountains,
+    SnowyTundra,
+    SnowyTaiga,
+    Taiga,
+    Plains,
+    Forest,
+    BirchForest,
+    DarkForest,
+    Desert,
+    Savanna,
+    Jungle,
+}
+
+const BIOMES: [&str; 19] = [
+    "minecraft:frozen_ocean",
+    "minecraft:cold_ocean",
+    "minecraft:ocean",
+    "minecraft:lukewarm_ocean",
+    "minecraft:warm_ocean",
+    "minecraft:snowy_beach",
+    "minecraft:beach",
+    "minecraft:snowy_mountains",
+    "minecraft:mountains",
+    "minecraft:snowy_tundra",
+    "minecraft:snowy_taiga",
+    "minecraft:taiga",
+    "minecraft:plains",
+    "minecraft:forest",
+    "minecraft:birch_forest",
+    "minecraft:dark_forest",
+    "minecraft:desert",
+    "minecraft:savanna",
+    "minecraft:jungle",
+];
+
+impl Biome {
+    /// Picks a biome from the surface height and the climate, both of which are between -1 and 1
+    fn select(height: i32, temperature: f64, humidity: f64) -> Self {
+        if height < SEA_LEVEL - 4 {
+            match temperature {
+                t if t < -0.5 => Biome::FrozenOcean,
+                t if t < -0.15 => Biome::ColdOcean,
+                t if t < 0.15 => Biome::Ocean,
+                t if t < 0.5 => Biome::LukewarmOcean,
+                _ => Biome::WarmOcean,
+            }
+        } else if height <= SEA_LEVEL + 2 {
+            if temperature < -0.5 {
+                Biome::SnowyBeach
+            } else {
+                Biome::Beach
+            }
+        } else if height > SEA_LEVEL + 40 {
+            if temperature < -0.5 {
+                Biome::SnowyMountains
+            } else {
+                Biome::Mountains
+            }
+        } else if temperature < -0.5 {
+            if humidity < 0.0 {
+                Biome::SnowyTundra
+            } else {
+                Biome::SnowyTaiga
+            }
+        } else if temperature < -0.15 {
+            Biome::Taiga
+        } else if temperature < 0.5 {
+            match humidity {
+                h if h < -0.2 => Biome::Plains,
+                h if h < 0.2 => Biome::Forest,
+                h if h < 0.45 => Biome::BirchForest,
+                _ => Biome::DarkForest,
+            }
+        } else {
+            match humidity {
+                h if h < -0.2 => Biome::Desert,
+                h if h < 0.2 => Biome::Savanna,
+                _ => Biome::Jungle,
+            }
+        }
+    }
+
+    /// Trees per chunk on average
+    fn tree_density(self) -> f64 {
+        match self {
+            Biome::Forest | Biome::BirchForest | Biome::Taiga | Biome::SnowyTaiga => 10.0,
+            Biome::DarkForest => 16.0,
+            Biome::Jungle => 20.0,
+            Biome::Savanna => 1.0,
+            Biome::Plains | Biome::Mountains | Biome::SnowyTundra => 0.1,
+            _ => 0.0,
+        }
+    }
+
+    fn tree(self, features: &FeatureBlocks, random: &mut Random) -> Option<Tree> {
+        match self {
+            Biome::Forest if random.next_bounded(5) == 0 => Some(Tree::Oak(features.birch)),
+            Biome::Forest | Biome::DarkForest | Biome::Plains | Biome::Savanna => {
+                Some(Tree::Oak(features.oak))
+            }
+            Biome::BirchForest => Some(Tree::Oak(features.birch)),
+            Biome::Jungle if random.next_bounded(3) == 0 => Some(Tree::Tall(features.jungle)),
+            Biome::Jungle => Some(Tree::Oak(features.jungle)),
+            Biome::Taiga | Biome::SnowyTaiga | Biome::Mountains | Biome::SnowyTundra => {
+                Some(Tree::Spruce(features.spruce))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Salts of [`Random::for_chunk`] for each kind of decoration
+const CAVES: i64 = 0;
+const ORES: i64 = 10_000;
+const TREES: i64 = 20_000;
+
+/// Rolling hills, mountains and oceans from a seed, every seed always gives the same terrain
+pub struct Noise {
+    seed: i64,
+    blocks: NoiseBlocks,
+    /// Caves, ores and trees are left out without
+    features: Option<FeatureBlocks>,
+    /// Ids of [`BIOMES`]
+    biomes: Vec<i32>,
+    height: OctaveNoise,
+    roughness: OctaveNoise,
+    temperature: OctaveNoise,
+    humidity: OctaveNoise,
+}
+
+/// The surface height and biome of a column of blocks
+#[derive(Debug, Copy, Clone)]
+struct Column {
+    height: i32,
+    biome: Biome,
+}
+
+impl Noise {
+    pub fn new(
+        seed: i64,
+        blocks: NoiseBlocks,
+        features: Option<FeatureBlocks>,
+        codec: &DimensionCodec,
+    ) -> Result<Self, GeneratorError> {
+        let biomes = BIOMES
+            .iter()
+            .map(|&name| {
+                codec
+                    .biomes
+                    .get(name)
+                    .map(|biome| biome.id)
+                    .ok_or_else(|| GeneratorError::UnknownBiome(name.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+        let mut random = Random::new(seed);
+        Ok(Self {
+            seed,
+            blocks,
+            features,
+            biomes,
+            height: OctaveNoise::new(&mut random, 6),
+            roughness: OctaveNoise::new(&mut random, 4),
+            temperature: OctaveNoise::new(&mut random, 4),
+            humidity: OctaveNoise::new(&mut random, 4),
+        })
+    }
+
+    /// Ids of the biomes the generator may place
+    pub fn biomes(&self) -> &[i32] {
+        &self.biomes
+    }
+
+    /// The highest block of the terrain at absolute coordinates, water may be above it
+    pub fn height(&self, x: i32, z: i32) -> i32 {
+        self.column(x, z).height
+    }
+
+    fn column(&self, x: i32, z: i32) -> Column {
+        let (x, z) = (x as f64, z as f64);
+        // the octave noises rarely leave -0.5..0.5, a bit more than half of the world is land
+        let continent =
+            (self.height.sample(x / 512.0, 0.0, z / 512.0) * 2.0 + 0.2).clamp(-1.0, 1.0);
+        let roughness = (self.roughness.sample(x / 256.0, 0.0, z / 256.0) + 0.5).clamp(0.0, 1.0);
+        let height = if continent < 0.0 {
+            SEA_LEVEL as f64 + continent * 30.0
+        } else {
+            SEA_LEVEL as f64 + continent * (8.0 + roughness * 72.0)
+        };
+        let temperature =
+            (self.temperature.sample(x / 1024.0, 0.0, z / 1024.0) * 2.0).clamp(-1.0, 1.0);
+        let humidity = (self.humidity.sample(x / 1024.0, 0.0, z / 1024.0) * 2.0).clamp(-1.0, 1.0);
+        let height = height as i32;
+        Column {
+            height,
+            biome: Biome::select(height, temperature, humidity),
+        }
+    }
+}
+
+impl Generator for Noise {
+    fn generate(&self, chunk: &mut Chunk, chunk_x: i32, chunk_z: i32) {
+        let min_y = chunk.min_y();
+        let max_y = min_y + chunk.height() - 1;
+        let mut columns = Vec::with_capacity(256);
+        for z in 0..16 {
+            for x in 0..16 {
+                let mut column = self.column(chunk_x * 16 + x, chunk_z * 16 + z);
+                column.height = column.height.clamp(min_y + 1, max_y);
+                columns.push(column);
+            }
+        }
+        let column = |x: usize, z: usize| columns[z << 4 | x];
+
+        // sections entirely below the surface are stone, without growing their palettes first
+        let lowest = columns.iter().map(|column| column.height).min().unwrap();
+        let solid = ((lowest - 4 - min_y) / 16).max(0) as usize;
+        for section in &mut chunk.sections_mut()[..solid] {
+            section.blocks.fill(self.blocks.stone);
+        }
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let Column { height, biome } = column(x, z);
+                let (top, filler) = if height < SEA_LEVEL {
+                    (self.blocks.sand, self.blocks.sand)
+                } else {
+                    match biome {
+                        Biome::Beach | Biome::SnowyBeach | Biome::Desert => {
+                            (self.blocks.sand, self.blocks.sand)
+                        }
+                        Biome::Mountains | Biome::SnowyMountains => {
+                            (self.blocks.stone, self.blocks.stone)
+                        }
+                        _ => (self.blocks.grass, self.blocks.dirt),
+                    }
+                };
+                for y in min_y + solid as i32 * 16..=height.max(SEA_LEVEL).min(max_y) {
+                    let state = if y == min_y {
+                        self.blocks.bedrock
+                    } else if y > height {
+                        self.blocks.water
+                    } else if y == height {
+                        top
+                    } else if y > height - 4 {
+                        filler
+                    } else {
+                        self.blocks.stone
+                    };
+                    chunk.set_block(x, y, z, state);
+                }
+            }
+        }
+        if solid > 0 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    chunk.set_block(x, min_y, z, self.blocks.bedrock);
+                }
+            }
+        }
+
+        // caves only change the chunk they are carved into, unlike the other decorations they do
+        // not need the neighbours
+        if self.features.is_some() {
+            let blocks = self.blocks;
+            let carvable =
+                |state| [blocks.stone, blocks.dirt, blocks.grass, blocks.sand].contains(&state);
+            Caves::new(chunk, chunk_x, chunk_z, &carvable, blocks.water).carve(self.seed, CAVES);
+        }
+
+        // biomes are stored for every 4x4x4 cube and do not change with the height here
+        for z in (0..16).step_by(4) {
+            for x in (0..16).step_by(4) {
+                let biome = self.biomes[column(x + 2, z + 2).biome as usize];
+                for y in (min_y..=max_y).step_by(4) {
+                    chunk.set_biome(x, y, z, biome);
+                }
+            }
+        }
+    }
+
+    fn decorate(&self, region: &mut Region) {
+        let features = match &self.features {
+            Some(features) => features,
+            None => return,
+        };
+        let (x, z) = (region.x(), region.z());
+        let blocks = self.blocks;
+        let mut random = Random::for_chunk(self.seed, x, z, ORES);
+        for ore in &features.ores() {
+            ore.place(region, features, &mut random, blocks.stone);
+        }
+
+        let mut random = Random::for_chunk(self.seed, x, z, TREES);
+        let density = self.column(x * 16 + 8, z * 16 + 8).biome.tree_density();
+        let mut trees = density as i32;
+        if random.next_f64() < density.fract() {
+            trees += 1;
+        }
+        for _ in 0..trees {
+            let tree_x = x * 16 + random.next_bounded(16);
+            let tree_z = z * 16 + random.next_bounded(16);
+            let tree = self
+                .column(tree_x, tree_z)
+                .biome
+                .tree(features, &mut random);
+            if let (Some(tree), Some(ground)) = (tree, region.top(tree_x, tree_z)) {
+                tree.place(
+                    region,
+                    features,
+                    &mut random,
+                    (tree_x, ground, tree_z),
+                    blocks.grass,
+                    blocks.dirt,
+                );
+            }
+        }
+    }
+
+    fn has_decorations(&self) -> bool {
+        self.features.is_some()
+    }
+}
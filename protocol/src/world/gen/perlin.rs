@@ -0,0 +1,126 @@
+use super::Random;
+
+/// Directions of the gradients at the lattice points, the 12 edges of a cube and four of them
+/// again to make it 16
+const GRADIENTS: [[f64; 3]; 16] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0],
+    [0.0, -1.0, 1.0],
+    [-1.0, 1.0, 0.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Ken Perlin's improved noise with a shuffled permutation and a random offset
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    permutation: [u8; 256],
+    offset: [f64; 3],
+}
+
+impl Perlin {
+    pub fn new(random: &mut Random) -> Self {
+        let offset = [
+            random.next_f64() * 256.0,
+            random.next_f64() * 256.0,
+            random.next_f64() * 256.0,
+        ];
+        let mut permutation = [0; 256];
+        for (index, value) in permutation.iter_mut().enumerate() {
+            *value = index as u8;
+        }
+        for index in 0..256 {
+            let other = index + random.next_bounded(256 - index as i32) as usize;
+            permutation.swap(index, other);
+        }
+        Self {
+            permutation,
+            offset,
+        }
+    }
+
+    /// Roughly between -1 and 1, 0 at every integer coordinate before the offset
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.offset[0], y + self.offset[1], z + self.offset[2]);
+        let (floor_x, floor_y, floor_z) = (x.floor(), y.floor(), z.floor());
+        let (cell_x, cell_y, cell_z) = (floor_x as i32, floor_y as i32, floor_z as i32);
+        let (x, y, z) = (x - floor_x, y - floor_y, z - floor_z);
+
+        let hash = |x: i32, y: i32, z: i32| {
+            let x = self.permute(x);
+            let y = self.permute(x + y);
+            self.permute(y + z)
+        };
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            let gradient = GRADIENTS[hash(cell_x + dx, cell_y + dy, cell_z + dz) as usize & 15];
+            gradient[0] * (x - dx as f64)
+                + gradient[1] * (y - dy as f64)
+                + gradient[2] * (z - dz as f64)
+        };
+        let (fade_x, fade_y, fade_z) = (fade(x), fade(y), fade(z));
+        lerp(
+            fade_z,
+            lerp(
+                fade_y,
+                lerp(fade_x, corner(0, 0, 0), corner(1, 0, 0)),
+                lerp(fade_x, corner(0, 1, 0), corner(1, 1, 0)),
+            ),
+            lerp(
+                fade_y,
+                lerp(fade_x, corner(0, 0, 1), corner(1, 0, 1)),
+                lerp(fade_x, corner(0, 1, 1), corner(1, 1, 1)),
+            ),
+        )
+    }
+
+    fn permute(&self, index: i32) -> i32 {
+        self.permutation[(index & 255) as usize] as i32
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Several layers of noise, each with twice the frequency and half the amplitude of the one before
+#[derive(Debug, Clone)]
+pub struct OctaveNoise {
+    octaves: Vec<Perlin>,
+}
+
+impl OctaveNoise {
+    pub fn new(random: &mut Random, octaves: usize) -> Self {
+        Self {
+            octaves: (0..octaves).map(|_| Perlin::new(random)).collect(),
+        }
+    }
+
+    /// Roughly between -1 and 1, the first octave changes over a distance of about 1
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut value = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        for octave in &self.octaves {
+            value += octave.sample(x * frequency, y * frequency, z * frequency) * amplitude;
+            total += amplitude;
+            frequency *= 2.0;
+            amplitude /= 2.0;
+        }
+        value / total
+    }
+}
@@ -0,0 +1,63 @@
+/// The linear congruential generator of `java.util.Random`, so a seed always gives the same world
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: i64,
+}
+
+const MULTIPLIER: i64 = 0x5_DEEC_E66D;
+const MASK: i64 = (1 << 48) - 1;
+
+impl Random {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            state: (seed ^ MULTIPLIER) & MASK,
+        }
+    }
+
+    /// Seeded from the world seed and a chunk position the way vanilla seeds decorations, so every
+    /// chunk gets its own sequence. `salt` tells apart the features of a chunk.
+    pub fn for_chunk(seed: i64, x: i32, z: i32, salt: i64) -> Self {
+        let mut random = Self::new(seed);
+        let a = random.next_i64() | 1;
+        let b = random.next_i64() | 1;
+        let chunk = (x as i64)
+            .wrapping_mul(a)
+            .wrapping_add((z as i64).wrapping_mul(b))
+            ^ seed;
+        Self::new(chunk.wrapping_add(salt))
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.state = (self.state.wrapping_mul(MULTIPLIER).wrapping_add(0xB)) & MASK;
+        (self.state >> (48 - bits)) as i32
+    }
+
+    pub fn next_i32(&mut self) -> i32 {
+        self.next(32)
+    }
+
+    /// Uniformly distributed in `0..bound`
+    pub fn next_bounded(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0, "bound must be positive");
+        if bound & -bound == bound {
+            return ((bound as i64 * self.next(31) as i64) >> 31) as i32;
+        }
+        loop {
+            let bits = self.next(31);
+            let value = bits % bound;
+            // rejects the values which would make the lower results more likely
+            if bits.wrapping_sub(value).wrapping_add(bound - 1) >= 0 {
+                return value;
+            }
+        }
+    }
+
+    pub fn next_i64(&mut self) -> i64 {
+        ((self.next(32) as i64) << 32).wrapping_add(self.next(32) as i64)
+    }
+
+    /// Uniformly distributed in `0.0..1.0`
+    pub fn next_f64(&mut self) -> f64 {
+        (((self.next(26) as i64) << 27) + self.next(27) as i64) as f64 * (1.0 / (1i64 << 53) as f64)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::world::{Chunk, AIR};
+
+/// A chunk being decorated and the 8 chunks around it, so features near the edge may reach into
+/// the neighbours. Block coordinates are absolute.
+///
+/// Decorations are based on the terrain as it was before any chunk was decorated, so they do not
+/// depend on which neighbours were decorated first.
+#[derive(Debug)]
+pub struct Region<'a> {
+    x: i32,
+    z: i32,
+    /// Row by row from the north-west corner
+    chunks: Vec<Chunk>,
+    /// The same chunks without decorations
+    terrain: Vec<&'a Chunk>,
+}
+
+impl<'a> Region<'a> {
+    /// `chunks` are the 9 chunks around x and z in chunk coordinates, row by row from the lowest z
+    /// and x, and `terrain` the same chunks before they were decorated
+    pub fn new(x: i32, z: i32, chunks: Vec<Chunk>, terrain: Vec<&'a Chunk>) -> Self {
+        assert_eq!(chunks.len(), 9, "a region has 3x3 chunks");
+        assert_eq!(terrain.len(), 9, "a region has 3x3 chunks");
+        Self {
+            x,
+            z,
+            chunks,
+            terrain,
+        }
+    }
+
+    pub fn into_chunks(self) -> Vec<Chunk> {
+        self.chunks
+    }
+
+    /// Chunk coordinates of the chunk being decorated
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn z(&self) -> i32 {
+        self.z
+    }
+
+    /// The chunk being decorated
+    pub fn center(&mut self) -> &mut Chunk {
+        &mut self.chunks[4]
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.chunks[4].min_y()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.chunks[4].height()
+    }
+
+    /// The undecorated terrain, outside of the region there is only air
+    pub fn block(&self, x: i32, y: i32, z: i32) -> i32 {
+        self.index(x, z).map_or(AIR, |index| {
+            self.terrain[index].block((x & 15) as usize, y, (z & 15) as usize)
+        })
+    }
+
+    /// The decoration at a position so far, `None` where there is only terrain
+    pub fn decoration(&self, x: i32, y: i32, z: i32) -> Option<i32> {
+        let index = self.index(x, z)?;
+        let state = self.chunks[index].block((x & 15) as usize, y, (z & 15) as usize);
+        Some(state).filter(|&state| state != self.block(x, y, z))
+    }
+
+    /// Returns the previous state, or `None` outside of the region
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: i32) -> Option<i32> {
+        let index = self.index(x, z)?;
+        self.chunks[index].set_block((x & 15) as usize, y, (z & 15) as usize, state)
+    }
+
+    /// Highest block of a column of the undecorated terrain which is not air
+    pub fn top(&self, x: i32, z: i32) -> Option<i32> {
+        let index = self.index(x, z)?;
+        self.terrain[index].top((x & 15) as usize, (z & 15) as usize)
+    }
+
+    fn index(&self, x: i32, z: i32) -> Option<usize> {
+        let x = (x >> 4) - self.x + 1;
+        let z = (z >> 4) - self.z + 1;
+        if (0..3).contains(&x) && (0..3).contains(&z) {
+            Some((z * 3 + x) as usize)
+        } else {
+            None
+        }
+    }
+}
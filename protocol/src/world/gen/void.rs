@@ -0,0 +1,26 @@
+use super::Generator;
+use crate::world::Chunk;
+
+/// Nothing but air in a single biome
+pub struct Void {
+    biome: i32,
+}
+
+impl Void {
+    pub fn new(biome: i32) -> Self {
+        Self { biome }
+    }
+}
+
+impl Generator for Void {
+    fn generate(&self, chunk: &mut Chunk, _x: i32, _z: i32) {
+        for section in chunk.sections_mut() {
+            section.biomes.fill(self.biome);
+        }
+    }
+
+    /// Vanilla's void is a superflat preset
+    fn is_flat(&self) -> bool {
+        true
+    }
+}
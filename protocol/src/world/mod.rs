@@ -0,0 +1,448 @@
+//! Block states and biomes of chunk columns, kept in paletted sections so thousands of chunks fit
+//! in memory
+
+pub mod anvil;
+mod encode;
+pub mod gen;
+mod palette;
+
+pub use encode::ChunkEncoder;
+pub use palette::{bits_for, BitArray, Kind, PalettedContainer, Storage};
+
+use crate::packets::play::dim::DimensionType;
+use gen::{Generator, Region};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// State id of `minecraft:air`, the first block state
+pub const AIR: i32 = 0;
+
+/// Bits of the direct storage, derived from the sizes of the global palettes
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Palettes {
+    pub block_bits: u8,
+    pub biome_bits: u8,
+}
+
+impl Palettes {
+    /// Takes the number of ids, i.e. the largest id plus one, of each global palette
+    pub fn new(block_states: usize, biomes: usize) -> Self {
+        Self {
+            block_bits: bits_for(block_states),
+            biome_bits: bits_for(biomes),
+        }
+    }
+}
+
+/// 16x16x16 blocks of a chunk column
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Section {
+    pub blocks: PalettedContainer,
+    pub biomes: PalettedContainer,
+}
+
+impl Section {
+    /// A section of air with a single biome
+    pub fn new(palettes: Palettes, biome: i32) -> Self {
+        Self {
+            blocks: PalettedContainer::new(Kind::BlockStates, palettes.block_bits, AIR),
+            biomes: PalettedContainer::new(Kind::Biomes, palettes.biome_bits, biome),
+        }
+    }
+
+    /// Coordinates are relative to the section
+    pub fn block(&self, x: usize, y: usize, z: usize) -> i32 {
+        self.blocks.get(block_index(x, y, z))
+    }
+
+    /// Returns the previous state
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: i32) -> i32 {
+        self.blocks.set(block_index(x, y, z), state)
+    }
+
+    /// Coordinates are relative to the section and in blocks
+    pub fn biome(&self, x: usize, y: usize, z: usize) -> i32 {
+        self.biomes.get(biome_index(x, y, z))
+    }
+
+    /// Sets the biome of the 4x4x4 blocks containing the position
+    pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: i32) -> i32 {
+        self.biomes.set(biome_index(x, y, z), biome)
+    }
+
+    /// Whether the section only contains air
+    pub fn is_empty(&self) -> bool {
+        *self.blocks.storage() == Storage::Single(AIR)
+    }
+}
+
+/// Entries are ordered by y, then z, then x
+fn block_index(x: usize, y: usize, z: usize) -> usize {
+    debug_assert!(
+        x < 16 && y < 16 && z < 16,
+        "{} {} {} outside of the section",
+        x,
+        y,
+        z
+    );
+    y << 8 | z << 4 | x
+}
+
+fn biome_index(x: usize, y: usize, z: usize) -> usize {
+    debug_assert!(
+        x < 16 && y < 16 && z < 16,
+        "{} {} {} outside of the section",
+        x,
+        y,
+        z
+    );
+    (y >> 2) << 4 | (z >> 2) << 2 | x >> 2
+}
+
+/// A column of sections spanning the height of a dimension
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Chunk {
+    min_y: i32,
+    /// From the bottom up
+    sections: Vec<Section>,
+}
+
+impl Chunk {
+    /// A chunk of air with a single biome
+    pub fn new(dimension: &DimensionType, palettes: Palettes, biome: i32) -> Self {
+        let sections = (dimension.height / 16).max(0) as usize;
+        Self {
+            min_y: dimension.min_y,
+            sections: vec![Section::new(palettes, biome); sections],
+        }
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    /// Number of blocks between the lowest and highest y, exclusive
+    pub fn height(&self) -> i32 {
+        self.sections.len() as i32 * 16
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    pub fn sections_mut(&mut self) -> &mut [Section] {
+        &mut self.sections
+    }
+
+    /// Section containing a y coordinate
+    pub fn section(&self, y: i32) -> Option<&Section> {
+        self.sections.get(self.section_index(y)?)
+    }
+
+    pub fn section_mut(&mut self, y: i32) -> Option<&mut Section> {
+        let index = self.section_index(y)?;
+        self.sections.get_mut(index)
+    }
+
+    /// x and z are relative to the chunk, y is absolute, outside of the column there is only air
+    pub fn block(&self, x: usize, y: i32, z: usize) -> i32 {
+        self.section(y)
+            .map_or(AIR, |section| section.block(x, (y & 15) as usize, z))
+    }
+
+    /// Returns the previous state, or `None` if y is outside of the column
+    pub fn set_block(&mut self, x: usize, y: i32, z: usize, state: i32) -> Option<i32> {
+        let section = self.section_mut(y)?;
+        Some(section.set_block(x, (y & 15) as usize, z, state))
+    }
+
+    /// Biome at a block position, y is clamped to the column
+    pub fn biome(&self, x: usize, y: i32, z: usize) -> i32 {
+        let y = y.clamp(self.min_y, self.min_y + self.height() - 1);
+        let section = self.section(y).expect("a chunk has at least one section");
+        section.biome(x, (y & 15) as usize, z)
+    }
+
+    /// Sets the biome of the 4x4x4 blocks containing the position, returns the previous biome or
+    /// `None` if y is outside of the column
+    pub fn set_biome(&mut self, x: usize, y: i32, z: usize, biome: i32) -> Option<i32> {
+        let section = self.section_mut(y)?;
+        Some(section.set_biome(x, (y & 15) as usize, z, biome))
+    }
+
+    /// Rebuilds the palettes of all sections, see [`PalettedContainer::compact`]
+    pub fn compact(&mut self) {
+        for section in &mut self.sections {
+            section.blocks.compact();
+            section.biomes.compact();
+        }
+    }
+
+    fn section_index(&self, y: i32) -> Option<usize> {
+        let index = (y - self.min_y).div_euclid(16);
+        if (0..self.sections.len() as i32).contains(&index) {
+            Some(index as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Highest block of a column which is not air, x and z are relative to the chunk
+    pub fn top(&self, x: usize, z: usize) -> Option<i32> {
+        for (index, section) in self.sections.iter().enumerate().rev() {
+            if section.is_empty() {
+                continue;
+            }
+            if let Some(y) = (0..16).rev().find(|&y| section.block(x, y, z) != AIR) {
+                return Some(self.min_y + index as i32 * 16 + y as i32);
+            }
+        }
+        None
+    }
+}
+
+/// Generates the terrain of chunks independently of a [`World`], so it can run in parallel without
+/// holding a lock on the world. The results are added with [`World::insert`].
+#[derive(Clone)]
+pub struct TerrainGenerator {
+    generator: Arc<dyn Generator>,
+    /// A chunk of air spanning the dimension
+    empty: Chunk,
+}
+
+impl TerrainGenerator {
+    /// The terrain of a chunk, without decorations
+    pub fn generate(&self, x: i32, z: i32) -> Chunk {
+        let mut chunk = self.empty.clone();
+        self.generator.generate(&mut chunk, x, z);
+        chunk
+    }
+}
+
+/// Chunks of a single dimension, generated the first time they are requested
+pub struct World {
+    dimension: DimensionType,
+    terrain_generator: TerrainGenerator,
+    chunks: HashMap<(i32, i32), Chunk>,
+    /// Chunks which went through [`Generator::decorate`]
+    decorated: HashSet<(i32, i32)>,
+    /// Copies of chunks before they were decorated, kept until every chunk which may decorate
+    /// them is done
+    terrain: HashMap<(i32, i32), Chunk>,
+    /// Least recently used chunks are unloaded once there are more than this
+    max_chunks: usize,
+    /// When each chunk was last handed out by [`World::chunk`], in calls of it
+    last_used: HashMap<(i32, i32), u64>,
+    clock: u64,
+}
+
+impl World {
+    pub fn new(
+        dimension: DimensionType,
+        palettes: Palettes,
+        generator: Box<dyn Generator>,
+    ) -> Self {
+        let terrain_generator = TerrainGenerator {
+            generator: generator.into(),
+            empty: Chunk::new(&dimension, palettes, 0),
+        };
+        Self {
+            dimension,
+            terrain_generator,
+            chunks: HashMap::new(),
+            decorated: HashSet::new(),
+            terrain: HashMap::new(),
+            max_chunks: usize::MAX,
+            last_used: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Limits the number of chunks kept in memory. Chunks can be regenerated exactly as they
+    /// were, so the least recently used ones are simply dropped.
+    pub fn set_max_chunks(&mut self, max_chunks: usize) {
+        self.max_chunks = max_chunks.max(1);
+    }
+
+    pub fn dimension(&self) -> &DimensionType {
+        &self.dimension
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.generator().is_flat()
+    }
+
+    fn generator(&self) -> &dyn Generator {
+        &*self.terrain_generator.generator
+    }
+
+    pub fn terrain_generator(&self) -> TerrainGenerator {
+        self.terrain_generator.clone()
+    }
+
+    /// Chunk at chunk coordinates, generates it if it does not exist yet. Decorations of the
+    /// chunks around it may reach into it, so those are decorated first.
+    pub fn chunk(&mut self, x: i32, z: i32) -> &mut Chunk {
+        if self.generator().has_decorations() {
+            for dz in -1..=1 {
+                for dx in -1..=1 {
+                    self.decorate(x + dx, z + dz);
+                }
+            }
+        }
+        self.generate(x, z);
+        self.clock += 1;
+        self.last_used.insert((x, z), self.clock);
+        if self.chunks.len() > self.max_chunks {
+            self.unload();
+        }
+        self.chunks.get_mut(&(x, z)).unwrap()
+    }
+
+    /// Unloads the least recently used chunks, down to 7/8 of the limit so this does not run on
+    /// every call
+    fn unload(&mut self) {
+        let mut chunks = self
+            .chunks
+            .keys()
+            .map(|pos| (self.last_used.get(pos).copied().unwrap_or(0), *pos))
+            .collect::<Vec<_>>();
+        chunks.sort_unstable();
+        let keep = self.max_chunks - self.max_chunks / 8;
+        let unload = chunks.len() - keep.max(1);
+        for &(_, (x, z)) in &chunks[..unload] {
+            self.chunks.remove(&(x, z));
+            self.terrain.remove(&(x, z));
+            self.last_used.remove(&(x, z));
+            // the decorations these regions placed into the chunk are gone with it, decorating
+            // them again places them exactly as before and leaves the other chunks unchanged
+            for dz in -1..=1 {
+                for dx in -1..=1 {
+                    self.decorated.remove(&(x + dx, z + dz));
+                }
+            }
+        }
+    }
+
+    /// Chunks whose terrain [`World::chunk`] would have to generate to hand out the chunk at x
+    /// and z. Generating them with a [`TerrainGenerator`] and adding them with [`World::insert`]
+    /// beforehand leaves only the decorations to `chunk`.
+    pub fn missing(&self, x: i32, z: i32) -> Vec<(i32, i32)> {
+        // decorating a region needs the terrain of all its chunks, which is gone for chunks that
+        // were decorated before if one of their neighbours was unloaded since
+        let mut terrain = vec![];
+        if self.generator().has_decorations() {
+            for rz in z - 1..=z + 1 {
+                for rx in x - 1..=x + 1 {
+                    if self.decorated.contains(&(rx, rz)) {
+                        continue;
+                    }
+                    for dz in -1..=1 {
+                        for dx in -1..=1 {
+                            terrain.push((rx + dx, rz + dz));
+                        }
+                    }
+                }
+            }
+        }
+        terrain.retain(|pos| !self.terrain.contains_key(pos) || !self.chunks.contains_key(pos));
+        if !self.chunks.contains_key(&(x, z)) {
+            terrain.push((x, z));
+        }
+        terrain.sort_unstable();
+        terrain.dedup();
+        terrain
+    }
+
+    /// Adds the terrain of a chunk generated by a [`TerrainGenerator`]. A chunk which exists
+    /// already is kept, only its copy without decorations is added if it is missing.
+    pub fn insert(&mut self, x: i32, z: i32, terrain: Chunk) {
+        if self.generator().has_decorations() && !self.terrain.contains_key(&(x, z)) {
+            self.terrain.insert((x, z), terrain.clone());
+        }
+        self.chunks.entry((x, z)).or_insert(terrain);
+    }
+
+    /// The terrain of a chunk, without decorations
+    fn generate(&mut self, x: i32, z: i32) -> &mut Chunk {
+        if !self.chunks.contains_key(&(x, z)) {
+            let terrain = self.terrain_generator.generate(x, z);
+            self.insert(x, z, terrain);
+        }
+        self.chunks.get_mut(&(x, z)).unwrap()
+    }
+
+    fn decorate(&mut self, x: i32, z: i32) {
+        if !self.decorated.insert((x, z)) {
+            return;
+        }
+        let mut chunks = Vec::with_capacity(9);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                self.generate(x + dx, z + dz);
+                chunks.push(self.chunks.remove(&(x + dx, z + dz)).unwrap());
+            }
+        }
+        for index in 0..9 {
+            let pos = (x + index % 3 - 1, z + index / 3 - 1);
+            // gone if the chunk was decorated before, and a neighbour was unloaded since
+            if !self.terrain.contains_key(&pos) {
+                let terrain = self.terrain_generator.generate(pos.0, pos.1);
+                self.terrain.insert(pos, terrain);
+            }
+        }
+        let terrain = (0..9)
+            .map(|index| &self.terrain[&(x + index % 3 - 1, z + index / 3 - 1)])
+            .collect();
+        let mut region = Region::new(x, z, chunks, terrain);
+        self.terrain_generator.generator.decorate(&mut region);
+        for (index, chunk) in region.into_chunks().into_iter().enumerate() {
+            let (dx, dz) = (index as i32 % 3 - 1, index as i32 / 3 - 1);
+            self.chunks.insert((x + dx, z + dz), chunk);
+        }
+
+        // chunks are only decorated from inside of their region
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let (x, z) = (x + dx, z + dz);
+                let done = (-1..=1)
+                    .all(|nz| (-1..=1).all(|nx| self.decorated.contains(&(x + nx, z + nz))));
+                if done {
+                    self.terrain.remove(&(x, z));
+                }
+            }
+        }
+    }
+
+    /// A chunk which was already generated
+    pub fn get(&self, x: i32, z: i32) -> Option<&Chunk> {
+        self.chunks.get(&(x, z))
+    }
+
+    /// Number of chunks in memory
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Block coordinates are absolute
+    pub fn block(&mut self, x: i32, y: i32, z: i32) -> i32 {
+        self.chunk(x >> 4, z >> 4)
+            .block((x & 15) as usize, y, (z & 15) as usize)
+    }
+
+    /// Returns the previous state, or `None` if y is outside of the world
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: i32) -> Option<i32> {
+        self.chunk(x >> 4, z >> 4)
+            .set_block((x & 15) as usize, y, (z & 15) as usize, state)
+    }
+
+    /// Highest block which is not air at a column, `None` if there is only air
+    pub fn top(&mut self, x: i32, z: i32) -> Option<i32> {
+        self.chunk(x >> 4, z >> 4)
+            .top((x & 15) as usize, (z & 15) as usize)
+    }
+}
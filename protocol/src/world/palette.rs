@@ -0,0 +1,286 @@
+use std::collections::HashSet;
+
+/// Fixed number of unsigned values of the same bit width, packed into longs without spanning two
+/// longs, the layout used on the wire and in region files
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BitArray {
+    bits: u8,
+    len: usize,
+    data: Box<[u64]>,
+}
+
+impl BitArray {
+    /// All values start out as 0
+    pub fn new(bits: u8, len: usize) -> Self {
+        assert!((1..=32).contains(&bits), "invalid bit width {}", bits);
+        let per_long = 64 / bits as usize;
+        Self {
+            bits,
+            len,
+            data: vec![0; len.div_ceil(per_long)].into_boxed_slice(),
+        }
+    }
+
+    /// Packed values as read from a region file, `None` if there are too few or too many longs
+    pub fn from_longs(bits: u8, len: usize, longs: Vec<u64>) -> Option<Self> {
+        let array = Self::new(bits, len);
+        if longs.len() != array.data.len() {
+            return None;
+        }
+        Some(Self {
+            data: longs.into_boxed_slice(),
+            ..array
+        })
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The packed values
+    pub fn longs(&self) -> &[u64] {
+        &self.data
+    }
+
+    pub fn get(&self, index: usize) -> u32 {
+        let (long, shift) = self.position(index);
+        ((self.data[long] >> shift) & self.mask()) as u32
+    }
+
+    pub fn set(&mut self, index: usize, value: u32) {
+        debug_assert!(value as u64 <= self.mask(), "{} needs more bits", value);
+        let (long, shift) = self.position(index);
+        let mask = self.mask();
+        self.data[long] = (self.data[long] & !(mask << shift)) | ((value as u64 & mask) << shift);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.len).map(move |index| self.get(index))
+    }
+
+    fn mask(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    fn position(&self, index: usize) -> (usize, usize) {
+        assert!(index < self.len, "index {} out of bounds", index);
+        let per_long = 64 / self.bits as usize;
+        (index / per_long, index % per_long * self.bits as usize)
+    }
+}
+
+/// What a [`PalettedContainer`] holds, which determines its size and palette limits
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Kind {
+    /// 16x16x16 block states
+    BlockStates,
+    /// 4x4x4 biomes, each covering 4x4x4 blocks
+    Biomes,
+}
+
+impl Kind {
+    /// Number of values in a container
+    pub fn entries(self) -> usize {
+        match self {
+            Kind::BlockStates => 4096,
+            Kind::Biomes => 64,
+        }
+    }
+
+    /// Bit widths an indirect palette may use
+    pub fn indirect_bits(self) -> (u8, u8) {
+        match self {
+            Kind::BlockStates => (4, 8),
+            Kind::Biomes => (1, 3),
+        }
+    }
+}
+
+/// Values of a section, stored with as few bits as the distinct values allow
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PalettedContainer {
+    kind: Kind,
+    /// Bits of the direct storage, enough for every id of the global palette
+    global_bits: u8,
+    storage: Storage,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Storage {
+    /// Every entry has the same value
+    Single(i32),
+    /// Indices into a palette of at most `2^bits` values
+    Indirect { palette: Vec<i32>, data: BitArray },
+    /// The global ids themselves
+    Direct(BitArray),
+}
+
+impl PalettedContainer {
+    pub fn new(kind: Kind, global_bits: u8, value: i32) -> Self {
+        Self {
+            kind,
+            global_bits,
+            storage: Storage::Single(value),
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn global_bits(&self) -> u8 {
+        self.global_bits
+    }
+
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    pub fn get(&self, index: usize) -> i32 {
+        match &self.storage {
+            Storage::Single(value) => {
+                assert!(index < self.kind.entries(), "index {} out of bounds", index);
+                *value
+            }
+            Storage::Indirect { palette, data } => palette[data.get(index) as usize],
+            Storage::Direct(data) => data.get(index) as i32,
+        }
+    }
+
+    /// Returns the previous value, grows the palette if the value is new
+    pub fn set(&mut self, index: usize, value: i32) -> i32 {
+        debug_assert!(
+            value >= 0 && (value as u64) < 1 << self.global_bits,
+            "{} is not a global id",
+            value
+        );
+        match &mut self.storage {
+            Storage::Single(current) if *current == value => {
+                assert!(index < self.kind.entries(), "index {} out of bounds", index);
+                value
+            }
+            Storage::Single(current) => {
+                let previous = *current;
+                let (bits, _) = self.kind.indirect_bits();
+                self.storage = Storage::Indirect {
+                    palette: vec![previous],
+                    data: BitArray::new(bits, self.kind.entries()),
+                };
+                self.set(index, value);
+                previous
+            }
+            Storage::Indirect { palette, data } => {
+                let previous = palette[data.get(index) as usize];
+                match palette.iter().position(|entry| *entry == value) {
+                    Some(entry) => data.set(index, entry as u32),
+                    None if palette.len() < 1 << data.bits() => {
+                        palette.push(value);
+                        data.set(index, palette.len() as u32 - 1);
+                    }
+                    None => {
+                        self.grow();
+                        self.set(index, value);
+                    }
+                }
+                previous
+            }
+            Storage::Direct(data) => {
+                let previous = data.get(index) as i32;
+                data.set(index, value as u32);
+                previous
+            }
+        }
+    }
+
+    /// Sets every entry to the same value
+    pub fn fill(&mut self, value: i32) {
+        self.storage = Storage::Single(value);
+    }
+
+    /// Number of entries for which `filter` returns true
+    pub fn count(&self, mut filter: impl FnMut(i32) -> bool) -> usize {
+        match &self.storage {
+            Storage::Single(value) if filter(*value) => self.kind.entries(),
+            Storage::Single(_) => 0,
+            Storage::Indirect { palette, data } => {
+                let matches = palette
+                    .iter()
+                    .map(|&value| filter(value))
+                    .collect::<Vec<_>>();
+                data.iter().filter(|&entry| matches[entry as usize]).count()
+            }
+            Storage::Direct(data) => data.iter().filter(|&value| filter(value as i32)).count(),
+        }
+    }
+
+    /// Rebuilds the storage from the values which are still in use, e.g. after large parts of a
+    /// section were replaced
+    pub fn compact(&mut self) {
+        let values = (0..self.kind.entries())
+            .map(|index| self.get(index))
+            .collect::<Vec<_>>();
+        let distinct = values.iter().copied().collect::<HashSet<_>>().len();
+        if distinct == 1 {
+            self.storage = Storage::Single(values[0]);
+            return;
+        }
+        let (min_bits, max_bits) = self.kind.indirect_bits();
+        let bits = bits_for(distinct).max(min_bits);
+        if bits > max_bits {
+            // the direct storage does not get any smaller
+            return;
+        }
+        let mut palette = Vec::with_capacity(distinct);
+        let mut data = BitArray::new(bits, values.len());
+        for (index, value) in values.into_iter().enumerate() {
+            let entry = match palette.iter().position(|entry| *entry == value) {
+                Some(entry) => entry,
+                None => {
+                    palette.push(value);
+                    palette.len() - 1
+                }
+            };
+            data.set(index, entry as u32);
+        }
+        self.storage = Storage::Indirect { palette, data };
+    }
+
+    /// Doubles the size of a full indirect palette, or switches to direct storage once the palette
+    /// would need too many bits
+    fn grow(&mut self) {
+        let (palette, data) = match std::mem::replace(&mut self.storage, Storage::Single(0)) {
+            Storage::Indirect { palette, data } => (palette, data),
+            _ => unreachable!("only an indirect palette can grow"),
+        };
+        let (_, max_bits) = self.kind.indirect_bits();
+        self.storage = if data.bits() < max_bits {
+            let mut grown = BitArray::new(data.bits() + 1, data.len());
+            for (index, entry) in data.iter().enumerate() {
+                grown.set(index, entry);
+            }
+            Storage::Indirect {
+                palette,
+                data: grown,
+            }
+        } else {
+            let mut direct = BitArray::new(self.global_bits, data.len());
+            for (index, entry) in data.iter().enumerate() {
+                direct.set(index, palette[entry as usize] as u32);
+            }
+            Storage::Direct(direct)
+        };
+    }
+}
+
+/// Bits needed to store `count` distinct values
+pub fn bits_for(count: usize) -> u8 {
+    (usize::BITS - count.saturating_sub(1).leading_zeros()).max(1) as u8
+}
@@ -0,0 +1,268 @@
+use protocol::packets::play::dim::{DimensionCodec, DimensionType};
+use protocol::registry::{Blocks, Registries};
+use protocol::types::{Blob, Value};
+use protocol::world::anvil::*;
+use protocol::world::gen::{Flat, Layer};
+use protocol::world::{Chunk, Palettes, Storage, World, AIR};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+const STONE: i32 = 1;
+/// `minecraft:oak_log` with `axis=x`
+const LOG_X: i32 = 2;
+const PLAINS: i32 = 1;
+const DESERT: i32 = 2;
+
+fn blocks() -> Blocks {
+    let reports = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/reports");
+    Registries::load(&reports).unwrap().blocks
+}
+
+fn overworld() -> DimensionType {
+    DimensionCodec::default()
+        .dimension_types
+        .get("minecraft:overworld")
+        .unwrap()
+        .element
+        .clone()
+}
+
+fn compound(entries: Vec<(&str, Value)>) -> Value {
+    Value::Compound(
+        entries
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+fn block(name: &str, properties: Vec<(&str, Value)>) -> Value {
+    let mut entries = vec![("Name", Value::String(name.to_string()))];
+    if !properties.is_empty() {
+        entries.push(("Properties", compound(properties)));
+    }
+    compound(entries)
+}
+
+/// A saved chunk with stone at the bottom of the first section, a log at x 1 and desert in the
+/// first column of biomes
+fn saved(x: i32, z: i32, status: &str, log: &str) -> Blob {
+    // 4 bits per block, palette index 1 for the first 256 blocks
+    let mut states = vec![0x1111_1111_1111_1111i64; 16];
+    states.resize(256, 0);
+    states[0] = 0x1111_1111_1111_1121;
+    let mut biomes = vec![PLAINS; 1024];
+    for y in 0..64 {
+        biomes[y * 16] = DESERT;
+    }
+    let sections = vec![
+        // light only, below the world
+        compound(vec![("Y", Value::Byte(-1))]),
+        compound(vec![
+            ("Y", Value::Byte(0)),
+            (
+                "Palette",
+                Value::List(vec![
+                    block("minecraft:air", vec![]),
+                    block("minecraft:stone", vec![]),
+                    block(log, vec![("axis", Value::String("x".to_string()))]),
+                ]),
+            ),
+            ("BlockStates", Value::LongArray(states)),
+        ]),
+        compound(vec![
+            ("Y", Value::Byte(3)),
+            (
+                "Palette",
+                Value::List(vec![block("minecraft:stone", vec![])]),
+            ),
+            ("BlockStates", Value::LongArray(vec![0; 256])),
+        ]),
+    ];
+    let chest = compound(vec![
+        ("id", Value::String("minecraft:chest".to_string())),
+        ("x", Value::Int(x * 16)),
+        ("y", Value::Int(1)),
+        ("z", Value::Int(z * 16)),
+    ]);
+    let level = compound(vec![
+        ("xPos", Value::Int(x)),
+        ("zPos", Value::Int(z)),
+        ("Status", Value::String(status.to_string())),
+        ("Sections", Value::List(sections)),
+        ("Biomes", Value::IntArray(biomes)),
+        (
+            "Heightmaps",
+            compound(vec![("MOTION_BLOCKING", Value::LongArray(vec![7; 37]))]),
+        ),
+        ("TileEntities", Value::List(vec![chest])),
+    ]);
+    let mut blob = Blob::new();
+    blob.insert("DataVersion", Value::Int(2730)).unwrap();
+    blob.insert("Level", level).unwrap();
+    blob
+}
+
+/// Region file bytes with chunks at positions inside the region
+fn region(chunks: &[(i32, i32, u8, Vec<u8>)]) -> Vec<u8> {
+    let mut header = vec![0; 8192];
+    let mut data = Vec::new();
+    for (x, z, compression, payload) in chunks {
+        let offset = 2 + data.len() / 4096;
+        let mut chunk = (payload.len() as u32 + 1).to_be_bytes().to_vec();
+        chunk.push(*compression);
+        chunk.extend_from_slice(payload);
+        chunk.resize(chunk.len().div_ceil(4096) * 4096, 0);
+        let location = (offset as u32) << 8 | (chunk.len() / 4096) as u32;
+        let index = ((z & 31) * 32 + (x & 31)) as usize * 4;
+        header[index..index + 4].copy_from_slice(&location.to_be_bytes());
+        data.extend(chunk);
+    }
+    header.extend(data);
+    header
+}
+
+fn zlib(blob: &Blob) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    blob.to_zlib_writer(&mut bytes).unwrap();
+    bytes
+}
+
+fn chunk() -> Chunk {
+    Chunk::new(&overworld(), Palettes::new(11, 176), 0)
+}
+
+#[test]
+fn compressions() {
+    let blob = saved(0, 0, "full", "minecraft:oak_log");
+    let mut gzip = Vec::new();
+    blob.to_gzip_writer(&mut gzip).unwrap();
+    let mut uncompressed = Vec::new();
+    blob.to_writer(&mut uncompressed).unwrap();
+    let bytes = region(&[
+        (0, 0, 1, gzip),
+        (1, 0, 2, zlib(&blob)),
+        (31, 31, 3, uncompressed),
+        (2, 0, 4, zlib(&blob)),
+    ]);
+    let mut file = RegionFile::new(Cursor::new(bytes)).unwrap();
+    let expected = file.chunk(1, 0).unwrap().unwrap();
+    assert_eq!(file.chunk(0, 0).unwrap().unwrap(), expected);
+    // only the position inside the region matters
+    assert_eq!(file.chunk(-1, -1).unwrap().unwrap(), expected);
+    assert!(file.contains(31, 31));
+    assert!(!file.contains(5, 5));
+    assert!(file.chunk(5, 5).unwrap().is_none());
+    assert!(matches!(file.chunk(2, 0), Err(AnvilError::Compression(4))));
+}
+
+#[test]
+fn chunk_nbt() {
+    let bytes = region(&[(3, 4, 2, zlib(&saved(3, 4, "full", "minecraft:oak_log")))]);
+    let saved = RegionFile::new(Cursor::new(bytes))
+        .unwrap()
+        .chunk(3, 4)
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.data_version, 2730);
+    assert!(saved.is_full());
+    assert_eq!((saved.level.x, saved.level.z), (3, 4));
+    assert_eq!(saved.level.sections.len(), 3);
+    assert_eq!(
+        saved.level.sections[1].palette[2],
+        PaletteEntry {
+            name: "minecraft:oak_log".to_string(),
+            properties: vec![("axis".to_string(), "x".to_string())]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        }
+    );
+    assert_eq!(saved.level.heightmaps["MOTION_BLOCKING"], vec![7; 37]);
+    assert_eq!(saved.level.block_entities.len(), 1);
+    assert!(matches!(
+        &saved.level.block_entities[0],
+        Value::Compound(chest) if chest["id"] == Value::String("minecraft:chest".to_string())
+    ));
+
+    let mut chunk = chunk();
+    saved.load(&mut chunk, &blocks()).unwrap();
+    assert_eq!(chunk.block(0, 0, 0), STONE);
+    assert_eq!(chunk.block(1, 0, 0), LOG_X);
+    assert_eq!(chunk.block(15, 0, 15), STONE);
+    assert_eq!(chunk.block(0, 1, 0), AIR);
+    assert_eq!(
+        chunk.sections()[3].blocks.storage(),
+        &Storage::Single(STONE)
+    );
+    assert!(chunk.sections()[4].is_empty());
+    assert_eq!(chunk.biome(0, 100, 0), DESERT);
+    assert_eq!(chunk.biome(4, 100, 0), PLAINS);
+}
+
+#[test]
+fn invalid_chunks() {
+    let blocks = blocks();
+    let load = |blob: Blob| {
+        let bytes = region(&[(0, 0, 2, zlib(&blob))]);
+        let saved = RegionFile::new(Cursor::new(bytes))
+            .unwrap()
+            .chunk(0, 0)
+            .unwrap()
+            .unwrap();
+        saved.load(&mut chunk(), &blocks)
+    };
+    assert!(load(saved(0, 0, "full", "minecraft:oak_log")).is_ok());
+    assert!(matches!(
+        load(saved(0, 0, "full", "minecraft:cherry_log")),
+        Err(AnvilError::UnknownBlock(name)) if name == "minecraft:cherry_log"
+    ));
+
+    let mut blob = saved(0, 0, "full", "minecraft:oak_log");
+    let mut level = blob.get("Level").unwrap().clone();
+    if let Value::Compound(level) = &mut level {
+        level.insert("Biomes".to_string(), Value::IntArray(vec![0; 1536]));
+    }
+    blob.insert("Level", level).unwrap();
+    assert!(matches!(load(blob), Err(AnvilError::Biomes(1536))));
+
+    // the header claims more sectors than the file has
+    let mut bytes = region(&[(0, 0, 2, vec![0; 10])]);
+    bytes.truncate(8192 + 100);
+    let mut file = RegionFile::new(Cursor::new(bytes)).unwrap();
+    assert!(matches!(file.chunk(0, 0), Err(AnvilError::Io(_))));
+}
+
+#[test]
+fn world_directory() {
+    let dir = std::env::temp_dir().join(format!("mcsrs-anvil-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("region")).unwrap();
+    let bytes = region(&[
+        (0, 0, 2, zlib(&saved(0, 0, "full", "minecraft:oak_log"))),
+        (1, 0, 2, zlib(&saved(1, 0, "features", "minecraft:oak_log"))),
+        (-1, -1, 2, zlib(&saved(-1, -1, "full", "minecraft:oak_log"))),
+    ]);
+    std::fs::write(dir.join("region/r.0.0.mca"), bytes).unwrap();
+
+    let fallback = Flat::new(
+        vec![Layer {
+            state: LOG_X,
+            height: 2,
+        }],
+        PLAINS,
+    );
+    let anvil = Anvil::new(&dir, blocks(), Box::new(fallback));
+    let mut world = World::new(overworld(), Palettes::new(11, 176), Box::new(anvil));
+    // whether the world was flat is only saved in level.dat
+    assert!(!world.is_flat());
+    assert_eq!(world.block(1, 0, 0), LOG_X);
+    assert_eq!(world.block(0, 0, 0), STONE);
+    assert_eq!(world.block(0, 50, 0), STONE);
+    // not done generating, not saved, in a missing region file and saved at the wrong place
+    for (x, z) in [(16, 0), (32, 0), (-600, 0), (31 * 16, 31 * 16)] {
+        assert_eq!(world.block(x, 0, z), LOG_X);
+        assert_eq!(world.block(x, 2, z), AIR);
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
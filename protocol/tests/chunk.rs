@@ -0,0 +1,216 @@
+mod common;
+
+use bytes::BufMut;
+use protocol::codec::Transcodeable;
+use protocol::packets::encode;
+use protocol::packets::play::dim::{DimensionCodec, DimensionType};
+use protocol::registry::Registries;
+use protocol::types::BitSet;
+use protocol::world::*;
+use std::path::Path;
+
+const STONE: i32 = 1;
+const PLAINS: i32 = 1;
+
+fn dimension(name: &str) -> DimensionType {
+    DimensionCodec::default()
+        .dimension_types
+        .get(name)
+        .unwrap()
+        .element
+        .clone()
+}
+
+fn encoder(dimension: &DimensionType) -> ChunkEncoder {
+    let reports = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/reports");
+    ChunkEncoder::new(&Registries::load(&reports).unwrap().blocks, dimension)
+}
+
+/// A layer of stone at y 0 of the overworld
+fn layer() -> Chunk {
+    let mut chunk = Chunk::new(
+        &dimension("minecraft:overworld"),
+        Palettes::new(11, 176),
+        PLAINS,
+    );
+    for x in 0..16 {
+        for z in 0..16 {
+            chunk.set_block(x, 0, z, STONE);
+        }
+    }
+    chunk
+}
+
+fn varint(buf: &mut Vec<u8>, value: i32) {
+    protocol::types::VarInt(value).encode(buf).unwrap();
+}
+
+/// The Chunk Data packet of [`layer`] at 2, -3, built by hand after the 1.17.1 packet layout: mask,
+/// heightmaps, biomes, sections and block entities. [`vanilla_frames`] checks the encoder against
+/// the frames in `tests/data/vanilla`.
+fn vanilla_layer() -> Vec<u8> {
+    let mut body = vec![0x22];
+    body.put_i32(2);
+    body.put_i32(-3);
+    // only the lowest section is sent
+    body.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+    // an unnamed compound with MOTION_BLOCKING, 256 heights of 1 packed into 9 bits each
+    body.extend_from_slice(&[0x0A, 0, 0, 0x0C, 0, 15]);
+    body.extend_from_slice(b"MOTION_BLOCKING");
+    body.put_i32(37);
+    let full = (0..7).map(|entry| 1u64 << (entry * 9)).sum::<u64>();
+    for _ in 0..36 {
+        body.put_u64(full);
+    }
+    body.put_u64((0..4).map(|entry| 1u64 << (entry * 9)).sum::<u64>());
+    body.put_u8(0);
+
+    varint(&mut body, 1024);
+    body.extend_from_slice(&[PLAINS as u8; 1024]);
+
+    let mut section = Vec::new();
+    section.put_i16(256);
+    // 4 bits per block, palette of air and stone
+    section.extend_from_slice(&[4, 2, 0, STONE as u8]);
+    varint(&mut section, 256);
+    for long in 0..256 {
+        section.put_u64(if long < 16 { 0x1111_1111_1111_1111 } else { 0 });
+    }
+    varint(&mut body, section.len() as i32);
+    body.extend_from_slice(&section);
+
+    // no block entities
+    body.push(0);
+
+    let mut frame = Vec::new();
+    varint(&mut frame, body.len() as i32);
+    frame.extend_from_slice(&body);
+    frame
+}
+
+#[test]
+fn bit_set() {
+    let mut set = BitSet::new();
+    set.set(0, true);
+    set.set(65, true);
+    assert_eq!(set.longs(), &[1, 2]);
+    assert!(set.get(65));
+    assert!(!set.get(64));
+    assert!(!set.get(1000));
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 65]);
+    assert_eq!(set.count(), 2);
+
+    // trailing zero longs are not sent
+    set.set(65, false);
+    assert_eq!(set.longs(), &[1]);
+    let mut buf = Vec::new();
+    set.encode(&mut buf).unwrap();
+    assert_eq!(buf, vec![1, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(set.size_hint(), Some(buf.len()));
+    assert_eq!(BitSet::decode(&buf[..]).unwrap(), set);
+
+    assert_eq!((0..3).collect::<BitSet>().longs(), &[0b111]);
+    assert_eq!(BitSet::from_longs(vec![4, 0, 0]).longs(), &[4]);
+    // a length which does not fit the packet
+    assert!(BitSet::decode(&[0x7F, 0, 0][..]).is_err());
+}
+
+#[test]
+fn chunk_data() {
+    let overworld = dimension("minecraft:overworld");
+    let packet = encoder(&overworld).chunk_data(&layer(), 2, -3).unwrap();
+    assert_eq!(&encode(packet).unwrap()[..], &vanilla_layer()[..]);
+}
+
+/// Uncompressed frames of chunk 2, -3 of a superflat world with the preset
+/// `minecraft:stone;minecraft:plains`. They were written byte by byte after the 1.17.1 protocol
+/// documentation, independently of the encoder, and can be swapped for frames captured from a
+/// vanilla server with the same world.
+fn vanilla_frame(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data/vanilla")
+        .join(name);
+    std::fs::read(&path).unwrap_or_else(|err| panic!("unable to read {:?}; err={}", path, err))
+}
+
+#[test]
+fn vanilla_frames() {
+    let overworld = dimension("minecraft:overworld");
+    let encoder = encoder(&overworld);
+    let chunk_data = encoder.chunk_data(&layer(), 2, -3).unwrap();
+    assert_eq!(
+        &encode(chunk_data).unwrap()[..],
+        &vanilla_frame("chunk_data.bin")[..]
+    );
+    let update_light = encoder.update_light(&layer(), 2, -3);
+    assert_eq!(
+        &encode(update_light).unwrap()[..],
+        &vanilla_frame("update_light.bin")[..]
+    );
+}
+
+#[test]
+fn empty_chunk_data() {
+    let overworld = dimension("minecraft:overworld");
+    let chunk = Chunk::new(&overworld, Palettes::new(11, 176), PLAINS);
+    let packet = encoder(&overworld).chunk_data(&chunk, 0, 0).unwrap();
+    assert!(packet.mask.is_empty());
+    assert!(packet.data.is_empty());
+    assert_eq!(packet.biomes.len(), 1024);
+    common::round_trip(packet);
+}
+
+#[test]
+fn direct_sections() {
+    let overworld = dimension("minecraft:overworld");
+    let mut chunk = Chunk::new(&overworld, Palettes::new(20342, 176), PLAINS);
+    for state in 0..300 {
+        chunk.set_block(
+            state % 16,
+            17 + state as i32 / 256,
+            state / 16 % 16,
+            state as i32 + 1,
+        );
+    }
+    let packet = encoder(&overworld).chunk_data(&chunk, 0, 0).unwrap();
+    assert_eq!(packet.mask.longs(), &[0b10]);
+    // block count, 15 bits per block without a palette and 4096 / 4 longs
+    assert_eq!(&packet.data[..4], &[1, 44, 15, 0x80]);
+    assert_eq!(&packet.data[4..5], &[0x08]);
+    assert_eq!(packet.data.len(), 2 + 1 + 2 + 1024 * 8);
+    common::round_trip(packet);
+}
+
+#[test]
+fn update_light() {
+    let overworld = dimension("minecraft:overworld");
+    let packet = encoder(&overworld).update_light(&layer(), 2, -3);
+    // the section below the world is dark, everything above the stone gets sky light, which the
+    // client assumes for the sections above the lowest one
+    assert_eq!(packet.empty_sky_light_mask.longs(), &[0b1]);
+    assert_eq!(packet.sky_light_mask.longs(), &[0b10]);
+    assert_eq!(packet.sky_light.len(), 1);
+    let lowest = &packet.sky_light[0];
+    assert!(lowest[..128].iter().all(|&light| light == 0));
+    assert!(lowest[128..].iter().all(|&light| light == 0xFF));
+
+    let mut tower = layer();
+    tower.set_block(0, 100, 0, STONE);
+    let packet = encoder(&overworld).update_light(&tower, 2, -3);
+    assert_eq!(packet.sky_light_mask.longs(), &[0b1111_1110]);
+    assert_eq!(packet.sky_light.len(), 7);
+    // the column below the tower is dark up to y 100
+    assert_eq!(packet.sky_light[1][0], 0xF0);
+    assert_eq!(packet.sky_light[6][4 * 128], 0xF0);
+    assert_eq!(packet.sky_light[6][5 * 128], 0xFF);
+    assert!(packet.block_light_mask.is_empty());
+    assert_eq!(packet.empty_block_light_mask.longs(), &[0x3FFFF]);
+    common::round_trip(packet);
+
+    let nether = dimension("minecraft:the_nether");
+    let packet = encoder(&nether).update_light(&layer(), 0, 0);
+    assert!(packet.sky_light_mask.is_empty());
+    assert!(packet.empty_sky_light_mask.is_empty());
+    assert!(packet.sky_light.is_empty());
+}
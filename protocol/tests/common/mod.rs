@@ -0,0 +1,101 @@
+// every test binary uses a different part of the helpers
+#![allow(dead_code)]
+
+use bytes::{Buf, BytesMut};
+use protocol::codec::{Packet, Transcodeable};
+use protocol::packets::{decode, encode, read_header};
+use protocol::types::{
+    Array, Blob, Chat, ChatObj, Identifier, ItemStack, OptionalNbt, Slot, StringN, VarInt,
+};
+use std::borrow::Cow;
+
+/// Encodes the packet, decodes it again and checks that encoding the result gives the same bytes
+pub fn round_trip<P: Packet>(packet: P) {
+    let hint = packet.size_hint();
+    let frame = encode(packet).unwrap();
+
+    let mut body = frame.clone();
+    let (len, id) = read_header(&mut body).unwrap();
+    assert_eq!(id, P::id(), "wrong packet id");
+    assert_eq!(
+        len + VarInt(len as i32).size_hint().unwrap(),
+        frame.len(),
+        "wrong frame length"
+    );
+    if let Some(hint) = hint {
+        assert_eq!(hint, body.remaining(), "size hint does not match");
+    }
+
+    P::decode(&mut body).unwrap();
+    assert!(!body.has_remaining(), "{} bytes left", body.remaining());
+    // decode the whole frame the way the server reads it
+    let decoded = decode::<P, _>(frame.clone()).unwrap();
+    assert_eq!(encode(decoded).unwrap(), frame, "packet changed");
+}
+
+/// Checks the packet against bytes written down from the protocol documentation, `bytes` starts
+/// with the packet id
+pub fn known_bytes<P: Packet>(packet: P, bytes: &[u8]) {
+    let mut expected = BytesMut::new();
+    VarInt(bytes.len() as i32).encode(&mut expected).unwrap();
+    expected.extend_from_slice(bytes);
+    assert_eq!(
+        &encode(packet).unwrap()[..],
+        &expected[..],
+        "wrong encoding"
+    );
+
+    let mut body = expected.clone().freeze();
+    let (_, id) = read_header(&mut body).unwrap();
+    assert_eq!(id, P::id(), "wrong packet id");
+    P::decode(&mut body).unwrap();
+    assert!(!body.has_remaining(), "{} bytes left", body.remaining());
+    let decoded = decode::<P, _>(expected.clone().freeze()).unwrap();
+    assert_eq!(
+        &encode(decoded).unwrap()[..],
+        &expected[..],
+        "packet changed"
+    );
+}
+
+pub fn chat(text: &str) -> Chat {
+    Chat::Obj(ChatObj {
+        text: Some(text.to_string()),
+        ..Default::default()
+    })
+}
+
+pub fn ident(name: &'static str) -> Identifier {
+    Identifier::from_static(name)
+}
+
+pub fn string<const N: usize>(text: &str) -> StringN<N> {
+    StringN(Cow::Owned(text.to_string()))
+}
+
+/// Only has a single entry, as the order of entries is not stable
+pub fn blob() -> Blob {
+    let mut blob = Blob::new();
+    blob.insert("count", 3i32).unwrap();
+    blob
+}
+
+pub fn item() -> Slot {
+    Some(ItemStack {
+        item: VarInt(1),
+        count: 64,
+        nbt: OptionalNbt(None),
+    })
+}
+
+pub fn enchanted_item() -> Slot {
+    Some(ItemStack {
+        item: VarInt(603),
+        count: 1,
+        nbt: OptionalNbt(Some(blob())),
+    })
+}
+
+pub fn varints(values: &[i32]) -> Array<VarInt, VarInt> {
+    Array::new(values.iter().copied().map(VarInt).collect())
+}
@@ -0,0 +1,195 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use protocol::codec::{DecodeError, Transcodeable};
+use protocol::packets::compression::{Compression, Compressor, Flate2};
+use protocol::packets::MAX_DECOMPRESSED_SIZE;
+use protocol::types::VarInt;
+
+const LEVEL: u32 = 4;
+const THRESHOLD: usize = 256;
+
+type Backend = (&'static str, fn() -> Box<dyn Compressor>);
+
+fn backends() -> Vec<Backend> {
+    vec![
+        ("flate2", || Box::new(Flate2::new(LEVEL))),
+        #[cfg(feature = "libdeflate")]
+        ("libdeflate", || {
+            Box::new(protocol::packets::compression::Libdeflate::new(LEVEL))
+        }),
+    ]
+}
+
+fn new(backend: &Backend) -> Compression {
+    Compression::with_compressor((backend.1)(), LEVEL)
+}
+
+/// A frame as produced by `encode`, with a packet id of 0x22 and `len` bytes of data
+fn frame(len: usize) -> Bytes {
+    let mut frame = BytesMut::new();
+    VarInt((len + 1) as i32).encode(&mut frame).unwrap();
+    frame.put_u8(0x22);
+    frame.extend((0..len).map(|i| (i % 7 + i / 100) as u8));
+    frame.freeze()
+}
+
+/// A compressed frame with an arbitrary data length and compressed data
+fn compressed(dlen: usize, data: &[u8]) -> Bytes {
+    let dlen = VarInt(dlen as i32);
+    let mut frame = BytesMut::new();
+    VarInt((data.len() + dlen.size_hint().unwrap()) as i32)
+        .encode(&mut frame)
+        .unwrap();
+    dlen.encode(&mut frame).unwrap();
+    frame.extend_from_slice(data);
+    frame.freeze()
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut compressor = Flate2::new(LEVEL);
+    let mut output = vec![0; compressor.compress_bound(data.len())];
+    let len = compressor.compress(data, &mut output);
+    output.truncate(len);
+    output
+}
+
+#[test]
+fn round_trip() {
+    for backend in backends() {
+        let mut compression = new(&backend);
+        for len in [THRESHOLD, 1 << 12, 1 << 17] {
+            let frame = frame(len);
+            let packed = compression.compress(frame.clone(), THRESHOLD);
+            assert!(packed.len() < frame.len(), "{}", backend.0);
+            // every backend reads what any other one wrote
+            for other in backends() {
+                let unpacked = new(&other)
+                    .decompress(packed.clone(), THRESHOLD, MAX_DECOMPRESSED_SIZE)
+                    .unwrap();
+                assert_eq!(unpacked, frame, "{} -> {}", backend.0, other.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn below_threshold() {
+    for backend in backends() {
+        let mut compression = new(&backend);
+        let frame = frame(THRESHOLD - 2);
+        let packed = compression.compress(frame.clone(), THRESHOLD);
+        // length, a data length of 0 and the packet as it was
+        let mut expected = vec![0x80, 0x02, 0x00];
+        expected.extend_from_slice(&frame[2..]);
+        assert_eq!(&packed[..], &expected[..], "{}", backend.0);
+        let unpacked = compression
+            .decompress(packed, THRESHOLD, MAX_DECOMPRESSED_SIZE)
+            .unwrap();
+        assert_eq!(unpacked, frame, "{}", backend.0);
+    }
+}
+
+#[test]
+fn oversize_data_length() {
+    let frame = frame(THRESHOLD);
+    for backend in backends() {
+        let mut compression = new(&backend);
+        let packed = compressed(MAX_DECOMPRESSED_SIZE + 1, &zlib(&frame[2..]));
+        assert!(
+            matches!(
+                compression.decompress(packed, THRESHOLD, MAX_DECOMPRESSED_SIZE),
+                Err(DecodeError::OversizePacket { max, recv })
+                    if max == MAX_DECOMPRESSED_SIZE && recv == MAX_DECOMPRESSED_SIZE + 1
+            ),
+            "{}",
+            backend.0
+        );
+    }
+}
+
+#[test]
+fn compressed_below_threshold() {
+    let frame = frame(THRESHOLD - 2);
+    let len = frame.len() - 2;
+    let data = zlib(&frame[2..]);
+    for backend in backends() {
+        let mut compression = new(&backend);
+        assert!(
+            matches!(
+                compression.decompress(compressed(len, &data), THRESHOLD, MAX_DECOMPRESSED_SIZE),
+                Err(DecodeError::InvalidData)
+            ),
+            "{}",
+            backend.0
+        );
+        // the same packet is fine with a lower threshold
+        let unpacked = compression
+            .decompress(compressed(len, &data), len, MAX_DECOMPRESSED_SIZE)
+            .unwrap();
+        assert_eq!(unpacked, frame, "{}", backend.0);
+    }
+}
+
+#[test]
+fn wrong_data_length() {
+    let frame = frame(THRESHOLD);
+    let data = zlib(&frame[2..]);
+    let len = frame.len() - 2;
+    for backend in backends() {
+        let mut compression = new(&backend);
+        for dlen in [len - 1, len + 1, len * 2] {
+            assert!(
+                matches!(
+                    compression.decompress(
+                        compressed(dlen, &data),
+                        THRESHOLD,
+                        MAX_DECOMPRESSED_SIZE
+                    ),
+                    Err(DecodeError::InvalidData)
+                ),
+                "{}; dlen={}",
+                backend.0,
+                dlen
+            );
+        }
+        // still usable afterwards
+        let unpacked = compression
+            .decompress(compressed(len, &data), THRESHOLD, MAX_DECOMPRESSED_SIZE)
+            .unwrap();
+        assert_eq!(unpacked, frame, "{}", backend.0);
+    }
+}
+
+#[test]
+fn corrupt_data() {
+    let frame = frame(THRESHOLD);
+    let len = frame.len() - 2;
+    let data = zlib(&frame[2..]);
+    let mut flipped = data.clone();
+    flipped[data.len() / 2] ^= 0xFF;
+    let mut checksum = data.clone();
+    *checksum.last_mut().unwrap() ^= 1;
+    for backend in backends() {
+        let mut compression = new(&backend);
+        for corrupt in [
+            flipped.clone(),
+            checksum.clone(),
+            data[..data.len() / 2].to_vec(),
+            vec![0xFF; 32],
+            vec![],
+        ] {
+            assert!(
+                matches!(
+                    compression.decompress(
+                        compressed(len, &corrupt),
+                        THRESHOLD,
+                        MAX_DECOMPRESSED_SIZE
+                    ),
+                    Err(DecodeError::InvalidData)
+                ),
+                "{}; data={:?}",
+                backend.0,
+                corrupt
+            );
+        }
+    }
+}
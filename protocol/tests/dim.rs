@@ -0,0 +1,108 @@
+use nbt::Value;
+use protocol::packets::play::dim::*;
+
+fn registry<'a>(blob: &'a nbt::Blob, name: &'static str) -> &'a Vec<Value> {
+    match blob.get(name) {
+        Some(Value::Compound(registry)) => match &registry["value"] {
+            Value::List(entries) => entries,
+            other => panic!("value is not a list: {:?}", other),
+        },
+        other => panic!("{} is not a compound: {:?}", name, other),
+    }
+}
+
+fn entry<'a>(entries: &'a [Value], name: &str) -> &'a nbt::Map<String, Value> {
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Value::Compound(entry) => Some(entry),
+            _ => None,
+        })
+        .find(|entry| entry["name"] == Value::String(name.to_string()))
+        .unwrap_or_else(|| panic!("{} is missing", name))
+}
+
+fn element<'a>(entries: &'a [Value], name: &str) -> &'a nbt::Map<String, Value> {
+    match &entry(entries, name)["element"] {
+        Value::Compound(element) => element,
+        other => panic!("element is not a compound: {:?}", other),
+    }
+}
+
+#[test]
+fn vanilla_dimension_types() {
+    let blob = DimensionCodec::default().to_blob().unwrap();
+    let types = registry(&blob, "minecraft:dimension_type");
+    assert_eq!(types.len(), 4);
+
+    let overworld = element(types, "minecraft:overworld");
+    assert_eq!(overworld["has_skylight"], Value::Byte(1));
+    assert_eq!(overworld["coordinate_scale"], Value::Double(1.0));
+    assert!(!overworld.contains_key("fixed_time"));
+
+    let nether = element(types, "minecraft:the_nether");
+    assert_eq!(nether["fixed_time"], Value::Long(18000));
+    assert_eq!(nether["logical_height"], Value::Int(128));
+    assert_eq!(entry(types, "minecraft:the_end")["id"], Value::Int(3));
+}
+
+#[test]
+fn vanilla_biomes() {
+    let blob = DimensionCodec::default().to_blob().unwrap();
+    let biomes = registry(&blob, "minecraft:worldgen/biome");
+    assert_eq!(biomes.len(), 81);
+    assert_eq!(entry(biomes, "minecraft:the_void")["id"], Value::Int(127));
+
+    let plains = element(biomes, "minecraft:plains");
+    assert_eq!(plains["precipitation"], Value::String("rain".to_string()));
+    assert_eq!(plains["temperature"], Value::Float(0.8));
+    match &plains["effects"] {
+        Value::Compound(effects) => {
+            assert_eq!(effects["sky_color"], Value::Int(7907327));
+            assert!(effects.contains_key("mood_sound"));
+            assert!(!effects.contains_key("music"));
+        }
+        other => panic!("effects are not a compound: {:?}", other),
+    }
+
+    let basalt = element(biomes, "minecraft:basalt_deltas");
+    assert!(basalt.contains_key("particle"));
+}
+
+#[test]
+fn sky_colors() {
+    assert_eq!(sky_color(0.8), 7907327);
+    assert_eq!(sky_color(2.0), 7254527);
+    assert_eq!(sky_color(-0.5), 8625919);
+}
+
+#[test]
+fn custom_entries() {
+    let mut codec = DimensionCodec::default();
+    let plains = codec
+        .biomes
+        .get("minecraft:plains")
+        .unwrap()
+        .element
+        .clone();
+    assert_eq!(codec.biomes.insert("custom:meadow", plains.clone()), 176);
+    assert_eq!(codec.biomes.insert("minecraft:plains", plains), 1);
+    assert_eq!(codec.biomes.value.len(), 82);
+
+    let tall = DimensionType {
+        min_y: -64,
+        height: 384,
+        logical_height: 384,
+        ..codec
+            .dimension_types
+            .get("minecraft:overworld")
+            .unwrap()
+            .element
+            .clone()
+    };
+    assert_eq!(codec.dimension_types.insert("custom:tall", tall), 4);
+
+    let blob = codec.to_blob().unwrap();
+    let types = registry(&blob, "minecraft:dimension_type");
+    assert_eq!(element(types, "custom:tall")["min_y"], Value::Int(-64));
+}
@@ -0,0 +1,391 @@
+use protocol::packets::play::dim::DimensionCodec;
+use protocol::registry::{Blocks, Registries};
+use protocol::world::gen::*;
+use protocol::world::{Chunk, Palettes, Storage, World, AIR};
+use std::path::Path;
+
+const STONE: i32 = 1;
+/// Default state of `minecraft:oak_log`
+const LOG: i32 = 3;
+/// Any distinct states will do for the noise generator
+const NOISE_BLOCKS: NoiseBlocks = NoiseBlocks {
+    stone: STONE,
+    dirt: 2,
+    grass: 3,
+    sand: 4,
+    water: 5,
+    bedrock: 6,
+};
+const FEATURE_BLOCKS: FeatureBlocks = FeatureBlocks {
+    oak: Wood { log: 7, leaves: 8 },
+    birch: Wood { log: 9, leaves: 10 },
+    spruce: Wood {
+        log: 11,
+        leaves: 12,
+    },
+    jungle: Wood {
+        log: 13,
+        leaves: 14,
+    },
+    coal_ore: 15,
+    iron_ore: 16,
+    copper_ore: 17,
+    gold_ore: 18,
+    redstone_ore: 19,
+    lapis_ore: 20,
+    diamond_ore: 21,
+};
+
+fn blocks() -> Blocks {
+    let reports = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/reports");
+    Registries::load(&reports).unwrap().blocks
+}
+
+fn biome(codec: &DimensionCodec, name: &str) -> i32 {
+    codec.biomes.get(name).unwrap().id
+}
+
+fn world(generator: Box<dyn Generator>) -> World {
+    let codec = DimensionCodec::default();
+    let overworld = codec.dimension_types.get("minecraft:overworld").unwrap();
+    World::new(overworld.element.clone(), Palettes::new(64, 176), generator)
+}
+
+#[test]
+fn flat_preset() {
+    let codec = DimensionCodec::default();
+    let flat = Flat::from_preset(
+        "minecraft:stone,2*oak_log;minecraft:desert;village",
+        &blocks(),
+        &codec,
+    )
+    .unwrap();
+    assert_eq!(
+        flat.layers(),
+        &[
+            Layer {
+                state: STONE,
+                height: 1
+            },
+            Layer {
+                state: LOG,
+                height: 2
+            }
+        ]
+    );
+    assert_eq!(flat.biome(), biome(&codec, "minecraft:desert"));
+
+    let flat = Flat::from_preset("60*stone", &blocks(), &codec).unwrap();
+    assert_eq!(flat.layers()[0].height, 60);
+    assert_eq!(flat.biome(), biome(&codec, "minecraft:plains"));
+}
+
+#[test]
+fn invalid_presets() {
+    let codec = DimensionCodec::default();
+    let blocks = blocks();
+    let parse = |preset| Flat::from_preset(preset, &blocks, &codec);
+    assert!(matches!(parse(""), Err(GeneratorError::Preset(_))));
+    assert!(matches!(parse("x*stone"), Err(GeneratorError::Preset(_))));
+    assert!(matches!(parse("-1*stone"), Err(GeneratorError::Preset(_))));
+    assert!(matches!(
+        parse("stone,minecraft:dirt"),
+        Err(GeneratorError::UnknownBlock(name)) if name == "minecraft:dirt"
+    ));
+    assert!(matches!(
+        parse("stone;minecraft:moon"),
+        Err(GeneratorError::UnknownBiome(_))
+    ));
+    // the vanilla default needs the full block registry
+    assert!(parse(CLASSIC_FLAT).is_err());
+}
+
+#[test]
+fn flat_chunks() {
+    let codec = DimensionCodec::default();
+    let flat = Flat::from_preset("20*stone,oak_log;desert", &blocks(), &codec).unwrap();
+    assert!(flat.is_flat());
+    let mut world = world(Box::new(flat));
+    let chunk = world.chunk(-3, 7).clone();
+    assert_eq!(chunk.block(0, 0, 0), STONE);
+    assert_eq!(chunk.block(15, 19, 15), STONE);
+    assert_eq!(chunk.block(7, 20, 9), LOG);
+    assert_eq!(chunk.block(7, 21, 9), AIR);
+    assert_eq!(chunk.top(4, 4), Some(20));
+    assert_eq!(chunk.biome(0, 100, 0), biome(&codec, "minecraft:desert"));
+    // whole sections of a single block take no space for the blocks
+    assert_eq!(
+        chunk.sections()[0].blocks.storage(),
+        &Storage::Single(STONE)
+    );
+    assert!(chunk.sections()[2].is_empty());
+}
+
+#[test]
+fn void_chunks() {
+    let mut world = world(Box::new(Void::new(127)));
+    assert!(world.is_flat());
+    assert_eq!(world.top(100, -100), None);
+    let chunk: &Chunk = world.chunk(6, -7);
+    assert!(chunk.sections().iter().all(|section| section.is_empty()));
+    assert_eq!(chunk.biome(3, 3, 3), 127);
+}
+
+#[test]
+fn lazy_generation() {
+    let flat = Flat::new(
+        vec![Layer {
+            state: STONE,
+            height: 4,
+        }],
+        1,
+    );
+    let mut world = world(Box::new(flat));
+    assert!(world.is_empty());
+    assert!(world.get(0, 0).is_none());
+
+    assert_eq!(world.block(-1, 3, -1), STONE);
+    assert_eq!(world.len(), 1);
+    assert!(world.get(-1, -1).is_some());
+
+    // changes stay, the chunk is not generated again
+    assert_eq!(world.set_block(-16, 3, -16, AIR), Some(STONE));
+    assert_eq!(world.block(-16, 3, -16), AIR);
+    assert_eq!(world.top(-16, -16), Some(2));
+    assert_eq!(world.len(), 1);
+
+    assert_eq!(world.top(16, 0), Some(3));
+    assert_eq!(world.len(), 2);
+}
+
+#[test]
+fn java_random() {
+    let mut random = Random::new(0);
+    assert_eq!(random.next_i32(), -1155484576);
+    let mut random = Random::new(0);
+    assert_eq!(random.next_f64(), 0.730967787376657);
+    let mut random = Random::new(0);
+    assert_eq!(random.next_i64(), -4962768465676381896);
+    let mut random = Random::new(42);
+    for _ in 0..1000 {
+        assert!((0..10).contains(&random.next_bounded(10)));
+    }
+}
+
+#[test]
+fn noise_is_deterministic() {
+    let codec = DimensionCodec::default();
+    let noise = |seed| Box::new(Noise::new(seed, NOISE_BLOCKS, None, &codec).unwrap());
+    let chunk = world(noise(42)).chunk(3, -5).clone();
+    assert_eq!(&chunk, world(noise(42)).chunk(3, -5));
+    assert_ne!(&chunk, world(noise(43)).chunk(3, -5));
+    assert!(!noise(42).is_flat());
+}
+
+#[test]
+fn noise_terrain() {
+    let codec = DimensionCodec::default();
+    let noise = Noise::new(1, NOISE_BLOCKS, None, &codec).unwrap();
+    let biomes = noise.biomes().to_vec();
+    let heights = (-40..40)
+        .flat_map(|x| (-40..40).map(move |z| (x * 37, z * 37)))
+        .map(|(x, z)| noise.height(x, z))
+        .collect::<Vec<_>>();
+    // both land and oceans
+    assert!(heights.iter().any(|&height| height < SEA_LEVEL - 4));
+    assert!(heights.iter().any(|&height| height > SEA_LEVEL + 10));
+
+    let reference = Noise::new(1, NOISE_BLOCKS, None, &codec).unwrap();
+    let mut world = world(Box::new(noise));
+    let surface = [NOISE_BLOCKS.grass, NOISE_BLOCKS.sand, NOISE_BLOCKS.stone];
+    for (chunk_x, chunk_z) in [(0, 0), (-9, 4), (20, -31), (-50, -50)] {
+        let chunk = world.chunk(chunk_x, chunk_z).clone();
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(chunk.block(x, 0, z), NOISE_BLOCKS.bedrock);
+                let top = chunk.top(x, z).unwrap();
+                let block = chunk.block(x, top, z);
+                if block == NOISE_BLOCKS.water {
+                    // oceans are filled up to sea level
+                    assert_eq!(top, SEA_LEVEL);
+                    assert!(surface.contains(&chunk.block(
+                        x,
+                        reference.height(chunk_x * 16 + x as i32, chunk_z * 16 + z as i32),
+                        z
+                    )));
+                } else {
+                    assert!(surface.contains(&block));
+                }
+                assert_eq!(chunk.block(x, top + 1, z), AIR);
+                assert_eq!(chunk.block(x, 1, z), STONE);
+                assert!(biomes.contains(&chunk.biome(x, top, z)));
+            }
+        }
+    }
+}
+
+#[test]
+fn seeds() {
+    assert_eq!(parse_seed("12345"), Some(12345));
+    assert_eq!(parse_seed(" -8 "), Some(-8));
+    // like "hello".hashCode()
+    assert_eq!(parse_seed("hello"), Some(99162322));
+    assert_eq!(parse_seed("glacier"), Some(108181935));
+    assert_eq!(parse_seed(""), None);
+    assert_eq!(parse_seed("0"), None);
+
+    assert_eq!(hashed_seed(0), 8794265229978523055);
+    assert_eq!(hashed_seed(12345), 293737985876514017);
+}
+
+fn decorated(seed: i64) -> World {
+    let codec = DimensionCodec::default();
+    let noise = Noise::new(seed, NOISE_BLOCKS, Some(FEATURE_BLOCKS), &codec).unwrap();
+    world(Box::new(noise))
+}
+
+#[test]
+fn decorations_are_deterministic() {
+    let mut world = decorated(7);
+    let chunk = world.chunk(0, 0).clone();
+    // all chunks around it are decorated before it is handed out
+    assert_eq!(world.len(), 25);
+    assert_eq!(decorated(7).chunk(0, 0), &chunk);
+    assert_ne!(decorated(8).chunk(0, 0), &chunk);
+}
+
+#[test]
+fn decorations_do_not_depend_on_order() {
+    let positions = (-3..3)
+        .flat_map(|x| (-3..3).map(move |z| (x, z)))
+        .collect::<Vec<_>>();
+    // the palettes depend on the order blocks were set in, so only the blocks are compared
+    let load = |order: &[(i32, i32)]| {
+        let mut world = decorated(1);
+        for &(x, z) in order {
+            world.chunk(x, z);
+        }
+        positions
+            .iter()
+            .flat_map(|&(x, z)| {
+                let chunk = world.chunk(x, z);
+                (0..16)
+                    .flat_map(|x| (0..16).flat_map(move |z| (0..256).map(move |y| (x, y, z))))
+                    .map(|(x, y, z)| chunk.block(x, y, z))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = load(&positions);
+    let mut reversed = positions.clone();
+    reversed.reverse();
+    assert_eq!(load(&reversed), expected);
+    // far apart first, so every chunk is decorated next to different neighbours
+    let mut scattered = positions.clone();
+    scattered.sort_by_key(|&(x, z)| ((x * 7 + z * 13).rem_euclid(5), x, z));
+    assert_eq!(load(&scattered), expected);
+
+    let logs = [
+        FEATURE_BLOCKS.oak.log,
+        FEATURE_BLOCKS.birch.log,
+        FEATURE_BLOCKS.spruce.log,
+        FEATURE_BLOCKS.jungle.log,
+    ];
+    let trees = expected.iter().filter(|state| logs.contains(state)).count();
+    assert!(trees > 0);
+}
+
+/// Palettes depend on the order blocks were set in, so chunks are compared by their blocks
+fn blocks_of(chunk: &Chunk) -> Vec<i32> {
+    (0..16)
+        .flat_map(|x| (0..16).flat_map(move |z| (0..256).map(move |y| (x, y, z))))
+        .map(|(x, y, z)| chunk.block(x, y, z))
+        .collect()
+}
+
+#[test]
+fn terrain_generated_outside_of_the_world() {
+    let mut world = decorated(2);
+    let mut detached = decorated(2);
+    let generator = detached.terrain_generator();
+    for (x, z) in [(0, 0), (1, 0), (5, 5), (0, 1), (4, 4)] {
+        let missing = detached.missing(x, z);
+        assert!(missing.contains(&(x, z)) || detached.get(x, z).is_some());
+        for (x, z) in missing {
+            detached.insert(x, z, generator.generate(x, z));
+        }
+        assert!(detached.missing(x, z).is_empty());
+        let len = detached.len();
+        let chunk = blocks_of(detached.chunk(x, z));
+        // everything was generated beforehand, only decorations are left
+        assert_eq!(detached.len(), len);
+        assert!(chunk == blocks_of(world.chunk(x, z)), "{} {}", x, z);
+        assert!(detached.missing(x, z).is_empty());
+    }
+}
+
+#[test]
+fn unloading() {
+    let mut world = decorated(3);
+    world.set_max_chunks(40);
+    let mut unlimited = decorated(3);
+    // there and back again, chunks on the way back were unloaded in between
+    let mut path = (0..24)
+        .flat_map(|x| (0..2).map(move |z| (x, z)))
+        .collect::<Vec<_>>();
+    path.extend(path.clone().into_iter().rev());
+    for (x, z) in path {
+        let chunk = blocks_of(world.chunk(x, z));
+        assert!(world.len() <= 40, "{} chunks", world.len());
+        assert!(chunk == blocks_of(unlimited.chunk(x, z)), "{} {}", x, z);
+    }
+    assert!(unlimited.len() > 40);
+
+    // only the least recently used ones are unloaded
+    world.chunk(0, 0);
+    for x in 20..24 {
+        world.chunk(x, 0);
+        assert!(world.get(0, 0).is_some());
+    }
+}
+
+#[test]
+fn decorations() {
+    let mut world = decorated(1);
+    let logs = [
+        FEATURE_BLOCKS.oak.log,
+        FEATURE_BLOCKS.birch.log,
+        FEATURE_BLOCKS.spruce.log,
+        FEATURE_BLOCKS.jungle.log,
+    ];
+    let (mut trees, mut ores, mut caves) = (0, 0, 0);
+    for chunk_x in -4..4 {
+        for chunk_z in -4..4 {
+            let chunk = world.chunk(chunk_x, chunk_z).clone();
+            for x in 0..16 {
+                for z in 0..16 {
+                    let top = chunk.top(x, z).unwrap();
+                    for y in 1..top {
+                        let state = chunk.block(x, y, z);
+                        // trees grow out of the ground, which turns into dirt
+                        if logs.contains(&state) && !logs.contains(&chunk.block(x, y - 1, z)) {
+                            assert_eq!(chunk.block(x, y - 1, z), NOISE_BLOCKS.dirt);
+                            trees += 1;
+                        }
+                        if (FEATURE_BLOCKS.coal_ore..=FEATURE_BLOCKS.diamond_ore).contains(&state) {
+                            ores += 1;
+                        }
+                        // the terrain is never this low, but caves are
+                        if y < 25 && state == AIR {
+                            caves += 1;
+                        }
+                    }
+                    assert_eq!(chunk.block(x, 0, z), NOISE_BLOCKS.bedrock);
+                }
+            }
+        }
+    }
+    assert!(trees > 0);
+    assert!(ores > 1000);
+    assert!(caves > 0);
+}
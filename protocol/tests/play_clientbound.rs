@@ -0,0 +1,1704 @@
+mod common;
+
+use bytes::Bytes;
+use common::*;
+use protocol::packets::play::clientbound::*;
+use protocol::types::{
+    Array, BitSet, Metadata, MetadataValue, OptionalNbt, Particle as ParticleType, ParticleData,
+    Position, Remaining, VarInt, VarLong, VibrationDestination,
+};
+use uuid::Uuid;
+
+const UUID: Uuid = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+const UUID_BYTES: [u8; 16] = [
+    0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef,
+];
+
+#[test]
+fn spawn_entity() {
+    round_trip(SpawnEntity {
+        eid: VarInt(42),
+        uuid: UUID,
+        kind: VarInt(2),
+        x: 1.5,
+        y: -64.0,
+        z: 300.25,
+        pitch: 12,
+        yaw: 250,
+        data: 1,
+        velocity_x: -100,
+        velocity_y: 0,
+        velocity_z: 8000,
+    });
+}
+
+#[test]
+fn spawn_experience_orb() {
+    round_trip(SpawnExperienceOrb {
+        id: VarInt(7),
+        x: 0.5,
+        y: 70.0,
+        z: -0.5,
+        count: 11,
+    });
+}
+
+#[test]
+fn spawn_living_entity() {
+    round_trip(SpawnLivingEntity {
+        eid: VarInt(1000),
+        uuid: UUID,
+        kind: VarInt(105),
+        x: 10.0,
+        y: 65.0,
+        z: 10.0,
+        yaw: 64,
+        pitch: 0,
+        head_pitch: 255,
+        velocity_x: 1,
+        velocity_y: 2,
+        velocity_z: 3,
+    });
+}
+
+#[test]
+fn spawn_painting() {
+    round_trip(SpawnPainting {
+        eid: VarInt(3),
+        uuid: UUID,
+        motive: VarInt(12),
+        location: Position::new(-30_000_000, -64, 29_999_999),
+        direction: 2,
+    });
+}
+
+#[test]
+fn spawn_player() {
+    round_trip(SpawnPlayer {
+        eid: VarInt(1),
+        uuid: UUID,
+        x: 8.5,
+        y: 64.0,
+        z: 8.5,
+        yaw: 128,
+        pitch: 32,
+    });
+}
+
+#[test]
+fn sculk_vibration_signal() {
+    round_trip(SculkVibrationSignal {
+        source: Position::new(1, 2, 3),
+        destination: VibrationDestination::Block(Position::new(4, -5, 6)),
+        arrival_ticks: VarInt(20),
+    });
+    round_trip(SculkVibrationSignal {
+        source: Position::new(1, 2, 3),
+        destination: VibrationDestination::Entity(VarInt(77)),
+        arrival_ticks: VarInt(20),
+    });
+}
+
+#[test]
+fn entity_animation() {
+    round_trip(EntityAnimation {
+        eid: VarInt(9),
+        animation: 4,
+    });
+}
+
+#[test]
+fn statistics() {
+    round_trip(Statistics(Array::new(vec![
+        Statistic {
+            category: VarInt(8),
+            id: VarInt(3),
+            value: VarInt(1200),
+        },
+        Statistic {
+            category: VarInt(0),
+            id: VarInt(1),
+            value: VarInt(-1),
+        },
+    ])));
+}
+
+#[test]
+fn acknowledge_player_digging() {
+    round_trip(AcknowledgePlayerDigging {
+        location: Position::new(-1, 63, -1),
+        block: VarInt(0),
+        status: VarInt(2),
+        successful: true,
+    });
+}
+
+#[test]
+fn block_break_animation() {
+    round_trip(BlockBreakAnimation {
+        eid: VarInt(5),
+        location: Position::new(0, 0, 0),
+        stage: 9,
+    });
+}
+
+#[test]
+fn block_entity_data() {
+    round_trip(BlockEntityData {
+        location: Position::new(100, 70, -100),
+        action: 9,
+        data: OptionalNbt(Some(blob())),
+    });
+    round_trip(BlockEntityData {
+        location: Position::new(100, 70, -100),
+        action: 9,
+        data: OptionalNbt(None),
+    });
+}
+
+#[test]
+fn block_action() {
+    round_trip(BlockAction {
+        location: Position::new(5, 6, 7),
+        action: 1,
+        param: 1,
+        block: VarInt(153),
+    });
+}
+
+#[test]
+fn block_change() {
+    round_trip(BlockChange {
+        location: Position::new(5, 2047, 7),
+        state: VarInt(1),
+    });
+}
+
+#[test]
+fn boss_bar() {
+    let actions = vec![
+        BossBarAction::Add {
+            title: chat("Ender Dragon"),
+            health: 1.0,
+            color: VarInt(5),
+            division: VarInt(0),
+            flags: 0x03,
+        },
+        BossBarAction::Remove,
+        BossBarAction::UpdateHealth(0.5),
+        BossBarAction::UpdateTitle(chat("Wither")),
+        BossBarAction::UpdateStyle {
+            color: VarInt(1),
+            division: VarInt(4),
+        },
+        BossBarAction::UpdateFlags(0x04),
+    ];
+    for action in actions {
+        round_trip(BossBar { uuid: UUID, action });
+    }
+
+    let action = |action: u8, data: &[u8]| [&[0x0D][..], &UUID_BYTES, &[action], data].concat();
+    known_bytes(
+        BossBar {
+            uuid: UUID,
+            action: BossBarAction::Add {
+                title: chat("Wither"),
+                health: 1.0,
+                color: VarInt(5),
+                division: VarInt(0),
+                flags: 0x03,
+            },
+        },
+        &action(
+            0x00,
+            &[
+                &[0x11][..],
+                br#"{"text":"Wither"}"#,
+                &[0x3F, 0x80, 0x00, 0x00, 0x05, 0x00, 0x03],
+            ]
+            .concat(),
+        ),
+    );
+    known_bytes(
+        BossBar {
+            uuid: UUID,
+            action: BossBarAction::Remove,
+        },
+        &action(0x01, &[]),
+    );
+    known_bytes(
+        BossBar {
+            uuid: UUID,
+            action: BossBarAction::UpdateHealth(0.5),
+        },
+        &action(0x02, &[0x3F, 0x00, 0x00, 0x00]),
+    );
+    known_bytes(
+        BossBar {
+            uuid: UUID,
+            action: BossBarAction::UpdateStyle {
+                color: VarInt(1),
+                division: VarInt(4),
+            },
+        },
+        &action(0x04, &[0x01, 0x04]),
+    );
+}
+
+#[test]
+fn server_difficulty() {
+    round_trip(ServerDifficulty {
+        difficulty: 2,
+        locked: false,
+    });
+}
+
+#[test]
+fn chat_message() {
+    round_trip(ChatMessage {
+        message: chat("hello"),
+        position: 0,
+        sender: UUID,
+    });
+}
+
+#[test]
+fn clear_titles() {
+    round_trip(ClearTitles { reset: true });
+}
+
+#[test]
+fn tab_complete() {
+    round_trip(TabComplete {
+        id: VarInt(3),
+        start: VarInt(1),
+        length: VarInt(2),
+        matches: Array::new(vec![
+            TabMatch {
+                text: string("gamemode"),
+                tooltip: None,
+            },
+            TabMatch {
+                text: string("gamerule"),
+                tooltip: Some(chat("Sets a game rule")),
+            },
+        ]),
+    });
+}
+
+#[test]
+fn declare_commands() {
+    round_trip(DeclareCommands {
+        nodes: Array::new(vec![
+            CommandNode {
+                flags: NODE_ROOT,
+                children: varints(&[1, 4]),
+                redirect: None,
+                name: None,
+                parser: None,
+                suggestions: None,
+            },
+            CommandNode {
+                flags: NODE_LITERAL,
+                children: varints(&[2, 3]),
+                redirect: None,
+                name: Some(string("tp")),
+                parser: None,
+                suggestions: None,
+            },
+            CommandNode {
+                flags: NODE_ARGUMENT | NODE_EXECUTABLE,
+                children: varints(&[]),
+                redirect: None,
+                name: Some(string("targets")),
+                parser: Some((ident("minecraft:entity"), ParserProperties::Flags(0x00))),
+                suggestions: None,
+            },
+            CommandNode {
+                flags: NODE_ARGUMENT | NODE_EXECUTABLE,
+                children: varints(&[5]),
+                redirect: None,
+                name: Some(string("distance")),
+                parser: Some((
+                    ident("brigadier:double"),
+                    ParserProperties::Double {
+                        min: Some(0.0),
+                        max: None,
+                    },
+                )),
+                suggestions: Some(ident("minecraft:ask_server")),
+            },
+            CommandNode {
+                flags: NODE_LITERAL,
+                children: varints(&[]),
+                redirect: Some(VarInt(1)),
+                name: Some(string("teleport")),
+                parser: None,
+                suggestions: None,
+            },
+            CommandNode {
+                flags: NODE_ARGUMENT | NODE_EXECUTABLE,
+                children: varints(&[6, 7, 8]),
+                redirect: None,
+                name: Some(string("count")),
+                parser: Some((
+                    ident("brigadier:integer"),
+                    ParserProperties::Integer {
+                        min: Some(1),
+                        max: Some(64),
+                    },
+                )),
+                suggestions: None,
+            },
+            CommandNode {
+                flags: NODE_ARGUMENT | NODE_EXECUTABLE,
+                children: varints(&[]),
+                redirect: None,
+                name: Some(string("message")),
+                parser: Some((
+                    ident("brigadier:string"),
+                    ParserProperties::String(VarInt(2)),
+                )),
+                suggestions: None,
+            },
+            CommandNode {
+                flags: NODE_ARGUMENT,
+                children: varints(&[]),
+                redirect: None,
+                name: Some(string("levels")),
+                parser: Some((ident("minecraft:int_range"), ParserProperties::None)),
+                suggestions: None,
+            },
+            CommandNode {
+                flags: NODE_ARGUMENT | NODE_EXECUTABLE,
+                children: varints(&[]),
+                redirect: None,
+                name: Some(string("value")),
+                parser: Some((
+                    ident("brigadier:long"),
+                    ParserProperties::Long {
+                        min: None,
+                        max: Some(i64::MAX),
+                    },
+                )),
+                suggestions: None,
+            },
+        ]),
+        root: VarInt(0),
+    });
+}
+
+#[test]
+fn close_window() {
+    round_trip(CloseWindow(1));
+}
+
+#[test]
+fn window_items() {
+    round_trip(WindowItems {
+        window: 0,
+        state: VarInt(12),
+        slots: Array::new(vec![None, item(), enchanted_item(), None]),
+        carried: None,
+    });
+}
+
+#[test]
+fn window_property() {
+    round_trip(WindowProperty {
+        window: 2,
+        property: 3,
+        value: 200,
+    });
+}
+
+#[test]
+fn set_slot() {
+    round_trip(SetSlot {
+        window: -1,
+        state: VarInt(0),
+        slot: -1,
+        data: enchanted_item(),
+    });
+}
+
+#[test]
+fn set_cooldown() {
+    round_trip(SetCooldown {
+        item: VarInt(848),
+        ticks: VarInt(20),
+    });
+}
+
+#[test]
+fn plugin_message() {
+    round_trip(PluginMessage {
+        channel: ident("minecraft:brand"),
+        data: Remaining(Bytes::from_static(b"\x05mcsrs")),
+    });
+}
+
+#[test]
+fn named_sound_effect() {
+    round_trip(NamedSoundEffect {
+        sound: ident("minecraft:entity.player.levelup"),
+        category: VarInt(7),
+        x: 8 * 100,
+        y: 8 * 64,
+        z: -8 * 100,
+        volume: 1.0,
+        pitch: 0.5,
+    });
+}
+
+#[test]
+fn disconnect() {
+    round_trip(Disconnect(chat("Server closed")));
+}
+
+#[test]
+fn entity_status() {
+    round_trip(EntityStatus { eid: 1, status: 24 });
+}
+
+#[test]
+fn explosion() {
+    round_trip(Explosion {
+        x: 1.0,
+        y: 2.0,
+        z: 3.0,
+        strength: 4.0,
+        records: Array::new(vec![
+            ExplosionRecord { x: 0, y: -1, z: 0 },
+            ExplosionRecord { x: 1, y: 0, z: -2 },
+        ]),
+        motion_x: 0.1,
+        motion_y: 0.2,
+        motion_z: 0.3,
+    });
+}
+
+#[test]
+fn unload_chunk() {
+    round_trip(UnloadChunk { x: -4, z: 12 });
+}
+
+#[test]
+fn change_game_state() {
+    round_trip(ChangeGameState {
+        reason: 3,
+        value: 1.0,
+    });
+}
+
+#[test]
+fn open_horse_window() {
+    round_trip(OpenHorseWindow {
+        window: 1,
+        slots: VarInt(17),
+        eid: 88,
+    });
+}
+
+#[test]
+fn initialize_world_border() {
+    round_trip(InitializeWorldBorder {
+        x: 0.0,
+        z: 0.0,
+        old_diameter: 59_999_968.0,
+        new_diameter: 59_999_968.0,
+        speed: VarLong(0),
+        portal_teleport_boundary: VarInt(29_999_984),
+        warning_blocks: VarInt(5),
+        warning_time: VarInt(15),
+    });
+}
+
+#[test]
+fn keep_alive() {
+    round_trip(KeepAlive(-1234567890123));
+}
+
+#[test]
+fn chunk_data() {
+    round_trip(ChunkData {
+        x: 3,
+        z: -7,
+        mask: BitSet::from_longs(vec![0b1111]),
+        heightmaps: blob(),
+        biomes: varints(&[1; 16]),
+        data: Array::new(vec![0; 64]),
+        block_entities: Array::new(vec![blob()]),
+    });
+}
+
+#[test]
+fn effect() {
+    round_trip(Effect {
+        id: 2001,
+        location: Position::new(1, 1, 1),
+        data: 1,
+        disable_relative_volume: false,
+    });
+}
+
+#[test]
+fn particle() {
+    let data = vec![
+        (0, ParticleData::None),
+        (4, ParticleData::Block(VarInt(1))),
+        (
+            15,
+            ParticleData::Dust {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.5,
+                scale: 2.0,
+            },
+        ),
+        (
+            16,
+            ParticleData::DustColorTransition {
+                from: (1.0, 0.0, 0.0),
+                scale: 1.0,
+                to: (0.0, 0.0, 1.0),
+            },
+        ),
+        (25, ParticleData::FallingDust(VarInt(66))),
+        (36, ParticleData::Item(item())),
+        (
+            37,
+            ParticleData::Vibration {
+                origin: (1.0, 2.0, 3.0),
+                destination: VibrationDestination::Entity(VarInt(4)),
+                ticks: VarInt(10),
+            },
+        ),
+    ];
+    for (id, data) in data {
+        round_trip(Particle {
+            id,
+            long_distance: true,
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            offset_x: 0.1,
+            offset_y: 0.2,
+            offset_z: 0.3,
+            particle_data: 0.0,
+            count: 8,
+            data,
+        });
+    }
+
+    let particle = |id, data| Particle {
+        id,
+        long_distance: true,
+        x: 1.0,
+        y: 2.0,
+        z: -3.0,
+        offset_x: 0.5,
+        offset_y: 0.25,
+        offset_z: 0.0,
+        particle_data: 0.0,
+        count: 8,
+        data,
+    };
+    let header = |id: u8| {
+        vec![
+            0x24, 0x00, 0x00, 0x00, id, 0x01, // id, long distance
+            0x3F, 0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // x
+            0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // y
+            0xC0, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // z
+            0x3F, 0x00, 0x00, 0x00, 0x3E, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // offset
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, // particle data, count
+        ]
+    };
+    known_bytes(particle(0, ParticleData::None), &header(0));
+    known_bytes(
+        particle(4, ParticleData::Block(VarInt(300))),
+        &[header(4), vec![0xAC, 0x02]].concat(),
+    );
+    known_bytes(
+        particle(
+            15,
+            ParticleData::Dust {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.5,
+                scale: 2.0,
+            },
+        ),
+        &[
+            header(15),
+            vec![
+                0x3F, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x40, 0x00,
+                0x00, 0x00,
+            ],
+        ]
+        .concat(),
+    );
+}
+
+#[test]
+fn update_light() {
+    round_trip(UpdateLight {
+        x: VarInt(0),
+        z: VarInt(-1),
+        trust_edges: true,
+        sky_light_mask: BitSet::from_longs(vec![0b10]),
+        block_light_mask: BitSet::new(),
+        empty_sky_light_mask: BitSet::from_longs(vec![0b01]),
+        empty_block_light_mask: BitSet::from_longs(vec![0x3FFFF]),
+        sky_light: Array::new(vec![Array::new(vec![0xFF; 2048])]),
+        block_light: Array::new(vec![]),
+    });
+}
+
+#[test]
+fn join_game() {
+    round_trip(JoinGame {
+        eid: 1,
+        hardcore: false,
+        gamemode: 1,
+        prev_gamemode: -1,
+        worlds: Array::new(vec![ident("minecraft:overworld")]),
+        dim_codec: blob(),
+        dim: blob(),
+        world_name: ident("minecraft:overworld"),
+        hashed_seed: 0x1234_5678,
+        max_players: VarInt(20),
+        view_distance: VarInt(10),
+        reduce_debug: false,
+        respawn_screen: true,
+        is_debug: false,
+        is_flat: true,
+    });
+}
+
+#[test]
+fn map_data() {
+    round_trip(MapData {
+        id: VarInt(0),
+        scale: 0,
+        locked: false,
+        icons: None,
+        patch: None,
+    });
+    round_trip(MapData {
+        id: VarInt(1),
+        scale: 4,
+        locked: true,
+        icons: Some(Array::new(vec![
+            MapIcon {
+                kind: VarInt(0),
+                x: -128,
+                z: 127,
+                direction: 15,
+                display_name: None,
+            },
+            MapIcon {
+                kind: VarInt(9),
+                x: 0,
+                z: 0,
+                direction: 0,
+                display_name: Some(chat("Home")),
+            },
+        ])),
+        patch: Some(MapPatch {
+            columns: 2,
+            rows: 2,
+            x: 10,
+            z: 20,
+            data: Array::new(vec![4, 5, 6, 7]),
+        }),
+    });
+
+    known_bytes(
+        MapData {
+            id: VarInt(0),
+            scale: 0,
+            locked: false,
+            icons: None,
+            patch: None,
+        },
+        &[0x27, 0x00, 0x00, 0x00, 0x00, 0x00],
+    );
+    known_bytes(
+        MapData {
+            id: VarInt(1),
+            scale: 4,
+            locked: true,
+            icons: Some(Array::new(vec![MapIcon {
+                kind: VarInt(0),
+                x: -128,
+                z: 127,
+                direction: 15,
+                display_name: None,
+            }])),
+            patch: Some(MapPatch {
+                columns: 2,
+                rows: 2,
+                x: 10,
+                z: 20,
+                data: Array::new(vec![4, 5, 6, 7]),
+            }),
+        },
+        &[
+            0x27, 0x01, 0x04, 0x01, // id, scale, locked
+            0x01, 0x01, 0x00, 0x80, 0x7F, 0x0F, 0x00, // one icon
+            0x02, 0x02, 0x0A, 0x14, 0x04, 0x04, 0x05, 0x06, 0x07, // patch
+        ],
+    );
+}
+
+#[test]
+fn trade_list() {
+    round_trip(TradeList {
+        window: VarInt(3),
+        trades: Array::new(vec![
+            Trade {
+                input: item(),
+                output: enchanted_item(),
+                second_input: None,
+                disabled: false,
+                uses: 0,
+                max_uses: 12,
+                experience: 1,
+                special_price: 0,
+                price_multiplier: 0.05,
+                demand: 0,
+            },
+            Trade {
+                input: item(),
+                output: item(),
+                second_input: Some(item()),
+                disabled: true,
+                uses: 12,
+                max_uses: 12,
+                experience: 5,
+                special_price: -3,
+                price_multiplier: 0.2,
+                demand: 4,
+            },
+        ]),
+        villager_level: VarInt(2),
+        experience: VarInt(10),
+        regular_villager: true,
+        can_restock: true,
+    });
+}
+
+#[test]
+fn entity_position() {
+    round_trip(EntityPosition {
+        eid: VarInt(4),
+        delta_x: 4096,
+        delta_y: -4096,
+        delta_z: 0,
+        on_ground: true,
+    });
+}
+
+#[test]
+fn entity_position_and_rotation() {
+    round_trip(EntityPositionAndRotation {
+        eid: VarInt(4),
+        delta_x: 1,
+        delta_y: 2,
+        delta_z: 3,
+        yaw: 4,
+        pitch: 5,
+        on_ground: false,
+    });
+}
+
+#[test]
+fn entity_rotation() {
+    round_trip(EntityRotation {
+        eid: VarInt(4),
+        yaw: 200,
+        pitch: 100,
+        on_ground: true,
+    });
+}
+
+#[test]
+fn vehicle_move() {
+    round_trip(VehicleMove {
+        x: 1.0,
+        y: 2.0,
+        z: 3.0,
+        yaw: 90.0,
+        pitch: -45.0,
+    });
+}
+
+#[test]
+fn open_book() {
+    round_trip(OpenBook { hand: VarInt(1) });
+}
+
+#[test]
+fn open_window() {
+    round_trip(OpenWindow {
+        window: VarInt(2),
+        kind: VarInt(2),
+        title: chat("Chest"),
+    });
+}
+
+#[test]
+fn open_sign_editor() {
+    round_trip(OpenSignEditor {
+        location: Position::new(12, 64, -12),
+    });
+}
+
+#[test]
+fn ping() {
+    round_trip(Ping(99));
+}
+
+#[test]
+fn craft_recipe_response() {
+    round_trip(CraftRecipeResponse {
+        window: 0,
+        recipe: ident("minecraft:oak_planks"),
+    });
+}
+
+#[test]
+fn player_abilities() {
+    round_trip(PlayerAbilities {
+        flags: 0x0D,
+        flying_speed: 0.05,
+        fov_modifier: 0.1,
+    });
+}
+
+#[test]
+fn end_combat_event() {
+    round_trip(EndCombatEvent {
+        duration: VarInt(100),
+        eid: -1,
+    });
+}
+
+#[test]
+fn enter_combat_event() {
+    round_trip(EnterCombatEvent);
+}
+
+#[test]
+fn death_combat_event() {
+    round_trip(DeathCombatEvent {
+        player: VarInt(1),
+        eid: 7,
+        message: chat("fell out of the world"),
+    });
+}
+
+#[test]
+fn player_info() {
+    let actions = vec![
+        PlayerInfoAction::AddPlayer(vec![(
+            UUID,
+            NewPlayer {
+                name: string("Notch"),
+                properties: Array::new(vec![Property {
+                    name: string("textures"),
+                    value: string("e30="),
+                    signature: Some(string("c2lnbmF0dXJl")),
+                }]),
+                gamemode: VarInt(0),
+                ping: VarInt(35),
+                display_name: Some(chat("The Notch")),
+            },
+        )]),
+        PlayerInfoAction::UpdateGamemode(vec![(UUID, VarInt(3))]),
+        PlayerInfoAction::UpdateLatency(vec![(UUID, VarInt(120)), (Uuid::nil(), VarInt(0))]),
+        PlayerInfoAction::UpdateDisplayName(vec![(UUID, None)]),
+        PlayerInfoAction::RemovePlayer(vec![UUID]),
+    ];
+    for action in actions {
+        round_trip(PlayerInfo(action));
+    }
+
+    known_bytes(
+        PlayerInfo(PlayerInfoAction::AddPlayer(vec![(
+            UUID,
+            NewPlayer {
+                name: string("Notch"),
+                properties: Array::new(vec![Property {
+                    name: string("textures"),
+                    value: string("e30="),
+                    signature: Some(string("c2ln")),
+                }]),
+                gamemode: VarInt(1),
+                ping: VarInt(35),
+                display_name: None,
+            },
+        )])),
+        &[
+            &[0x36, 0x00, 0x01][..],
+            &UUID_BYTES,
+            b"\x05Notch\x01\x08textures\x04e30=\x01\x04c2ln",
+            &[0x01, 0x23, 0x00],
+        ]
+        .concat(),
+    );
+    known_bytes(
+        PlayerInfo(PlayerInfoAction::UpdateLatency(vec![
+            (UUID, VarInt(120)),
+            (Uuid::nil(), VarInt(300)),
+        ])),
+        &[
+            &[0x36, 0x02, 0x02][..],
+            &UUID_BYTES,
+            &[0x78],
+            &[0x00; 16],
+            &[0xAC, 0x02],
+        ]
+        .concat(),
+    );
+    known_bytes(
+        PlayerInfo(PlayerInfoAction::RemovePlayer(vec![UUID])),
+        &[&[0x36, 0x04, 0x01][..], &UUID_BYTES].concat(),
+    );
+}
+
+#[test]
+fn face_player() {
+    round_trip(FacePlayer {
+        anchor: VarInt(1),
+        x: 1.0,
+        y: 2.0,
+        z: 3.0,
+        entity: None,
+    });
+    round_trip(FacePlayer {
+        anchor: VarInt(0),
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        entity: Some((VarInt(12), VarInt(1))),
+    });
+}
+
+#[test]
+fn player_position_and_look() {
+    round_trip(PlayerPositionAndLook {
+        x: 8.5,
+        y: 65.0,
+        z: 8.5,
+        yaw: 0.0,
+        pitch: 0.0,
+        flags: 0,
+        teleport_id: VarInt(1),
+        dismount: false,
+    });
+}
+
+#[test]
+fn unlock_recipes() {
+    let book = || RecipeBookState {
+        open: false,
+        filter_active: true,
+    };
+    round_trip(UnlockRecipes {
+        action: VarInt(0),
+        crafting: book(),
+        smelting: book(),
+        blast_furnace: book(),
+        smoker: book(),
+        recipes: Array::new(vec![ident("minecraft:crafting_table")]),
+        init: Some(Array::new(vec![
+            ident("minecraft:crafting_table"),
+            ident("minecraft:stick"),
+        ])),
+    });
+    round_trip(UnlockRecipes {
+        action: VarInt(1),
+        crafting: book(),
+        smelting: book(),
+        blast_furnace: book(),
+        smoker: book(),
+        recipes: Array::new(vec![ident("minecraft:torch")]),
+        init: None,
+    });
+
+    known_bytes(
+        UnlockRecipes {
+            action: VarInt(0),
+            crafting: RecipeBookState {
+                open: true,
+                filter_active: false,
+            },
+            smelting: book(),
+            blast_furnace: book(),
+            smoker: book(),
+            recipes: Array::new(vec![ident("minecraft:crafting_table")]),
+            init: Some(Array::new(vec![ident("minecraft:stick")])),
+        },
+        &[
+            &[0x39, 0x00, 0x01, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01][..],
+            b"\x01\x18minecraft:crafting_table",
+            b"\x01\x0Fminecraft:stick",
+        ]
+        .concat(),
+    );
+    // only initializing sends the second list
+    known_bytes(
+        UnlockRecipes {
+            action: VarInt(2),
+            crafting: book(),
+            smelting: book(),
+            blast_furnace: book(),
+            smoker: book(),
+            recipes: Array::new(vec![ident("minecraft:torch")]),
+            init: None,
+        },
+        &[
+            &[0x39, 0x02, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01][..],
+            b"\x01\x0Fminecraft:torch",
+        ]
+        .concat(),
+    );
+}
+
+#[test]
+fn destroy_entities() {
+    round_trip(DestroyEntities(varints(&[1, 2, 300])));
+}
+
+#[test]
+fn remove_entity_effect() {
+    round_trip(RemoveEntityEffect {
+        eid: VarInt(1),
+        effect: 14,
+    });
+}
+
+#[test]
+fn resource_pack_send() {
+    round_trip(ResourcePackSend {
+        url: string("https://example.com/pack.zip"),
+        hash: string("0123456789abcdef0123456789abcdef01234567"),
+        forced: true,
+        prompt: Some(chat("Please accept")),
+    });
+}
+
+#[test]
+fn respawn() {
+    round_trip(Respawn {
+        dim: blob(),
+        world_name: ident("minecraft:the_nether"),
+        hashed_seed: -1,
+        gamemode: 0,
+        prev_gamemode: 1,
+        is_debug: false,
+        is_flat: false,
+        copy_metadata: true,
+    });
+}
+
+#[test]
+fn entity_head_look() {
+    round_trip(EntityHeadLook {
+        eid: VarInt(6),
+        head_yaw: 64,
+    });
+}
+
+#[test]
+fn multi_block_change() {
+    round_trip(MultiBlockChange {
+        section: (5 << 42) | (-3i64 & 0x3F_FFFF) << 20 | 4,
+        trust_edges: false,
+        blocks: Array::new(vec![VarLong(1 << 12), VarLong(9 << 12 | 0x123)]),
+    });
+}
+
+#[test]
+fn select_advancement_tab() {
+    round_trip(SelectAdvancementTab(Some(ident("minecraft:story/root"))));
+    round_trip(SelectAdvancementTab(None));
+}
+
+#[test]
+fn action_bar() {
+    round_trip(ActionBar(chat("You may not rest now")));
+}
+
+#[test]
+fn world_border_center() {
+    round_trip(WorldBorderCenter { x: 100.0, z: -50.0 });
+}
+
+#[test]
+fn world_border_lerp_size() {
+    round_trip(WorldBorderLerpSize {
+        old_diameter: 100.0,
+        new_diameter: 50.0,
+        speed: VarLong(60_000),
+    });
+}
+
+#[test]
+fn world_border_size() {
+    round_trip(WorldBorderSize(1000.0));
+}
+
+#[test]
+fn world_border_warning_delay() {
+    round_trip(WorldBorderWarningDelay(VarInt(15)));
+}
+
+#[test]
+fn world_border_warning_reach() {
+    round_trip(WorldBorderWarningReach(VarInt(5)));
+}
+
+#[test]
+fn camera() {
+    round_trip(Camera(VarInt(1)));
+}
+
+#[test]
+fn held_item_change() {
+    round_trip(HeldItemChange(8));
+}
+
+#[test]
+fn update_view_position() {
+    round_trip(UpdateViewPosition {
+        x: VarInt(-2),
+        z: VarInt(2),
+    });
+}
+
+#[test]
+fn update_view_distance() {
+    round_trip(UpdateViewDistance(VarInt(12)));
+}
+
+#[test]
+fn spawn_position() {
+    round_trip(SpawnPosition {
+        location: Position::new(0, 64, 0),
+        angle: 0.0,
+    });
+}
+
+#[test]
+fn display_scoreboard() {
+    round_trip(DisplayScoreboard {
+        position: 1,
+        name: string("kills"),
+    });
+}
+
+#[test]
+fn entity_metadata() {
+    round_trip(EntityMetadata {
+        eid: VarInt(1),
+        metadata: Metadata(vec![
+            (0, MetadataValue::Byte(0x02)),
+            (1, MetadataValue::VarInt(VarInt(300))),
+            (2, MetadataValue::Float(20.0)),
+            (3, MetadataValue::String(string("name"))),
+            (4, MetadataValue::Chat(chat("name"))),
+            (5, MetadataValue::OptChat(None)),
+            (6, MetadataValue::Slot(enchanted_item())),
+            (7, MetadataValue::Boolean(true)),
+            (8, MetadataValue::Rotation(1.0, 2.0, 3.0)),
+            (9, MetadataValue::Position(Position::new(1, 2, 3))),
+            (
+                10,
+                MetadataValue::OptPosition(Some(Position::new(-1, -2, -3))),
+            ),
+            (11, MetadataValue::Direction(VarInt(5))),
+            (12, MetadataValue::OptUuid(Some(UUID))),
+            (13, MetadataValue::OptBlockState(VarInt(0))),
+            (14, MetadataValue::Nbt(OptionalNbt(Some(blob())))),
+            (
+                15,
+                MetadataValue::Particle(ParticleType {
+                    id: VarInt(4),
+                    data: ParticleData::Block(VarInt(10)),
+                }),
+            ),
+            (
+                16,
+                MetadataValue::VillagerData {
+                    kind: VarInt(2),
+                    profession: VarInt(5),
+                    level: VarInt(1),
+                },
+            ),
+            (17, MetadataValue::OptVarInt(Some(VarInt(0)))),
+            (18, MetadataValue::OptVarInt(None)),
+            (19, MetadataValue::Pose(VarInt(1))),
+        ]),
+    });
+}
+
+#[test]
+fn attach_entity() {
+    round_trip(AttachEntity {
+        attached: 5,
+        holding: -1,
+    });
+}
+
+#[test]
+fn entity_velocity() {
+    round_trip(EntityVelocity {
+        eid: VarInt(3),
+        velocity_x: 0,
+        velocity_y: -3920,
+        velocity_z: 0,
+    });
+}
+
+#[test]
+fn entity_equipment() {
+    round_trip(EntityEquipment {
+        eid: VarInt(3),
+        equipment: Equipment(vec![(0, item())]),
+    });
+    round_trip(EntityEquipment {
+        eid: VarInt(3),
+        equipment: Equipment(vec![(0, item()), (1, None), (5, enchanted_item())]),
+    });
+    // the top bit of every slot but the last one is set
+    known_bytes(
+        EntityEquipment {
+            eid: VarInt(3),
+            equipment: Equipment(vec![(0, item()), (5, None)]),
+        },
+        &[0x50, 0x03, 0x80, 0x01, 0x01, 0x40, 0x00, 0x05, 0x00],
+    );
+}
+
+#[test]
+fn set_experience() {
+    round_trip(SetExperience {
+        bar: 0.75,
+        level: VarInt(30),
+        total: VarInt(1395),
+    });
+}
+
+#[test]
+fn update_health() {
+    round_trip(UpdateHealth {
+        health: 20.0,
+        food: VarInt(20),
+        saturation: 5.0,
+    });
+}
+
+#[test]
+fn scoreboard_objective() {
+    let actions = vec![
+        ObjectiveAction::Create {
+            value: chat("Kills"),
+            kind: VarInt(0),
+        },
+        ObjectiveAction::Remove,
+        ObjectiveAction::Update {
+            value: chat("Health"),
+            kind: VarInt(1),
+        },
+    ];
+    for action in actions {
+        round_trip(ScoreboardObjective {
+            name: string("obj"),
+            action,
+        });
+    }
+
+    known_bytes(
+        ScoreboardObjective {
+            name: string("obj"),
+            action: ObjectiveAction::Create {
+                value: chat("Kills"),
+                kind: VarInt(1),
+            },
+        },
+        &[&b"\x53\x03obj\x00\x10"[..], br#"{"text":"Kills"}"#, &[0x01]].concat(),
+    );
+    known_bytes(
+        ScoreboardObjective {
+            name: string("obj"),
+            action: ObjectiveAction::Remove,
+        },
+        b"\x53\x03obj\x01",
+    );
+}
+
+#[test]
+fn set_passengers() {
+    round_trip(SetPassengers {
+        eid: VarInt(10),
+        passengers: varints(&[11, 12]),
+    });
+}
+
+#[test]
+fn teams() {
+    let info = || TeamInfo {
+        display_name: chat("Red"),
+        friendly_flags: 0x01,
+        name_tag_visibility: string("always"),
+        collision_rule: string("pushOwnTeam"),
+        color: VarInt(12),
+        prefix: chat("[R] "),
+        suffix: chat(""),
+    };
+    let entities = || Array::new(vec![string("Notch"), string("jeb_")]);
+    let actions = vec![
+        TeamAction::Create(info(), entities()),
+        TeamAction::Remove,
+        TeamAction::Update(info()),
+        TeamAction::AddEntities(entities()),
+        TeamAction::RemoveEntities(entities()),
+    ];
+    for action in actions {
+        round_trip(Teams {
+            name: string("red"),
+            action,
+        });
+    }
+    known_bytes(
+        Teams {
+            name: string("red"),
+            action: TeamAction::Remove,
+        },
+        b"\x55\x03red\x01",
+    );
+    known_bytes(
+        Teams {
+            name: string("red"),
+            action: TeamAction::AddEntities(entities()),
+        },
+        b"\x55\x03red\x03\x02\x05Notch\x04jeb_",
+    );
+}
+
+#[test]
+fn update_score() {
+    round_trip(UpdateScore {
+        entity: string("Notch"),
+        objective: string("kills"),
+        value: Some(VarInt(3)),
+    });
+    round_trip(UpdateScore {
+        entity: string("Notch"),
+        objective: string("kills"),
+        value: None,
+    });
+    known_bytes(
+        UpdateScore {
+            entity: string("Notch"),
+            objective: string("kills"),
+            value: Some(VarInt(3)),
+        },
+        b"\x56\x05Notch\x00\x05kills\x03",
+    );
+    known_bytes(
+        UpdateScore {
+            entity: string("Notch"),
+            objective: string("kills"),
+            value: None,
+        },
+        b"\x56\x05Notch\x01\x05kills",
+    );
+}
+
+#[test]
+fn set_title_subtitle() {
+    round_trip(SetTitleSubtitle(chat("subtitle")));
+}
+
+#[test]
+fn time_update() {
+    round_trip(TimeUpdate {
+        world_age: 24000,
+        time_of_day: -6000,
+    });
+}
+
+#[test]
+fn set_title_text() {
+    round_trip(SetTitleText(chat("title")));
+}
+
+#[test]
+fn set_title_times() {
+    round_trip(SetTitleTimes {
+        fade_in: 10,
+        stay: 70,
+        fade_out: 20,
+    });
+}
+
+#[test]
+fn entity_sound_effect() {
+    round_trip(EntitySoundEffect {
+        sound: VarInt(5),
+        category: VarInt(5),
+        eid: VarInt(42),
+        volume: 1.0,
+        pitch: 1.0,
+    });
+}
+
+#[test]
+fn sound_effect() {
+    round_trip(SoundEffect {
+        sound: VarInt(300),
+        category: VarInt(0),
+        x: -8,
+        y: 512,
+        z: 8,
+        volume: 0.5,
+        pitch: 2.0,
+    });
+}
+
+#[test]
+fn stop_sound() {
+    for (category, sound) in [
+        (None, None),
+        (Some(VarInt(2)), None),
+        (None, Some(ident("minecraft:music.game"))),
+        (Some(VarInt(1)), Some(ident("minecraft:music.game"))),
+    ] {
+        round_trip(StopSound { category, sound });
+    }
+    for (category, sound, bytes) in [
+        (None, None, &b"\x5D\x00"[..]),
+        (Some(VarInt(2)), None, b"\x5D\x01\x02"),
+        (
+            None,
+            Some(ident("minecraft:music.game")),
+            b"\x5D\x02\x14minecraft:music.game",
+        ),
+        (
+            Some(VarInt(2)),
+            Some(ident("minecraft:music.game")),
+            b"\x5D\x03\x02\x14minecraft:music.game",
+        ),
+    ] {
+        known_bytes(StopSound { category, sound }, bytes);
+    }
+}
+
+#[test]
+fn player_list_header_and_footer() {
+    round_trip(PlayerListHeaderAndFooter {
+        header: chat("header"),
+        footer: chat("footer"),
+    });
+}
+
+#[test]
+fn nbt_query_response() {
+    round_trip(NbtQueryResponse {
+        transaction: VarInt(1),
+        nbt: OptionalNbt(Some(blob())),
+    });
+    round_trip(NbtQueryResponse {
+        transaction: VarInt(2),
+        nbt: OptionalNbt(None),
+    });
+}
+
+#[test]
+fn collect_item() {
+    round_trip(CollectItem {
+        collected: VarInt(20),
+        collector: VarInt(1),
+        count: VarInt(3),
+    });
+}
+
+#[test]
+fn entity_teleport() {
+    round_trip(EntityTeleport {
+        eid: VarInt(20),
+        x: -1.0,
+        y: 100.0,
+        z: 1.0,
+        yaw: 0,
+        pitch: 0,
+        on_ground: false,
+    });
+}
+
+#[test]
+fn advancements() {
+    round_trip(Advancements {
+        reset: true,
+        advancements: Array::new(vec![
+            (
+                ident("minecraft:story/root"),
+                Advancement {
+                    parent: None,
+                    display: Some(AdvancementDisplay {
+                        title: chat("Minecraft"),
+                        description: chat("The heart and story of the game"),
+                        icon: item(),
+                        frame: VarInt(0),
+                        flags: 0x02,
+                        background: Some(ident(
+                            "minecraft:textures/gui/advancements/backgrounds/stone.png",
+                        )),
+                        x: 0.0,
+                        y: 0.0,
+                    }),
+                    criteria: Array::new(vec![ident("crafting_table")]),
+                    requirements: Array::new(vec![Array::new(vec![string("crafting_table")])]),
+                },
+            ),
+            (
+                ident("minecraft:story/mine_stone"),
+                Advancement {
+                    parent: Some(ident("minecraft:story/root")),
+                    display: Some(AdvancementDisplay {
+                        title: chat("Stone Age"),
+                        description: chat("Mine stone"),
+                        icon: None,
+                        frame: VarInt(0),
+                        flags: 0x06,
+                        background: None,
+                        x: 1.0,
+                        y: 0.0,
+                    }),
+                    criteria: Array::new(vec![]),
+                    requirements: Array::new(vec![]),
+                },
+            ),
+        ]),
+        removed: Array::new(vec![ident("minecraft:story/old")]),
+        progress: Array::new(vec![(
+            ident("minecraft:story/root"),
+            AdvancementProgress(Array::new(vec![
+                (ident("crafting_table"), Some(1_600_000_000_000)),
+                (ident("other"), None),
+            ])),
+        )]),
+    });
+}
+
+#[test]
+fn entity_properties() {
+    round_trip(EntityProperties {
+        eid: VarInt(1),
+        properties: Array::new(vec![EntityProperty {
+            key: ident("minecraft:generic.movement_speed"),
+            value: 0.1,
+            modifiers: Array::new(vec![Modifier {
+                uuid: UUID,
+                amount: 0.3,
+                operation: 2,
+            }]),
+        }]),
+    });
+}
+
+#[test]
+fn entity_effect() {
+    round_trip(EntityEffect {
+        eid: VarInt(1),
+        effect: 1,
+        amplifier: 1,
+        duration: VarInt(600),
+        flags: 0x04,
+    });
+}
+
+#[test]
+fn declare_recipes() {
+    let ingredient = || Array::new(vec![item(), None]);
+    round_trip(DeclareRecipes(Array::new(vec![
+        Recipe {
+            kind: ident("minecraft:crafting_shapeless"),
+            id: ident("minecraft:oak_planks"),
+            data: RecipeData::Shapeless {
+                group: string("planks"),
+                ingredients: Array::new(vec![ingredient()]),
+                result: item(),
+            },
+        },
+        Recipe {
+            kind: ident("minecraft:crafting_shaped"),
+            id: ident("minecraft:stick"),
+            data: RecipeData::Shaped {
+                width: VarInt(1),
+                height: VarInt(2),
+                group: string("sticks"),
+                ingredients: vec![ingredient(), ingredient()],
+                result: item(),
+            },
+        },
+        Recipe {
+            kind: ident("minecraft:smelting"),
+            id: ident("minecraft:glass"),
+            data: RecipeData::Cooking {
+                group: string(""),
+                ingredient: ingredient(),
+                result: item(),
+                experience: 0.1,
+                cooking_time: VarInt(200),
+            },
+        },
+        Recipe {
+            kind: ident("minecraft:stonecutting"),
+            id: ident("minecraft:stone_slab"),
+            data: RecipeData::Stonecutting {
+                group: string(""),
+                ingredient: ingredient(),
+                result: item(),
+            },
+        },
+        Recipe {
+            kind: ident("minecraft:smithing"),
+            id: ident("minecraft:netherite_sword"),
+            data: RecipeData::Smithing {
+                base: ingredient(),
+                addition: ingredient(),
+                result: enchanted_item(),
+            },
+        },
+        Recipe {
+            kind: ident("minecraft:crafting_special_mapcloning"),
+            id: ident("minecraft:map_cloning"),
+            data: RecipeData::Special,
+        },
+    ])));
+}
+
+#[test]
+fn tags() {
+    round_trip(Tags(Array::new(vec![
+        (
+            ident("minecraft:block"),
+            Array::new(vec![
+                Tag {
+                    name: ident("minecraft:climbable"),
+                    entries: varints(&[160, 379]),
+                },
+                Tag {
+                    name: ident("minecraft:empty"),
+                    entries: varints(&[]),
+                },
+            ]),
+        ),
+        (ident("minecraft:fluid"), Array::new(vec![])),
+    ])));
+}
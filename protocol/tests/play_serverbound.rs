@@ -0,0 +1,459 @@
+mod common;
+
+use bytes::Bytes;
+use common::*;
+use protocol::codec::Transcodeable;
+use protocol::packets::play::serverbound::*;
+use protocol::types::{Array, Position, Remaining, VarInt, VarLong};
+use uuid::Uuid;
+
+#[test]
+fn teleport_confirm() {
+    round_trip(TeleportConfirm(VarInt(1)));
+}
+
+#[test]
+fn query_block_nbt() {
+    round_trip(QueryBlockNbt {
+        transaction: VarInt(5),
+        location: Position::new(-10, 20, -30),
+    });
+}
+
+#[test]
+fn set_difficulty() {
+    round_trip(SetDifficulty(3));
+}
+
+#[test]
+fn chat_message() {
+    round_trip(ChatMessage(string("/help")));
+}
+
+#[test]
+fn client_status() {
+    round_trip(ClientStatus { action: VarInt(0) });
+}
+
+#[test]
+fn client_settings() {
+    round_trip(ClientSettings {
+        locale: string("en_us"),
+        view_distance: 12,
+        chat_mode: VarInt(0),
+        chat_colors: true,
+        skin_parts: 0x7F,
+        main_hand: VarInt(1),
+        disable_text_filtering: false,
+    });
+}
+
+#[test]
+fn tab_complete() {
+    round_trip(TabComplete {
+        transaction: VarInt(7),
+        text: string("/gam"),
+    });
+}
+
+#[test]
+fn click_window_button() {
+    round_trip(ClickWindowButton {
+        window: 3,
+        button: 1,
+    });
+}
+
+#[test]
+fn click_window() {
+    round_trip(ClickWindow {
+        window: 1,
+        state: VarInt(4),
+        slot: 36,
+        button: 0,
+        mode: VarInt(0),
+        changed: Array::new(vec![(36, None), (0, item())]),
+        carried: enchanted_item(),
+    });
+    // claims i32::MAX changed slots without sending any, nothing gets reserved for them
+    let body = [1, 4, 0, 36, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0x07];
+    assert!(ClickWindow::decode(&body[..]).is_err());
+}
+
+#[test]
+fn close_window() {
+    round_trip(CloseWindow(0));
+}
+
+#[test]
+fn plugin_message() {
+    round_trip(PluginMessage {
+        channel: ident("minecraft:brand"),
+        data: Remaining(Bytes::from_static(b"\x07vanilla")),
+    });
+    round_trip(PluginMessage {
+        channel: ident("mod:empty"),
+        data: Remaining(Bytes::new()),
+    });
+    // long enough for a two byte frame length
+    let data = (0..200).map(|i| i as u8).collect::<Vec<_>>();
+    known_bytes(
+        PluginMessage {
+            channel: ident("mod:long"),
+            data: Remaining(Bytes::from(data.clone())),
+        },
+        &[&b"\x0A\x08mod:long"[..], &data].concat(),
+    );
+}
+
+#[test]
+fn edit_book() {
+    round_trip(EditBook {
+        hand: VarInt(0),
+        pages: Array::new(vec![string("first page"), string("")]),
+        title: Some(string("Diary")),
+    });
+    round_trip(EditBook {
+        hand: VarInt(1),
+        pages: Array::new(vec![]),
+        title: None,
+    });
+}
+
+#[test]
+fn query_entity_nbt() {
+    round_trip(QueryEntityNbt {
+        transaction: VarInt(2),
+        eid: VarInt(99),
+    });
+}
+
+#[test]
+fn interact_entity() {
+    let actions = vec![
+        InteractAction::Interact { hand: VarInt(0) },
+        InteractAction::Attack,
+        InteractAction::InteractAt {
+            x: 0.1,
+            y: 1.2,
+            z: -0.3,
+            hand: VarInt(1),
+        },
+    ];
+    for action in actions {
+        round_trip(InteractEntity {
+            eid: VarInt(12),
+            action,
+            sneaking: true,
+        });
+    }
+    known_bytes(
+        InteractEntity {
+            eid: VarInt(12),
+            action: InteractAction::Attack,
+            sneaking: false,
+        },
+        &[0x0D, 0x0C, 0x01, 0x00],
+    );
+    known_bytes(
+        InteractEntity {
+            eid: VarInt(300),
+            action: InteractAction::InteractAt {
+                x: 0.5,
+                y: 1.0,
+                z: -2.0,
+                hand: VarInt(1),
+            },
+            sneaking: true,
+        },
+        &[
+            0x0D, 0xAC, 0x02, 0x02, // eid, type
+            0x3F, 0x00, 0x00, 0x00, 0x3F, 0x80, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, // target
+            0x01, 0x01, // hand, sneaking
+        ],
+    );
+}
+
+#[test]
+fn generate_structure() {
+    round_trip(GenerateStructure {
+        location: Position::new(0, 60, 0),
+        levels: VarInt(7),
+        keep_jigsaws: false,
+    });
+}
+
+#[test]
+fn keep_alive() {
+    round_trip(KeepAlive(i64::MIN));
+}
+
+#[test]
+fn lock_difficulty() {
+    round_trip(LockDifficulty(true));
+}
+
+#[test]
+fn player_position() {
+    round_trip(PlayerPosition {
+        x: 0.5,
+        y: 64.0,
+        z: -0.5,
+        on_ground: true,
+    });
+}
+
+#[test]
+fn player_position_and_rotation() {
+    round_trip(PlayerPositionAndRotation {
+        x: 1.0,
+        y: 2.0,
+        z: 3.0,
+        yaw: 359.9,
+        pitch: -90.0,
+        on_ground: false,
+    });
+}
+
+#[test]
+fn player_rotation() {
+    round_trip(PlayerRotation {
+        yaw: 10.0,
+        pitch: 20.0,
+        on_ground: true,
+    });
+}
+
+#[test]
+fn player_movement() {
+    round_trip(PlayerMovement { on_ground: false });
+}
+
+#[test]
+fn vehicle_move() {
+    round_trip(VehicleMove {
+        x: 100.0,
+        y: 62.5,
+        z: -100.0,
+        yaw: 45.0,
+        pitch: 0.0,
+    });
+}
+
+#[test]
+fn steer_boat() {
+    round_trip(SteerBoat {
+        left_paddle: true,
+        right_paddle: false,
+    });
+}
+
+#[test]
+fn pick_item() {
+    round_trip(PickItem(VarInt(40)));
+}
+
+#[test]
+fn craft_recipe_request() {
+    round_trip(CraftRecipeRequest {
+        window: 0,
+        recipe: ident("minecraft:crafting_table"),
+        make_all: true,
+    });
+}
+
+#[test]
+fn player_abilities() {
+    round_trip(PlayerAbilities { flags: 0x02 });
+}
+
+#[test]
+fn player_digging() {
+    round_trip(PlayerDigging {
+        status: VarInt(0),
+        location: Position::new(3, -60, 3),
+        face: 1,
+    });
+}
+
+#[test]
+fn entity_action() {
+    round_trip(EntityAction {
+        eid: VarInt(1),
+        action: VarInt(5),
+        jump_boost: VarInt(100),
+    });
+}
+
+#[test]
+fn steer_vehicle() {
+    round_trip(SteerVehicle {
+        sideways: -0.98,
+        forward: 0.98,
+        flags: 0x01,
+    });
+}
+
+#[test]
+fn pong() {
+    round_trip(Pong(-7));
+}
+
+#[test]
+fn set_recipe_book_state() {
+    round_trip(SetRecipeBookState {
+        book: VarInt(1),
+        open: true,
+        filter_active: false,
+    });
+}
+
+#[test]
+fn set_displayed_recipe() {
+    round_trip(SetDisplayedRecipe(ident("minecraft:furnace")));
+}
+
+#[test]
+fn name_item() {
+    round_trip(NameItem(string("Excalibur")));
+}
+
+#[test]
+fn resource_pack_status() {
+    round_trip(ResourcePackStatus(VarInt(3)));
+}
+
+#[test]
+fn advancement_tab() {
+    round_trip(AdvancementTab(AdvancementTabAction::Opened(ident(
+        "minecraft:story/root",
+    ))));
+    round_trip(AdvancementTab(AdvancementTabAction::Closed));
+    known_bytes(
+        AdvancementTab(AdvancementTabAction::Opened(ident("minecraft:story/root"))),
+        b"\x22\x00\x14minecraft:story/root",
+    );
+    known_bytes(AdvancementTab(AdvancementTabAction::Closed), &[0x22, 0x01]);
+}
+
+#[test]
+fn select_trade() {
+    round_trip(SelectTrade(VarInt(2)));
+}
+
+#[test]
+fn set_beacon_effect() {
+    round_trip(SetBeaconEffect {
+        primary: VarInt(1),
+        secondary: VarInt(10),
+    });
+}
+
+#[test]
+fn held_item_change() {
+    round_trip(HeldItemChange(4));
+}
+
+#[test]
+fn update_command_block() {
+    round_trip(UpdateCommandBlock {
+        location: Position::new(1, 2, 3),
+        command: string("say hi"),
+        mode: VarInt(2),
+        flags: 0x04,
+    });
+}
+
+#[test]
+fn update_command_block_minecart() {
+    round_trip(UpdateCommandBlockMinecart {
+        eid: VarInt(8),
+        command: string("time set day"),
+        track_output: true,
+    });
+}
+
+#[test]
+fn creative_inventory_action() {
+    round_trip(CreativeInventoryAction {
+        slot: 36,
+        item: enchanted_item(),
+    });
+    round_trip(CreativeInventoryAction {
+        slot: -1,
+        item: None,
+    });
+}
+
+#[test]
+fn update_jigsaw_block() {
+    round_trip(UpdateJigsawBlock {
+        location: Position::new(0, 0, 0),
+        name: ident("minecraft:bottom"),
+        target: ident("minecraft:top"),
+        pool: ident("minecraft:empty"),
+        final_state: string("minecraft:air"),
+        joint_type: string("rollable"),
+    });
+}
+
+#[test]
+fn update_structure_block() {
+    round_trip(UpdateStructureBlock {
+        location: Position::new(10, 64, 10),
+        action: VarInt(1),
+        mode: VarInt(0),
+        name: string("minecraft:house"),
+        offset: StructureVector { x: 0, y: 1, z: 0 },
+        size: StructureVector {
+            x: 32,
+            y: 16,
+            z: -32,
+        },
+        mirror: VarInt(0),
+        rotation: VarInt(3),
+        metadata: string(""),
+        integrity: 1.0,
+        seed: VarLong(-5),
+        flags: 0x02,
+    });
+}
+
+#[test]
+fn update_sign() {
+    round_trip(UpdateSign {
+        location: Position::new(7, 70, 7),
+        line_1: string("Welcome"),
+        line_2: string(""),
+        line_3: string("to the"),
+        line_4: string("server"),
+    });
+}
+
+#[test]
+fn animation() {
+    round_trip(Animation { hand: VarInt(0) });
+}
+
+#[test]
+fn spectate() {
+    round_trip(Spectate(Uuid::from_u128(42)));
+}
+
+#[test]
+fn player_block_placement() {
+    round_trip(PlayerBlockPlacement {
+        hand: VarInt(0),
+        location: Position::new(-1, 63, 5),
+        face: VarInt(1),
+        cursor_x: 0.5,
+        cursor_y: 1.0,
+        cursor_z: 0.25,
+        inside_block: false,
+    });
+}
+
+#[test]
+fn use_item() {
+    round_trip(UseItem { hand: VarInt(1) });
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use protocol::registry::{Registries, RegistryError, Tags};
+use std::path::{Path, PathBuf};
+
+fn data(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(path)
+}
+
+fn registries() -> Registries {
+    Registries::load(&data("reports")).unwrap()
+}
+
+/// A pack with a single block tag, in a directory of its own
+fn pack(name: &str, json: &str) -> PathBuf {
+    let pack = std::env::temp_dir().join(format!("mcsrs-tags-{}-{}", name, std::process::id()));
+    let dir = pack.join("data/minecraft/tags/blocks");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("{}.json", name)), json).unwrap();
+    pack
+}
+
+#[test]
+fn registry_ids() {
+    let registries = registries();
+    assert_eq!(registries.items.id("minecraft:oak_log"), Some(2));
+    assert_eq!(registries.items.get(1), Some("minecraft:stone"));
+    assert_eq!(registries.items.get(-1), None);
+    assert_eq!(registries.items.default_id(), Some(0));
+    assert_eq!(registries.fluids.id("minecraft:water"), Some(2));
+
+    let motives = registries.get("minecraft:motive").unwrap();
+    assert_eq!(motives.id("minecraft:aztec"), Some(1));
+    assert_eq!(
+        motives.iter().collect::<Vec<_>>(),
+        vec![(0, "minecraft:kebab"), (1, "minecraft:aztec")]
+    );
+    assert!(registries.get("minecraft:unknown").is_none());
+}
+
+#[test]
+fn block_states() {
+    let blocks = registries().blocks;
+    assert_eq!(blocks.states(), 11);
+    assert_eq!(blocks.default_state("minecraft:air"), Some(0));
+    assert_eq!(blocks.default_state("minecraft:oak_log"), Some(3));
+    assert_eq!(
+        blocks.state_id("minecraft:oak_log", &[("axis", "z")]),
+        Some(4)
+    );
+    assert_eq!(
+        blocks.state_id("minecraft:oak_slab", &[("type", "double")]),
+        Some(10)
+    );
+    assert_eq!(
+        blocks.state_id(
+            "minecraft:oak_slab",
+            &[("waterlogged", "true"), ("type", "top")]
+        ),
+        Some(5)
+    );
+    assert_eq!(blocks.state_id("minecraft:oak_log", &[("axis", "w")]), None);
+    assert_eq!(
+        blocks.state_id("minecraft:oak_log", &[("facing", "x")]),
+        None
+    );
+    assert_eq!(blocks.get("minecraft:oak_slab").unwrap().id, 3);
+}
+
+#[test]
+fn state_properties() {
+    let blocks = registries().blocks;
+    let state = blocks.state(7).unwrap();
+    assert_eq!(state.name(), "minecraft:oak_slab");
+    assert!(!state.is_default());
+    assert_eq!(
+        state.properties().collect::<Vec<_>>(),
+        vec![("type", "bottom"), ("waterlogged", "true")]
+    );
+    assert_eq!(state.get("waterlogged"), Some("true"));
+    assert_eq!(state.with("waterlogged", "false"), Some(8));
+
+    assert_eq!(blocks.state(1).unwrap().name(), "minecraft:stone");
+    assert!(blocks.state(11).is_none());
+    assert!(blocks.state(-1).is_none());
+}
+
+#[test]
+fn invalid_reports() {
+    let registries = std::fs::read_to_string(data("reports/registries.json")).unwrap();
+    let blocks = r#"{"minecraft:air": {"states": [{"id": 1, "default": true}]}}"#;
+    assert!(matches!(
+        Registries::from_json(blocks, &registries),
+        Err(RegistryError::Invalid(_))
+    ));
+    assert!(matches!(
+        Registries::from_json("{}", "{}"),
+        Err(RegistryError::Missing("minecraft:block"))
+    ));
+    assert!(matches!(
+        Registries::from_json("[]", &registries),
+        Err(RegistryError::Json("blocks.json", _))
+    ));
+}
+
+#[test]
+fn tags() {
+    let tags = Tags::load(&[data("pack")], &registries()).unwrap();
+    assert_eq!(
+        tags.get("minecraft:block", "minecraft:logs"),
+        Some(&[2][..])
+    );
+    // references are expanded, duplicates and missing optional entries are dropped
+    assert_eq!(
+        tags.get("minecraft:block", "minecraft:mineable/axe"),
+        Some(&[2, 3][..])
+    );
+    assert_eq!(tags.get("minecraft:item", "minecraft:logs"), Some(&[2][..]));
+    assert_eq!(
+        tags.get("minecraft:fluid", "minecraft:water"),
+        Some(&[2, 1][..])
+    );
+    assert!(tags.contains("minecraft:block", "minecraft:slabs", 3));
+    assert!(!tags.contains("minecraft:block", "minecraft:slabs", 2));
+    assert!(tags
+        .get("minecraft:entity_type", "minecraft:logs")
+        .is_none());
+    assert_eq!(tags.len(), 5);
+}
+
+#[test]
+fn later_packs() {
+    let tags = Tags::load(&[data("pack"), data("extra")], &registries()).unwrap();
+    assert_eq!(
+        tags.get("minecraft:block", "minecraft:logs"),
+        Some(&[2, 1][..])
+    );
+    assert_eq!(
+        tags.get("minecraft:block", "minecraft:mineable/axe"),
+        Some(&[2, 1, 3][..])
+    );
+    assert_eq!(
+        tags.get("minecraft:block", "minecraft:slabs"),
+        Some(&[][..])
+    );
+    assert_eq!(
+        tags.get("minecraft:block", "custom:hard"),
+        Some(&[2, 1][..])
+    );
+}
+
+#[test]
+fn invalid_tags() {
+    let registries = registries();
+    let unknown = pack("unknown", r#"{"values": ["minecraft:dirt"]}"#);
+    assert!(matches!(
+        Tags::load(&[unknown], &registries),
+        Err(RegistryError::Invalid(_))
+    ));
+    let cycle = pack("cycle", r##"{"values": ["#minecraft:cycle"]}"##);
+    assert!(matches!(
+        Tags::load(&[cycle], &registries),
+        Err(RegistryError::Invalid(_))
+    ));
+    let broken = pack("broken", r#"{"values": "minecraft:stone"}"#);
+    assert!(matches!(
+        Tags::load(&[broken], &registries),
+        Err(RegistryError::Tag(..))
+    ));
+    assert!(matches!(
+        Tags::load(&[data("missing")], &registries),
+        Err(RegistryError::Io(..))
+    ));
+}
+
+#[test]
+fn tags_packet() {
+    let tags = Tags::load(&[data("pack")], &registries()).unwrap();
+    let packet = tags.packet();
+    let registries = packet
+        .0
+        .iter()
+        .map(|(name, _)| &*name.0)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        registries,
+        vec![
+            "minecraft:block",
+            "minecraft:item",
+            "minecraft:fluid",
+            "minecraft:entity_type",
+            "minecraft:game_event"
+        ]
+    );
+    let blocks = &packet.0[0].1;
+    assert_eq!(&*blocks[0].name.0, "minecraft:logs");
+    assert_eq!(*blocks[0].entries[0], 2);
+    common::round_trip(packet);
+}
@@ -0,0 +1,201 @@
+use protocol::packets::play::dim::{DimensionCodec, DimensionType};
+use protocol::world::*;
+
+fn overworld() -> DimensionType {
+    DimensionCodec::default()
+        .dimension_types
+        .get("minecraft:overworld")
+        .unwrap()
+        .element
+        .clone()
+}
+
+/// The overworld with the height of the 1.18 caves update
+fn deep() -> DimensionType {
+    DimensionType {
+        min_y: -64,
+        height: 384,
+        ..overworld()
+    }
+}
+
+/// Vanilla 1.17.1 has 20342 block states and biome ids up to 175
+fn palettes() -> Palettes {
+    Palettes::new(20342, 176)
+}
+
+#[test]
+fn bit_array() {
+    let mut array = BitArray::new(5, 100);
+    // 12 values per long, the remaining 4 bits are unused
+    assert_eq!(array.longs().len(), 9);
+    array.set(11, 31);
+    array.set(12, 17);
+    array.set(99, 1);
+    assert_eq!(array.get(11), 31);
+    assert_eq!(array.get(12), 17);
+    assert_eq!(array.get(10), 0);
+    assert_eq!(array.longs()[0], 31 << 55);
+    assert_eq!(array.longs()[1], 17);
+    assert_eq!(array.iter().filter(|&value| value != 0).count(), 3);
+    array.set(11, 0);
+    assert_eq!(array.longs()[0], 0);
+}
+
+#[test]
+fn bits_needed() {
+    assert_eq!(bits_for(1), 1);
+    assert_eq!(bits_for(2), 1);
+    assert_eq!(bits_for(3), 2);
+    assert_eq!(bits_for(16), 4);
+    assert_eq!(bits_for(17), 5);
+    assert_eq!(
+        palettes(),
+        Palettes {
+            block_bits: 15,
+            biome_bits: 8
+        }
+    );
+}
+
+#[test]
+fn palette_growth() {
+    let mut container = PalettedContainer::new(Kind::BlockStates, 15, 0);
+    assert_eq!(container.set(0, 0), 0);
+    assert_eq!(*container.storage(), Storage::Single(0));
+
+    assert_eq!(container.set(5, 1), 0);
+    match container.storage() {
+        Storage::Indirect { palette, data } => {
+            assert_eq!(palette, &[0, 1]);
+            assert_eq!(data.bits(), 4);
+        }
+        other => panic!("unexpected storage {:?}", other),
+    }
+
+    for state in 2..=16 {
+        container.set(state as usize, state);
+    }
+    match container.storage() {
+        Storage::Indirect { palette, data } => {
+            assert_eq!(palette.len(), 17);
+            assert_eq!(data.bits(), 5);
+        }
+        other => panic!("unexpected storage {:?}", other),
+    }
+
+    for state in 17..=256 {
+        container.set(state as usize, state * 10);
+    }
+    match container.storage() {
+        Storage::Direct(data) => assert_eq!(data.bits(), 15),
+        other => panic!("unexpected storage {:?}", other),
+    }
+    assert_eq!(container.get(5), 5);
+    assert_eq!(container.get(1), 0);
+    assert_eq!(container.get(256), 2560);
+    assert_eq!(container.get(4095), 0);
+    assert_eq!(container.count(|state| state != 0), 255);
+}
+
+#[test]
+fn compact() {
+    let mut container = PalettedContainer::new(Kind::BlockStates, 15, 0);
+    for index in 0..300 {
+        container.set(index, index as i32 + 1);
+    }
+    assert!(matches!(container.storage(), Storage::Direct(_)));
+    for index in 0..300 {
+        container.set(index, 7);
+    }
+    container.compact();
+    match container.storage() {
+        Storage::Indirect { palette, data } => {
+            assert_eq!(palette, &[7, 0]);
+            assert_eq!(data.bits(), 4);
+        }
+        other => panic!("unexpected storage {:?}", other),
+    }
+    assert_eq!(container.get(299), 7);
+    assert_eq!(container.get(300), 0);
+
+    container.fill(3);
+    container.set(10, 3);
+    container.compact();
+    assert_eq!(*container.storage(), Storage::Single(3));
+}
+
+#[test]
+fn biome_palette() {
+    let mut container = PalettedContainer::new(Kind::Biomes, 8, 1);
+    for index in 0..7 {
+        container.set(index, 100 + index as i32);
+    }
+    match container.storage() {
+        Storage::Indirect { palette, data } => {
+            assert_eq!(palette.len(), 8);
+            assert_eq!(data.bits(), 3);
+        }
+        other => panic!("unexpected storage {:?}", other),
+    }
+    container.set(63, 175);
+    assert!(matches!(container.storage(), Storage::Direct(_)));
+    assert_eq!(container.get(63), 175);
+    assert_eq!(container.get(6), 106);
+    assert_eq!(container.get(7), 1);
+}
+
+#[test]
+fn chunk_height() {
+    let chunk = Chunk::new(&overworld(), palettes(), 1);
+    assert_eq!(chunk.min_y(), 0);
+    assert_eq!(chunk.height(), 256);
+    assert_eq!(chunk.sections().len(), 16);
+    assert!(chunk.section(-1).is_none());
+    assert!(chunk.section(255).is_some());
+}
+
+#[test]
+fn chunk_blocks() {
+    let mut chunk = Chunk::new(&deep(), palettes(), 1);
+    assert_eq!(chunk.min_y(), -64);
+    assert_eq!(chunk.height(), 384);
+    assert_eq!(chunk.sections().len(), 24);
+    assert!(chunk.sections().iter().all(Section::is_empty));
+
+    assert_eq!(chunk.set_block(3, -64, 15, 1), Some(AIR));
+    assert_eq!(chunk.set_block(3, -1, 4, 2), Some(AIR));
+    assert_eq!(chunk.set_block(0, 319, 0, 3), Some(AIR));
+    assert_eq!(chunk.set_block(0, 320, 0, 3), None);
+    assert_eq!(chunk.set_block(0, -65, 0, 3), None);
+
+    assert_eq!(chunk.block(3, -64, 15), 1);
+    assert_eq!(chunk.block(3, -1, 4), 2);
+    assert_eq!(chunk.block(0, 319, 0), 3);
+    assert_eq!(chunk.block(0, 320, 0), AIR);
+    assert_eq!(chunk.block(3, -63, 15), AIR);
+    assert_eq!(chunk.section(-1).unwrap().block(3, 15, 4), 2);
+    assert!(!chunk.sections()[0].is_empty());
+    assert!(!chunk.sections()[3].is_empty());
+    assert!(chunk.sections()[4].is_empty());
+
+    assert_eq!(chunk.set_block(3, -1, 4, AIR), Some(2));
+    chunk.compact();
+    assert!(chunk.sections()[3].is_empty());
+}
+
+#[test]
+fn chunk_biomes() {
+    let mut chunk = Chunk::new(&deep(), palettes(), 1);
+    assert_eq!(chunk.biome(7, 100, 7), 1);
+    assert_eq!(chunk.set_biome(5, 6, 7, 4), Some(1));
+    // the whole 4x4x4 cell changes
+    assert_eq!(chunk.biome(4, 4, 4), 4);
+    assert_eq!(chunk.biome(7, 7, 7), 4);
+    assert_eq!(chunk.biome(8, 7, 7), 1);
+    assert_eq!(chunk.biome(7, 8, 7), 1);
+
+    chunk.set_biome(0, 319, 0, 7);
+    assert_eq!(chunk.biome(0, 1000, 0), 7);
+    assert_eq!(chunk.set_biome(0, 1000, 0, 7), None);
+}
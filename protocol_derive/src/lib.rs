@@ -2,6 +2,7 @@
 
 extern crate proc_macro;
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 
 use quote::{format_ident, quote};
 use syn::{parse_macro_input, AttributeArgs, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
@@ -35,23 +36,20 @@ pub fn json_derive(input: TokenStream) -> TokenStream {
     .into()
 }
 
+#[proc_macro_derive(Transcodeable)]
+pub fn transcodeable_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match transcodeable(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into(),
+    }
+}
+
 #[proc_macro_attribute]
 pub fn packet(args: TokenStream, input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let args = parse_macro_input!(args as AttributeArgs);
 
-    let data = if let Data::Struct(data) = &input.data {
-        data
-    } else {
-        error!("Deriving Packet is only allowed on structs");
-    };
-
-    let punct = match &data.fields {
-        Fields::Named(named) => Some(&named.named),
-        Fields::Unnamed(unnamed) => Some(&unnamed.unnamed),
-        Fields::Unit => None,
-    };
-
     let mut pid = None;
 
     for meta in args.as_slice() {
@@ -79,6 +77,42 @@ pub fn packet(args: TokenStream, input: TokenStream) -> TokenStream {
         None => error!("packet id not defined"),
     };
 
+    let transcodeable = match transcodeable(&input) {
+        Ok(tokens) => tokens,
+        Err(err) => return err.into(),
+    };
+    let struct_name = &input.ident;
+
+    (quote! {
+        #input
+
+        impl crate::codec::Packet for #struct_name {
+            fn id() -> i32 {
+                #pid
+            }
+        }
+
+        #transcodeable
+    })
+    .into()
+}
+
+/// Encodes and decodes all fields in declaration order
+fn transcodeable(input: &DeriveInput) -> Result<TokenStream2, TokenStream2> {
+    let data = if let Data::Struct(data) = &input.data {
+        data
+    } else {
+        return Err(quote! {
+            compile_error!("Deriving Transcodeable is only allowed on structs");
+        });
+    };
+
+    let punct = match &data.fields {
+        Fields::Named(named) => Some(&named.named),
+        Fields::Unnamed(unnamed) => Some(&unnamed.unnamed),
+        Fields::Unit => None,
+    };
+
     let struct_name = &input.ident;
 
     let mut encode = Vec::new();
@@ -87,11 +121,15 @@ pub fn packet(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut size = Vec::new();
 
     if let Some(punct) = punct {
-        for (id, field) in (0u32..).zip(punct.clone()) {
+        for (id, field) in punct.iter().enumerate() {
             let name = field
                 .ident
+                .as_ref()
                 .map(|ident| quote!(#ident))
-                .unwrap_or_else(|| quote!(#id));
+                .unwrap_or_else(|| {
+                    let id = syn::Index::from(id);
+                    quote!(#id)
+                });
             let local_name = format_ident!("_{}", name.to_string());
 
             encode.push(quote! {
@@ -109,15 +147,7 @@ pub fn packet(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     }
 
-    (quote! {
-        #input
-
-        impl crate::codec::Packet for #struct_name {
-            fn id() -> i32 {
-                #pid
-            }
-        }
-
+    Ok(quote! {
         impl crate::codec::Transcodeable for #struct_name  {
             fn encode<B: bytes::BufMut>(&self, mut buf: B) -> std::result::Result<(), crate::codec::EncodeError> {
                 use crate::codec::Transcodeable;
@@ -138,5 +168,4 @@ pub fn packet(args: TokenStream, input: TokenStream) -> TokenStream {
             }
         }
     })
-    .into()
 }